@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::map::{Map, RotatingBlock};
+
+// Everything drawn for the current map, replaced when another one is picked
+#[derive(Component)]
+pub struct ArenaEntity {}
+
+#[derive(Component)]
+pub struct RotatingObstacle {
+    pub block: RotatingBlock,
+}
+
+// The map of the match, the classic field until the server says otherwise
+#[derive(Resource, Default)]
+pub struct CurrentMap(pub Map);
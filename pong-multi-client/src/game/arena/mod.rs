@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+use component::CurrentMap;
+use system::{build_arena, rotate_obstacles};
+
+use crate::AppState;
+
+pub mod component;
+pub mod system;
+
+// Draws the map the server picked for the match, the server does the collisions
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        // The map can come in the same frame as match_found, before InGame
+        app.init_resource::<CurrentMap>()
+            .add_systems(Update, build_arena)
+            .add_systems(Update, rotate_obstacles.run_if(in_state(AppState::InGame)));
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::{
+    map::{Map, PORTAL_RADIUS, ROTATING_BLOCK_WIDTH},
+    FIELD_HEIGHT, FIELD_WIDTH,
+};
+
+use crate::{game::interpolation::resource::SnapshotBuffer, network::resource::ServerMessage};
+
+use super::component::{ArenaEntity, CurrentMap, RotatingObstacle};
+
+// Thickness of the back walls drawn around a smaller goal
+const BACK_WALL_WIDTH: f32 = 16.0;
+
+// Every match starts on the classic field, map_picked replaces it
+pub fn build_arena(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut message_reader: EventReader<ServerMessage>,
+    mut current_map: ResMut<CurrentMap>,
+    arena_query: Query<Entity, With<ArenaEntity>>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        let map = match json["action"].as_str() {
+            Some("match_found") => Map::classic(),
+            Some("map_picked") => match Map::from_json(&json["map"]) {
+                Ok(map) => map,
+                Err(e) => {
+                    println!("Invalid map received from the server: {e}");
+                    continue;
+                }
+            },
+            _ => continue,
+        };
+
+        for entity in arena_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        if map != Map::classic() {
+            println!("Playing on {}", map.name);
+            spawn_map(&mut commands, &asset_server, &map);
+        }
+        current_map.0 = map;
+    }
+}
+
+fn spawn_map(commands: &mut Commands, asset_server: &AssetServer, map: &Map) {
+    let sprite = |path: &str, width: f32, height: f32| Sprite {
+        image: asset_server.load(format!("sprites/{path}.png")),
+        custom_size: Some(Vec2::new(width, height)),
+        ..default()
+    };
+
+    commands.spawn((
+        sprite(&map.background, FIELD_WIDTH, FIELD_HEIGHT),
+        Transform::from_xyz(0.0, 0.0, -10.0),
+        ArenaEntity {},
+    ));
+
+    for block in &map.blocks {
+        commands.spawn((
+            sprite("block_square", block.width, block.height),
+            Transform::from_xyz(block.x, block.y, 0.0),
+            ArenaEntity {},
+        ));
+    }
+
+    for block in &map.rotating_blocks {
+        commands.spawn((
+            sprite("block_rotate_large", block.length, ROTATING_BLOCK_WIDTH),
+            Transform::from_xyz(block.x, block.y, 0.0),
+            RotatingObstacle { block: *block },
+            ArenaEntity {},
+        ));
+    }
+
+    let hole_size = PORTAL_RADIUS * 2.0;
+    for portal in &map.portals {
+        commands.spawn((
+            sprite("hole_start", hole_size, hole_size),
+            Transform::from_xyz(portal.from.x, portal.from.y, -1.0),
+            ArenaEntity {},
+        ));
+        commands.spawn((
+            sprite("hole_large_end", hole_size, hole_size),
+            Transform::from_xyz(portal.to.x, portal.to.y, -1.0),
+            ArenaEntity {},
+        ));
+    }
+
+    // The back walls cover what is left of each side above and below the goal
+    let wall_height = (FIELD_HEIGHT - map.goal_size) / 2.0;
+    if wall_height > 0.0 {
+        let wall_y = map.goal_size / 2.0 + wall_height / 2.0;
+        let wall_x = FIELD_WIDTH / 2.0 - BACK_WALL_WIDTH / 2.0;
+
+        for (x, y) in [
+            (wall_x, wall_y),
+            (wall_x, -wall_y),
+            (-wall_x, wall_y),
+            (-wall_x, -wall_y),
+        ] {
+            commands.spawn((
+                sprite("block_narrow", BACK_WALL_WIDTH, wall_height),
+                Transform::from_xyz(x, y, 0.0),
+                ArenaEntity {},
+            ));
+        }
+    }
+}
+
+// Rotating blocks follow the tick being rendered, like the ball
+pub fn rotate_obstacles(
+    buffer: Res<SnapshotBuffer>,
+    mut query: Query<(&RotatingObstacle, &mut Transform)>,
+) {
+    let Some(render_tick) = buffer.render_tick else {
+        return;
+    };
+
+    for (obstacle, mut transform) in query.iter_mut() {
+        transform.rotation = Quat::from_rotation_z(obstacle.block.angle_at(render_tick));
+    }
+}
@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+use system::spawn_ball;
+
+use crate::AppState;
+
+pub mod component;
+pub mod system;
+
+pub struct BallPlugin;
+
+impl Plugin for BallPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), spawn_ball);
+    }
+}
@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+
+use super::component::Ball;
+
+pub fn spawn_ball(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Sprite {
+            image: asset_server.load("sprites/ball_blue_small.png"),
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, 0.0),
+        Ball {},
+        StateScoped(AppState::InGame),
+    ));
+}
@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+use resource::{InterpolationSettings, SnapshotBuffer};
+use system::{advance_render_clock, buffer_snapshots, interpolate_entities, reset_snapshots};
+
+use crate::{user_interface::pause::Paused, AppState};
+
+pub mod resource;
+pub mod system;
+
+// Renders the remote entities (other paddles and ball) slightly in the past,
+// between two snapshots, so they move smoothly at any network rate
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InterpolationSettings>()
+            .init_resource::<SnapshotBuffer>()
+            .add_systems(OnEnter(AppState::InGame), reset_snapshots)
+            .add_systems(
+                Update,
+                (
+                    buffer_snapshots,
+                    // Stays on the last picture until the match goes on
+                    advance_render_clock.run_if(not(resource_exists::<Paused>)),
+                    interpolate_entities,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
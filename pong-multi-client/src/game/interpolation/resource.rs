@@ -0,0 +1,266 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+use pong_multi_shared::game::{
+    ball::Ball,
+    formation::Formation,
+    paddle::PADDLE_HEIGHT,
+    power_up::{Lock, PowerUp, PowerUpKind},
+    state::GameState,
+    Side, FIELD_HEIGHT, TICK_RATE,
+};
+use serde_json::Value;
+
+// Snapshots older than this are dropped from the buffer
+const MAX_BUFFERED_SNAPSHOTS: usize = 32;
+
+// Two per team in doubles, singles only use the first two
+pub const MAX_PADDLES: usize = 4;
+
+#[derive(Resource)]
+pub struct InterpolationSettings {
+    // How far in the past remote entities are rendered
+    pub delay: Duration,
+
+    // How long to keep extrapolating when snapshots stop arriving
+    pub max_extrapolation: Duration,
+}
+
+impl Default for InterpolationSettings {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(100),
+            max_extrapolation: Duration::from_millis(250),
+        }
+    }
+}
+
+impl InterpolationSettings {
+    pub fn delay_ticks(&self) -> f64 {
+        self.delay.as_secs_f64() * TICK_RATE as f64
+    }
+
+    pub fn max_extrapolation_ticks(&self) -> f64 {
+        self.max_extrapolation.as_secs_f64() * TICK_RATE as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub ball: Ball,
+
+    // Indexed like the seats of the formation
+    pub paddles: [f32; MAX_PADDLES],
+    pub score: [u32; 2],
+
+    // Power-ups, only there while they are going on
+    pub extra_ball: Option<Ball>,
+    pub power_up: Option<PowerUp>,
+    pub stunned: [bool; 2],
+    pub lock: Option<Lock>,
+}
+
+impl Snapshot {
+    pub fn from_json(json: &Value, formation: &Formation) -> Option<Self> {
+        let score = &json["score"];
+
+        let mut paddles = [0.0; MAX_PADDLES];
+        for seat in formation.seats() {
+            let y = json["paddles"][seat.key()].as_f64()?;
+            *paddles.get_mut(formation.index(seat))? = y as f32;
+        }
+
+        Some(Self {
+            tick: json["tick"].as_u64()?,
+            ball: ball_from_json(&json["ball"])?,
+            paddles,
+            score: [
+                score[Side::Left.as_str()].as_u64()? as u32,
+                score[Side::Right.as_str()].as_u64()? as u32,
+            ],
+            extra_ball: ball_from_json(&json["extra_ball"]),
+            power_up: power_up_from_json(&json["power_up"]),
+            stunned: [Side::Left, Side::Right].map(|side| {
+                json["stunned"][side.as_str()]
+                    .as_u64()
+                    .is_some_and(|ticks| ticks > 0)
+            }),
+            lock: json["lock"]["open"]
+                .as_u64()
+                .map(|open_ticks| Lock { open_ticks }),
+        })
+    }
+
+    // The same picture taken from a match simulated here
+    pub fn from_state(state: &GameState) -> Self {
+        let mut paddles = [0.0; MAX_PADDLES];
+        for (paddle, y) in state.paddles.iter().zip(paddles.iter_mut()) {
+            *y = paddle.y;
+        }
+
+        Self {
+            tick: state.tick,
+            ball: state.ball,
+            paddles,
+            score: state.score,
+            extra_ball: state.extra_ball.map(|extra_ball| extra_ball.ball),
+            power_up: state.power_up,
+            stunned: [Side::Left, Side::Right].map(|side| state.is_stunned(side)),
+            lock: state.lock,
+        }
+    }
+}
+
+fn ball_from_json(ball: &Value) -> Option<Ball> {
+    Some(Ball {
+        x: ball["x"].as_f64()? as f32,
+        y: ball["y"].as_f64()? as f32,
+        vx: ball["vx"].as_f64()? as f32,
+        vy: ball["vy"].as_f64()? as f32,
+    })
+}
+
+fn power_up_from_json(power_up: &Value) -> Option<PowerUp> {
+    Some(PowerUp {
+        kind: PowerUpKind::from_id(power_up["kind"].as_u64()?)?,
+        x: power_up["x"].as_f64()? as f32,
+        y: power_up["y"].as_f64()? as f32,
+    })
+}
+
+#[derive(Resource, Default)]
+pub struct SnapshotBuffer {
+    // Ordered by tick, oldest first
+    pub snapshots: VecDeque<Snapshot>,
+
+    // Server tick currently rendered, None until the first snapshot arrives
+    pub render_tick: Option<f64>,
+}
+
+impl SnapshotBuffer {
+    pub fn latest_tick(&self) -> Option<u64> {
+        self.snapshots.back().map(|snapshot| snapshot.tick)
+    }
+
+    // Insert a snapshot in tick order, ignoring duplicates and ones already rendered past
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self
+            .render_tick
+            .is_some_and(|render_tick| (snapshot.tick as f64) < render_tick.floor())
+        {
+            return;
+        }
+
+        let index = self
+            .snapshots
+            .partition_point(|buffered| buffered.tick < snapshot.tick);
+        if self
+            .snapshots
+            .get(index)
+            .is_some_and(|buffered| buffered.tick == snapshot.tick)
+        {
+            return;
+        }
+        self.snapshots.insert(index, snapshot);
+
+        while self.snapshots.len() > MAX_BUFFERED_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+    }
+
+    // Drop everything except the last snapshot at or before the given tick
+    pub fn discard_before(&mut self, tick: f64) {
+        while self
+            .snapshots
+            .get(1)
+            .is_some_and(|next| next.tick as f64 <= tick)
+        {
+            self.snapshots.pop_front();
+        }
+    }
+
+    // State of the remote entities at a (fractional) server tick
+    pub fn sample(&self, tick: f64, max_extrapolation_ticks: f64) -> Option<Snapshot> {
+        let first = self.snapshots.front()?;
+        if tick <= first.tick as f64 {
+            return Some(*first);
+        }
+
+        let next_index = self
+            .snapshots
+            .partition_point(|snapshot| snapshot.tick as f64 <= tick);
+
+        match (
+            self.snapshots.get(next_index - 1),
+            self.snapshots.get(next_index),
+        ) {
+            (Some(from), Some(to)) => Some(interpolate(from, to, tick)),
+            (Some(last), None) => Some(self.extrapolate(last, tick, max_extrapolation_ticks)),
+            _ => None,
+        }
+    }
+
+    // No newer snapshot yet: keep the ball on its course and the paddles on theirs
+    fn extrapolate(&self, last: &Snapshot, tick: f64, max_extrapolation_ticks: f64) -> Snapshot {
+        let elapsed = (tick - last.tick as f64).min(max_extrapolation_ticks);
+        let dt = (elapsed / TICK_RATE as f64) as f32;
+
+        let mut extrapolated = *last;
+        extrapolated.ball.advance(dt);
+        if let Some(extra_ball) = &mut extrapolated.extra_ball {
+            extra_ball.advance(dt);
+        }
+
+        if let Some(previous) = self.snapshots.iter().rev().nth(1) {
+            let ticks = (last.tick - previous.tick) as f32;
+            let limit = FIELD_HEIGHT / 2.0 - PADDLE_HEIGHT / 2.0;
+
+            for (index, paddle) in extrapolated.paddles.iter_mut().enumerate() {
+                let velocity = (last.paddles[index] - previous.paddles[index]) / ticks;
+                *paddle = (*paddle + velocity * elapsed as f32).clamp(-limit, limit);
+            }
+        }
+
+        extrapolated
+    }
+}
+
+fn interpolate(from: &Snapshot, to: &Snapshot, tick: f64) -> Snapshot {
+    // A point was scored in between, the ball was reset so don't slide it across the field
+    if from.score != to.score {
+        return if tick - (from.tick as f64) < (to.tick as f64) - tick {
+            *from
+        } else {
+            *to
+        };
+    }
+
+    let t = ((tick - from.tick as f64) / (to.tick - from.tick) as f64) as f32;
+
+    Snapshot {
+        tick: tick.floor() as u64,
+        ball: Ball {
+            x: from.ball.x.lerp(to.ball.x, t),
+            y: from.ball.y.lerp(to.ball.y, t),
+            vx: to.ball.vx,
+            vy: to.ball.vy,
+        },
+        paddles: std::array::from_fn(|index| from.paddles[index].lerp(to.paddles[index], t)),
+        score: to.score,
+
+        // A new extra ball appears where it is, no sliding in from nowhere
+        extra_ball: match (from.extra_ball, to.extra_ball) {
+            (Some(from), Some(to)) => Some(Ball {
+                x: from.x.lerp(to.x, t),
+                y: from.y.lerp(to.y, t),
+                vx: to.vx,
+                vy: to.vy,
+            }),
+            (_, to) => to,
+        },
+        power_up: to.power_up,
+        stunned: to.stunned,
+        lock: to.lock,
+    }
+}
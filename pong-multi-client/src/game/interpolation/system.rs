@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::TICK_RATE;
+
+use crate::{
+    game::{ball::component::Ball, player::component::RemotePaddle},
+    network::resource::{MatchInfo, ServerMessage},
+};
+
+use super::resource::{InterpolationSettings, Snapshot, SnapshotBuffer};
+
+// Jump straight to the target render time when drifting further than this
+const RESYNC_TICKS: f64 = 30.0;
+
+// Fraction of the drift corrected per second
+const CATCH_UP_RATE: f64 = 2.0;
+
+pub fn reset_snapshots(mut buffer: ResMut<SnapshotBuffer>) {
+    *buffer = SnapshotBuffer::default();
+}
+
+pub fn buffer_snapshots(
+    mut message_reader: EventReader<ServerMessage>,
+    match_info: Res<MatchInfo>,
+    mut buffer: ResMut<SnapshotBuffer>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("snapshot") {
+            continue;
+        }
+
+        if let Some(snapshot) = Snapshot::from_json(json, &match_info.formation) {
+            buffer.push(snapshot);
+        }
+    }
+}
+
+// Move the render time forward at the tick rate, slowly pulling it towards
+// `latest snapshot - delay` so jitter doesn't make it jump around
+pub fn advance_render_clock(
+    time: Res<Time>,
+    settings: Res<InterpolationSettings>,
+    mut buffer: ResMut<SnapshotBuffer>,
+) {
+    let Some(latest_tick) = buffer.latest_tick() else {
+        return;
+    };
+
+    let target = latest_tick as f64 - settings.delay_ticks();
+    let dt = time.delta_secs_f64();
+
+    let render_tick = match buffer.render_tick {
+        None => target,
+        Some(render_tick) => {
+            let render_tick = render_tick + dt * TICK_RATE as f64;
+            let drift = target - render_tick;
+
+            if drift.abs() > RESYNC_TICKS {
+                target
+            } else {
+                render_tick + drift * (CATCH_UP_RATE * dt).min(1.0)
+            }
+        }
+    };
+
+    buffer.render_tick = Some(render_tick);
+    buffer.discard_before(render_tick);
+}
+
+pub fn interpolate_entities(
+    settings: Res<InterpolationSettings>,
+    buffer: Res<SnapshotBuffer>,
+    match_info: Res<MatchInfo>,
+    mut ball_query: Query<&mut Transform, (With<Ball>, Without<RemotePaddle>)>,
+    mut paddle_query: Query<(&mut Transform, &RemotePaddle), Without<Ball>>,
+) {
+    let Some(render_tick) = buffer.render_tick else {
+        return;
+    };
+
+    let Some(sample) = buffer.sample(render_tick, settings.max_extrapolation_ticks()) else {
+        return;
+    };
+
+    for mut transform in ball_query.iter_mut() {
+        transform.translation.x = sample.ball.x;
+        transform.translation.y = sample.ball.y;
+    }
+
+    for (mut transform, paddle) in paddle_query.iter_mut() {
+        transform.translation.y = sample.paddles[match_info.formation.index(paddle.seat)];
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+use resource::LocalMatch;
+use system::{restart_local_match, step_local_match, toggle_pause};
+
+use crate::{
+    user_interface::{pause::Paused, results::MatchResult},
+    AppState,
+};
+
+pub mod resource;
+pub mod system;
+
+// Matches simulated on this machine, without a server. The simulation fills the
+// snapshot buffer like the server would, so everything else draws it the same way.
+pub struct LocalPlugin;
+
+impl Plugin for LocalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            step_local_match.run_if(
+                in_state(AppState::InGame)
+                    .and(resource_exists::<LocalMatch>)
+                    .and(not(resource_exists::<Paused>))
+                    .and(not(resource_exists::<MatchResult>)),
+            ),
+        )
+        .add_systems(
+            Update,
+            (toggle_pause, restart_local_match)
+                .run_if(in_state(AppState::InGame).and(resource_exists::<LocalMatch>)),
+        );
+    }
+}
+
+// Systems talking to the server stay off during local matches
+pub fn playing_online(local: Option<Res<LocalMatch>>) -> bool {
+    local.is_none()
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::{
+    ai::{AiController, Difficulty},
+    formation::{Formation, Seat},
+    state::GameState,
+    Side,
+};
+
+// First to this many points wins a local match
+pub const POINTS_TO_WIN: u32 = 5;
+
+// Who moves a paddle. People at the keyboard can also use a gamepad, the nth
+// one connected.
+pub enum Controller {
+    Human {
+        up: Vec<KeyCode>,
+        down: Vec<KeyCode>,
+        gamepad: usize,
+    },
+    Ai(AiController),
+}
+
+impl Controller {
+    // W/S or the arrows, like online
+    pub fn keyboard() -> Self {
+        Controller::Human {
+            up: vec![KeyCode::KeyW, KeyCode::ArrowUp],
+            down: vec![KeyCode::KeyS, KeyCode::ArrowDown],
+            gamepad: 0,
+        }
+    }
+
+    // Two people on one keyboard, W/S on the left and the arrows on the right
+    pub fn shared_keyboard(side: Side) -> Self {
+        let (up, down, gamepad) = match side {
+            Side::Left => (KeyCode::KeyW, KeyCode::KeyS, 0),
+            Side::Right => (KeyCode::ArrowUp, KeyCode::ArrowDown, 1),
+        };
+
+        Controller::Human {
+            up: vec![up],
+            down: vec![down],
+            gamepad,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct LocalMatch {
+    pub state: GameState,
+    pub controllers: Vec<(Seat, Controller)>,
+}
+
+impl LocalMatch {
+    // The player on the first seat, the AI everywhere else
+    pub fn against_ai(formation: Formation, difficulty: Difficulty) -> Self {
+        let controllers = formation
+            .seats()
+            .map(|seat| {
+                let controller = if seat == Seat::new(Side::Left, 0) {
+                    Controller::keyboard()
+                } else {
+                    Controller::Ai(AiController::new(difficulty))
+                };
+                (seat, controller)
+            })
+            .collect();
+
+        Self {
+            state: GameState::with_formation(formation),
+            controllers,
+        }
+    }
+
+    // One person per team on the front seats, the AI plays the teammates in doubles
+    pub fn hot_seat(formation: Formation) -> Self {
+        let controllers = formation
+            .seats()
+            .map(|seat| {
+                let controller = if seat.slot == 0 {
+                    Controller::shared_keyboard(seat.side)
+                } else {
+                    Controller::Ai(AiController::new(Difficulty::default()))
+                };
+                (seat, controller)
+            })
+            .collect();
+
+        Self {
+            state: GameState::with_formation(formation),
+            controllers,
+        }
+    }
+
+    // Seats played by someone at this machine
+    pub fn human_seats(&self) -> Vec<Seat> {
+        self.controllers
+            .iter()
+            .filter(|(_, controller)| matches!(controller, Controller::Human { .. }))
+            .map(|(seat, _)| *seat)
+            .collect()
+    }
+
+    // Both teams have someone at the keyboard
+    pub fn is_hot_seat(&self) -> bool {
+        let seats = self.human_seats();
+        [Side::Left, Side::Right]
+            .iter()
+            .all(|side| seats.iter().any(|seat| seat.side == *side))
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        [Side::Left, Side::Right]
+            .into_iter()
+            .find(|side| self.state.score[side.index()] >= POINTS_TO_WIN)
+    }
+
+    // Same players, back to 0 - 0
+    pub fn restart(&mut self) {
+        self.state = GameState::with_formation(self.state.formation.clone());
+    }
+}
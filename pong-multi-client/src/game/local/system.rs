@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        interpolation::resource::{Snapshot, SnapshotBuffer},
+        player::component::{Player, Prediction},
+    },
+    user_interface::{pause::Paused, results::MatchResult},
+};
+
+use super::resource::{Controller, LocalMatch};
+
+// How far the stick has to be pushed to move the paddle
+const STICK_DEAD_ZONE: f32 = 0.5;
+
+// One tick of the match, the same steps the server room goes through.
+// Stopped while paused or once someone won.
+pub fn step_local_match(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepad_query: Query<(Entity, &Gamepad)>,
+    mut local: ResMut<LocalMatch>,
+    mut buffer: ResMut<SnapshotBuffer>,
+    mut player_query: Query<(&Player, &mut Prediction)>,
+) {
+    let mut rng = rand::rng();
+
+    // In the order they were connected
+    let mut gamepads: Vec<_> = gamepad_query.iter().collect();
+    gamepads.sort_by_key(|(entity, _)| *entity);
+    let LocalMatch { state, controllers } = &mut *local;
+
+    for (seat, controller) in controllers.iter_mut() {
+        let direction = match controller {
+            Controller::Human { up, down, gamepad } => {
+                let direction = keyboard.any_pressed(up.iter().copied()) as i8
+                    - keyboard.any_pressed(down.iter().copied()) as i8;
+
+                match gamepads.get(*gamepad) {
+                    Some((_, gamepad)) if direction == 0 => gamepad_direction(gamepad),
+                    _ => direction,
+                }
+            }
+            Controller::Ai(ai) => ai.direction(state, *seat, &mut rng),
+        };
+
+        if !state.is_stunned(seat.side) {
+            state.paddle_mut(*seat).apply_input(direction);
+        }
+    }
+
+    if let Some(scorer) = state.step() {
+        println!(
+            "{} scored ({} - {})",
+            scorer.as_str(),
+            state.score[0],
+            state.score[1]
+        );
+    }
+
+    buffer.push(Snapshot::from_state(state));
+
+    // Nothing to predict, our paddles are where the simulation says
+    for (player, mut prediction) in player_query.iter_mut() {
+        prediction.y = state.paddle(player.seat).y;
+    }
+
+    if let Some(winner) = local.winner() {
+        commands.insert_resource(MatchResult {
+            winner,
+            score: local.state.score,
+            reason: None,
+            rematch: true,
+        });
+    }
+}
+
+fn gamepad_direction(gamepad: &Gamepad) -> i8 {
+    let stick = gamepad.left_stick().y;
+
+    if gamepad.pressed(GamepadButton::DPadUp) || stick > STICK_DEAD_ZONE {
+        1
+    } else if gamepad.pressed(GamepadButton::DPadDown) || stick < -STICK_DEAD_ZONE {
+        -1
+    } else {
+        0
+    }
+}
+
+// P or Escape, only while the match is on
+pub fn toggle_pause(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    paused: Option<Res<Paused>>,
+    result: Option<Res<MatchResult>>,
+) {
+    if result.is_some() || !keyboard.any_just_pressed([KeyCode::KeyP, KeyCode::Escape]) {
+        return;
+    }
+
+    if paused.is_some() {
+        commands.remove_resource::<Paused>();
+    } else {
+        commands.insert_resource(Paused {});
+    }
+}
+
+pub fn restart_local_match(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    result: Option<Res<MatchResult>>,
+    mut local: ResMut<LocalMatch>,
+    mut buffer: ResMut<SnapshotBuffer>,
+) {
+    if result.is_none() || !keyboard.just_pressed(KeyCode::Enter) {
+        return;
+    }
+
+    local.restart();
+    *buffer = SnapshotBuffer::default();
+    commands.remove_resource::<MatchResult>();
+}
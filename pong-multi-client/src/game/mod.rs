@@ -1,2 +1,7 @@
+pub mod arena;
+pub mod ball;
+pub mod interpolation;
+pub mod local;
 pub mod player;
+pub mod power_up;
 pub mod world;
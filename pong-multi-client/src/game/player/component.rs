@@ -1,4 +1,38 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
+use pong_multi_shared::game::formation::Seat;
+
+// A paddle controlled by this client, two of them when playing hot seat
+#[derive(Component)]
+pub struct Player {
+    pub seat: Seat,
+}
 
+// A paddle controlled by another player, teammate or opponent, driven by server snapshots
 #[derive(Component)]
-pub struct Player {}
+pub struct RemotePaddle {
+    pub seat: Seat,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PendingInput {
+    pub seq: u32,
+    pub direction: i8,
+}
+
+// Local paddle state predicted ahead of the server
+#[derive(Component, Default)]
+pub struct Prediction {
+    // Where the paddle is once every sent input is applied
+    pub y: f32,
+
+    // Visual offset left over from the last correction, decays towards zero
+    pub error: f32,
+
+    pub next_seq: u32,
+    pub last_tick: u64,
+
+    // Inputs sent to the server but not yet acknowledged by a snapshot
+    pub pending: VecDeque<PendingInput>,
+}
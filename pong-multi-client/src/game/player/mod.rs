@@ -1,5 +1,11 @@
-use bevy::app::*;
-use system::spawn_player;
+use bevy::prelude::*;
+use system::{reconcile_player, send_player_input, smooth_player, spawn_player};
+
+use crate::{
+    game::local::playing_online,
+    user_interface::{chat::components::ChatDraft, pause::Paused},
+    AppState,
+};
 
 pub mod component;
 pub mod system;
@@ -8,6 +14,21 @@ pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_player);
+        app.add_systems(OnEnter(AppState::InGame), spawn_player)
+            .add_systems(
+                FixedUpdate,
+                send_player_input.run_if(
+                    in_state(AppState::InGame)
+                        .and(playing_online)
+                        .and(not(resource_exists::<Paused>))
+                        .and(not(resource_exists::<ChatDraft>)),
+                ),
+            )
+            .add_systems(
+                Update,
+                (reconcile_player.run_if(playing_online), smooth_player)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
     }
 }
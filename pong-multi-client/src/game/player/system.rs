@@ -1,25 +1,199 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
+use pong_multi_shared::game::paddle::step_paddle;
+use serde_json::json;
 
-use super::component::Player;
+use crate::{
+    game::{interpolation::resource::SnapshotBuffer, local::resource::LocalMatch},
+    network::resource::{MatchInfo, ServerConnection, ServerMessage},
+    AppState,
+};
 
-const PADDLE_SIZE: f32 = 32.0;
+use super::component::{PendingInput, Player, Prediction, RemotePaddle};
+
+// Corrections bigger than this are snapped instead of smoothed
+const SNAP_DISTANCE: f32 = 64.0;
+
+// How fast the visual error decays, per second
+const CORRECTION_RATE: f32 = 10.0;
+
+// Stop buffering when the server stops acknowledging inputs
+const MAX_PENDING_INPUTS: usize = 256;
+
+// Tints telling the teams apart, our own paddle keeps the sprite colors
+const TEAMMATE_COLOR: Color = Color::srgb(0.6, 1.0, 0.6);
+const OPPONENT_COLOR: Color = Color::srgb(1.0, 0.6, 0.6);
 
 pub fn spawn_player(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    match_info: Res<MatchInfo>,
+    local: Option<Res<LocalMatch>>,
 ) {
-    if let Ok(window) = window_query.get_single() {
-        let window_width = window.width();
-        let window_height = window.height();
+    let formation = &match_info.formation;
+    let own_seat = match_info.seat;
+
+    // Online we only play our own seat, a local match can have more people at the keyboard
+    let player_seats = match local {
+        Some(local) => local.human_seats(),
+        None if match_info.spectating => Vec::new(),
+        None => vec![own_seat],
+    };
+
+    for seat in player_seats.iter().copied() {
+        commands.spawn((
+            Sprite {
+                image: asset_server.load("sprites/block_narrow.png"),
+                ..default()
+            },
+            Transform::from_xyz(formation.x(seat), 0.0, 0.0),
+            Player { seat },
+            Prediction::default(),
+            StateScoped(AppState::InGame),
+        ));
+    }
+
+    for seat in formation
+        .seats()
+        .filter(|seat| !player_seats.contains(seat))
+    {
+        let color = if seat.side == own_seat.side && !match_info.spectating {
+            TEAMMATE_COLOR
+        } else {
+            OPPONENT_COLOR
+        };
 
         commands.spawn((
             Sprite {
                 image: asset_server.load("sprites/block_narrow.png"),
+                color,
                 ..default()
             },
-            Transform::from_xyz(window_width / 2.0 - PADDLE_SIZE, 0.0, 0.0),
-            Player {},
+            Transform::from_xyz(formation.x(seat), 0.0, 0.0),
+            RemotePaddle { seat },
+            StateScoped(AppState::InGame),
         ));
     }
 }
+
+fn read_direction(keyboard: &ButtonInput<KeyCode>) -> i8 {
+    let mut direction = 0;
+
+    if keyboard.pressed(KeyCode::KeyW) || keyboard.pressed(KeyCode::ArrowUp) {
+        direction += 1;
+    }
+    if keyboard.pressed(KeyCode::KeyS) || keyboard.pressed(KeyCode::ArrowDown) {
+        direction -= 1;
+    }
+
+    direction
+}
+
+// Runs once per simulation tick: move the paddle right away and tell the server
+pub fn send_player_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    connection: Res<ServerConnection>,
+    match_info: Res<MatchInfo>,
+    snapshot_buffer: Res<SnapshotBuffer>,
+    mut player_query: Query<&mut Prediction, With<Player>>,
+) {
+    let Ok(mut prediction) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let direction = read_direction(&keyboard);
+    if direction == 0 {
+        return;
+    }
+
+    // Hit by a laser, the server won't move us either
+    let stunned = snapshot_buffer
+        .snapshots
+        .back()
+        .is_some_and(|snapshot| snapshot.stunned[match_info.seat.side.index()]);
+    if stunned {
+        return;
+    }
+
+    prediction.next_seq += 1;
+    let seq = prediction.next_seq;
+
+    prediction.y = step_paddle(prediction.y, direction);
+    prediction
+        .pending
+        .push_back(PendingInput { seq, direction });
+    if prediction.pending.len() > MAX_PENDING_INPUTS {
+        prediction.pending.pop_front();
+    }
+
+    // The tick we are showing the ball at, so the server can rewind hits to it
+    let mut message = json!({
+        "action": "move",
+        "room_id": match_info.room_id,
+        "seq": seq,
+        "direction": direction,
+    });
+    if let Some(render_tick) = snapshot_buffer.render_tick {
+        message["tick"] = json!(render_tick.max(0.0).floor() as u64);
+    }
+
+    connection.send(&message);
+}
+
+// Rewind to the server position and replay everything it hasn't seen yet
+pub fn reconcile_player(
+    mut message_reader: EventReader<ServerMessage>,
+    match_info: Res<MatchInfo>,
+    mut player_query: Query<&mut Prediction, With<Player>>,
+) {
+    let Ok(mut prediction) = player_query.get_single_mut() else {
+        return;
+    };
+
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("snapshot") {
+            continue;
+        }
+
+        let (Some(tick), Some(ack), Some(server_y)) = (
+            json["tick"].as_u64(),
+            json["ack"].as_u64(),
+            json["paddles"][match_info.seat.key()].as_f64(),
+        ) else {
+            continue;
+        };
+
+        // Snapshots can arrive out of order
+        if tick <= prediction.last_tick {
+            continue;
+        }
+        prediction.last_tick = tick;
+
+        let displayed_y = prediction.y + prediction.error;
+
+        prediction.pending.retain(|input| input.seq as u64 > ack);
+        let mut y = server_y as f32;
+        for input in prediction.pending.iter() {
+            y = step_paddle(y, input.direction);
+        }
+        prediction.y = y;
+
+        prediction.error = displayed_y - y;
+        if prediction.error.abs() > SNAP_DISTANCE {
+            prediction.error = 0.0;
+        }
+    }
+}
+
+pub fn smooth_player(
+    time: Res<Time>,
+    mut player_query: Query<(&mut Transform, &mut Prediction), With<Player>>,
+) {
+    for (mut transform, mut prediction) in player_query.iter_mut() {
+        prediction.error *= (-CORRECTION_RATE * time.delta_secs()).exp();
+        if prediction.error.abs() < 0.01 {
+            prediction.error = 0.0;
+        }
+
+        transform.translation.y = prediction.y + prediction.error;
+    }
+}
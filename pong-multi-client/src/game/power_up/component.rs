@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::Side;
+
+// The power-up waiting on the field, hidden while there is none
+#[derive(Component)]
+pub struct PowerUpPickup {}
+
+// The second ball given by a star
+#[derive(Component)]
+pub struct ExtraBall {}
+
+// One of the two blocks a key opens
+#[derive(Component)]
+pub struct LockedBlock {}
+
+// Laser over a paddle, shown while its team is stunned
+#[derive(Component)]
+pub struct StunMarker {
+    pub side: Side,
+}
+
+#[derive(Resource)]
+pub struct PowerUpSprites {
+    pub laser: Handle<Image>,
+    pub star: Handle<Image>,
+    pub key: Handle<Image>,
+    pub locked: Handle<Image>,
+    pub open: Handle<Image>,
+}
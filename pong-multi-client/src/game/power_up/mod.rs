@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+use system::{add_stun_markers, show_power_ups, spawn_power_ups};
+
+use crate::AppState;
+
+pub mod component;
+pub mod system;
+
+// Draws the power-ups the server puts in snapshots, the server decides everything
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), spawn_power_ups)
+            .add_systems(
+                Update,
+                (add_stun_markers, show_power_ups).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::{
+    paddle::{PADDLE_HEIGHT, PADDLE_WIDTH},
+    power_up::{PowerUpKind, LOCKED_BLOCK_HEIGHT, LOCKED_BLOCK_WIDTH, LOCKED_BLOCK_Y},
+};
+
+use crate::{
+    game::{
+        interpolation::resource::{InterpolationSettings, SnapshotBuffer},
+        player::component::{Player, RemotePaddle},
+    },
+    AppState,
+};
+
+use super::component::{ExtraBall, LockedBlock, PowerUpPickup, PowerUpSprites, StunMarker};
+
+pub fn spawn_power_ups(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let sprites = PowerUpSprites {
+        laser: asset_server.load("sprites/laser_shooter.png"),
+        star: asset_server.load("sprites/star.png"),
+        key: asset_server.load("sprites/key.png"),
+        locked: asset_server.load("sprites/block_locked_narrow.png"),
+        open: asset_server.load("sprites/block_narrow.png"),
+    };
+
+    commands.spawn((
+        Sprite::from_image(sprites.star.clone()),
+        Transform::from_xyz(0.0, 0.0, 1.0),
+        Visibility::Hidden,
+        PowerUpPickup {},
+        StateScoped(AppState::InGame),
+    ));
+
+    commands.spawn((
+        Sprite::from_image(asset_server.load("sprites/star_outline.png")),
+        Transform::from_xyz(0.0, 0.0, 0.0),
+        Visibility::Hidden,
+        ExtraBall {},
+        StateScoped(AppState::InGame),
+    ));
+
+    for y in [LOCKED_BLOCK_Y, -LOCKED_BLOCK_Y] {
+        commands.spawn((
+            Sprite {
+                image: sprites.locked.clone(),
+                custom_size: Some(Vec2::new(LOCKED_BLOCK_WIDTH, LOCKED_BLOCK_HEIGHT)),
+                ..default()
+            },
+            Transform::from_xyz(0.0, y, 0.0),
+            Visibility::Hidden,
+            LockedBlock {},
+            StateScoped(AppState::InGame),
+        ));
+    }
+
+    commands.insert_resource(sprites);
+}
+
+// Every paddle gets a laser over it, shown when its team gets stunned
+pub fn add_stun_markers(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    player_query: Query<(Entity, &Player), Added<Player>>,
+    remote_query: Query<(Entity, &RemotePaddle), Added<RemotePaddle>>,
+) {
+    let paddles = player_query
+        .iter()
+        .map(|(entity, player)| (entity, player.seat.side))
+        .chain(
+            remote_query
+                .iter()
+                .map(|(entity, paddle)| (entity, paddle.seat.side)),
+        );
+
+    for (entity, side) in paddles {
+        commands.entity(entity).with_child((
+            Sprite {
+                image: asset_server.load("sprites/laser.png"),
+                custom_size: Some(Vec2::new(PADDLE_WIDTH, PADDLE_HEIGHT)),
+                ..default()
+            },
+            Transform::from_xyz(0.0, 0.0, 1.0),
+            Visibility::Hidden,
+            StunMarker { side },
+        ));
+    }
+}
+
+fn shown(visible: bool) -> Visibility {
+    if visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn show_power_ups(
+    settings: Res<InterpolationSettings>,
+    buffer: Res<SnapshotBuffer>,
+    sprites: Res<PowerUpSprites>,
+    mut pickup_query: Query<
+        (&mut Transform, &mut Sprite, &mut Visibility),
+        (
+            With<PowerUpPickup>,
+            Without<ExtraBall>,
+            Without<LockedBlock>,
+        ),
+    >,
+    mut extra_ball_query: Query<
+        (&mut Transform, &mut Visibility),
+        (
+            With<ExtraBall>,
+            Without<PowerUpPickup>,
+            Without<LockedBlock>,
+        ),
+    >,
+    mut block_query: Query<
+        (&mut Sprite, &mut Visibility),
+        (
+            With<LockedBlock>,
+            Without<PowerUpPickup>,
+            Without<StunMarker>,
+        ),
+    >,
+    mut stun_query: Query<
+        (&StunMarker, &mut Visibility),
+        (
+            Without<PowerUpPickup>,
+            Without<ExtraBall>,
+            Without<LockedBlock>,
+        ),
+    >,
+) {
+    let Some(render_tick) = buffer.render_tick else {
+        return;
+    };
+
+    let Some(sample) = buffer.sample(render_tick, settings.max_extrapolation_ticks()) else {
+        return;
+    };
+
+    for (mut transform, mut sprite, mut visibility) in pickup_query.iter_mut() {
+        *visibility = shown(sample.power_up.is_some());
+
+        if let Some(power_up) = sample.power_up {
+            transform.translation.x = power_up.x;
+            transform.translation.y = power_up.y;
+            sprite.image = match power_up.kind {
+                PowerUpKind::Laser => sprites.laser.clone(),
+                PowerUpKind::Star => sprites.star.clone(),
+                PowerUpKind::Key => sprites.key.clone(),
+            };
+        }
+    }
+
+    for (mut transform, mut visibility) in extra_ball_query.iter_mut() {
+        *visibility = shown(sample.extra_ball.is_some());
+
+        if let Some(ball) = sample.extra_ball {
+            transform.translation.x = ball.x;
+            transform.translation.y = ball.y;
+        }
+    }
+
+    for (mut sprite, mut visibility) in block_query.iter_mut() {
+        *visibility = shown(sample.lock.is_some());
+
+        if let Some(lock) = sample.lock {
+            sprite.image = if lock.is_locked() {
+                sprites.locked.clone()
+            } else {
+                sprites.open.clone()
+            };
+        }
+    }
+
+    for (marker, mut visibility) in stun_query.iter_mut() {
+        *visibility = shown(sample.stunned[marker.side.index()]);
+    }
+}
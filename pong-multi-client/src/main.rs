@@ -5,15 +5,24 @@ use bevy::{
 };
 use bevy_dev_tools::fps_overlay::{FpsOverlayConfig, FpsOverlayPlugin};
 
-use game::{player::PlayerPlugin, world::WorldPlugin};
-use user_interface::welcome::WelcomePlugin;
+use game::{
+    arena::ArenaPlugin, ball::BallPlugin, interpolation::InterpolationPlugin, local::LocalPlugin,
+    player::PlayerPlugin, power_up::PowerUpPlugin, world::WorldPlugin,
+};
+use network::NetworkPlugin;
+use pong_multi_shared::game::TICK_RATE;
+use user_interface::{
+    chat::ChatPlugin, debug::NetworkDebugPlugin, hud::HudPlugin, party::PartyPlugin,
+    pause::PausePlugin, results::ResultsPlugin, rooms::RoomsPlugin, tournament::TournamentPlugin,
+    vote::VotePlugin, welcome::WelcomePlugin,
+};
 
 pub mod game;
+pub mod network;
 pub mod user_interface;
 
 struct OverlayColor;
 impl OverlayColor {
-    const RED: Color = Color::srgb(1.0, 0.0, 0.0);
     const GREEN: Color = Color::srgb(0.0, 1.0, 0.0);
 }
 
@@ -61,7 +70,6 @@ fn main() {
                         font_size: 20.0,
                         font: default(),
                         font_smoothing: FontSmoothing::default(),
-                        ..default()
                     },
 
                     text_color: OverlayColor::GREEN,
@@ -69,14 +77,40 @@ fn main() {
                 },
             },
         ))
+        .init_state::<AppState>()
+        // Everything of a match goes when leaving it
+        .enable_state_scoped_entities::<AppState>()
         // Game resources
         .insert_resource(PlayerData {
             name: String::new(),
             connected: false,
         })
+        // Run FixedUpdate at the server tick rate so predicted inputs match
+        .insert_resource(Time::<Fixed>::from_hz(TICK_RATE as f64))
+        // Network plugins
+        .add_plugins(NetworkPlugin)
         // UI plugins
-        .add_plugins((WelcomePlugin))
+        .add_plugins((
+            WelcomePlugin,
+            NetworkDebugPlugin,
+            HudPlugin,
+            VotePlugin,
+            PausePlugin,
+            ResultsPlugin,
+            ChatPlugin,
+            PartyPlugin,
+            TournamentPlugin,
+            RoomsPlugin,
+        ))
         // Game plugins
-        .add_plugins((WorldPlugin, PlayerPlugin))
+        .add_plugins((
+            WorldPlugin,
+            ArenaPlugin,
+            PlayerPlugin,
+            InterpolationPlugin,
+            BallPlugin,
+            PowerUpPlugin,
+            LocalPlugin,
+        ))
         .run();
 }
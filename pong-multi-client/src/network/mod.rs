@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use resource::{ClockSync, MatchInfo};
+use system::{
+    connect_to_server, flush_server_messages, handle_handshake, handle_kicked, handle_match_found,
+    handle_pong, handle_spectating, receive_server_messages, send_ping, update_server_tick,
+};
+
+use crate::{game::local::playing_online, user_interface::results::MatchResult, AppState};
+
+pub mod resource;
+pub mod system;
+
+pub struct NetworkPlugin;
+
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<resource::ServerMessage>()
+            .init_resource::<ClockSync>()
+            .add_systems(Startup, connect_to_server)
+            .add_systems(PreUpdate, receive_server_messages)
+            .add_systems(Last, flush_server_messages)
+            .add_systems(Update, (send_ping, handle_pong, update_server_tick).chain())
+            .add_systems(Update, handle_kicked)
+            .add_systems(
+                Update,
+                handle_handshake.run_if(in_state(AppState::Matching).or(in_state(AppState::Lobby))),
+            )
+            .add_systems(
+                Update,
+                handle_match_found.after(handle_handshake).run_if(
+                    in_state(AppState::Matching)
+                        .or(in_state(AppState::Lobby))
+                        .or(in_state(AppState::InGame)
+                            .and(playing_online)
+                            .and(resource_exists::<MatchResult>)),
+                ),
+            )
+            .add_systems(Update, handle_spectating.run_if(in_state(AppState::Lobby)));
+    }
+}
+
+pub fn spectating(match_info: Option<Res<MatchInfo>>) -> bool {
+    match_info.is_some_and(|match_info| match_info.spectating)
+}
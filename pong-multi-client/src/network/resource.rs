@@ -0,0 +1,324 @@
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::UdpSocket,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use pong_multi_shared::{
+    fragment::{reassembler::Reassembler, Fragmenter, MAX_PACKET_SIZE},
+    game::{
+        ai::Difficulty,
+        formation::{Formation, Seat},
+        TICK_RATE,
+    },
+    netsim::{conditioner::Conditioner, NetworkConditions},
+    protocol::{Decoder, Encoder, WireFormat},
+    security::{
+        key_from_hex,
+        packet::{is_secure, Session},
+        to_hex, Handshake, Role,
+    },
+};
+use serde_json::{json, Value};
+
+pub const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:8090";
+
+// Number of ping samples kept to pick the clock offset from
+const CLOCK_SAMPLES: usize = 8;
+
+// Seconds between two pings
+const PING_INTERVAL: f32 = 1.0;
+
+#[derive(Resource)]
+pub struct ServerConnection {
+    pub socket: UdpSocket,
+
+    // Ask the server to encrypt the session, not only authenticate it
+    pub encrypt: bool,
+
+    // Our half of the key exchange, until the server answers
+    pub handshake: Mutex<Option<Handshake>>,
+    pub session: Mutex<Option<Session>>,
+
+    // Simulated network conditions, one queue per direction
+    pub outgoing: Mutex<Conditioner<Vec<u8>>>,
+    pub incoming: Mutex<Conditioner<Vec<u8>>>,
+
+    // Large messages travel in several datagrams
+    pub fragmenter: Mutex<Fragmenter>,
+    pub reassembler: Mutex<Reassembler>,
+
+    // Binary unless asked for JSON, snapshots come as deltas against the ones we acked
+    pub encoder: Mutex<Encoder>,
+    pub decoder: Mutex<Decoder>,
+}
+
+impl ServerConnection {
+    pub fn new(
+        socket: UdpSocket,
+        encrypt: bool,
+        conditions: NetworkConditions,
+        wire_format: WireFormat,
+    ) -> Self {
+        Self {
+            socket,
+            encrypt,
+            handshake: Mutex::new(None),
+            session: Mutex::new(None),
+            outgoing: Mutex::new(Conditioner::new(conditions)),
+            incoming: Mutex::new(Conditioner::new(conditions)),
+            fragmenter: Mutex::new(Fragmenter::default()),
+            reassembler: Mutex::new(Reassembler::default()),
+            encoder: Mutex::new(Encoder::new(wire_format)),
+            decoder: Mutex::new(Decoder::default()),
+        }
+    }
+
+    pub fn conditions(&self) -> NetworkConditions {
+        self.outgoing.lock().unwrap().conditions
+    }
+
+    pub fn set_conditions(&self, conditions: NetworkConditions) {
+        self.outgoing.lock().unwrap().conditions = conditions;
+        self.incoming.lock().unwrap().conditions = conditions;
+    }
+
+    // Messages are secured as soon as the session is set up
+    pub fn send(&self, message: &Value) {
+        let payload = self.encoder.lock().unwrap().encode(message);
+        let packet = match self.session.lock().unwrap().as_mut() {
+            Some(session) => session.seal(&payload),
+            None => payload,
+        };
+
+        let fragments = self.fragmenter.lock().unwrap().split(&packet);
+        let mut outgoing = self.outgoing.lock().unwrap();
+
+        for fragment in fragments {
+            if outgoing.passes_through() {
+                self.send_packet(&fragment);
+            } else {
+                let size = fragment.len();
+                outgoing.push(Instant::now(), fragment, size);
+            }
+        }
+    }
+
+    fn send_packet(&self, packet: &[u8]) {
+        if let Err(e) = self.socket.send(packet) {
+            eprintln!("Error sending packet: {:?}", e);
+        }
+    }
+
+    // Send the packets held back by the simulated network that are due
+    pub fn flush(&self) {
+        let ready = self.outgoing.lock().unwrap().pop_ready(Instant::now());
+        for packet in ready {
+            self.send_packet(&packet);
+        }
+    }
+
+    // Everything the server sent since the last call, as it comes out of the simulated network
+    pub fn receive(&self) -> Vec<Vec<u8>> {
+        let mut incoming = self.incoming.lock().unwrap();
+        let mut datagrams = Vec::new();
+
+        // One byte more than we accept, to tell a full datagram from a truncated one
+        let mut buf = [0; MAX_PACKET_SIZE + 1];
+
+        loop {
+            match self.socket.recv(&mut buf) {
+                Ok(len) if len > MAX_PACKET_SIZE => println!("Dropped an oversized datagram"),
+                Ok(len) if incoming.passes_through() => datagrams.push(buf[..len].to_vec()),
+                Ok(len) => incoming.push(Instant::now(), buf[..len].to_vec(), len),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    eprintln!("Error receiving packet: {:?}", e);
+                    break;
+                }
+            }
+        }
+
+        datagrams.extend(incoming.pop_ready(Instant::now()));
+
+        let mut reassembler = self.reassembler.lock().unwrap();
+        datagrams
+            .into_iter()
+            .filter_map(|datagram| reassembler.receive(Instant::now(), datagram))
+            .collect()
+    }
+
+    // Speak the version the server picked from the ones we sent with enter
+    pub fn set_protocol(&self, version: u8) {
+        self.encoder.lock().unwrap().version = version;
+    }
+
+    pub fn start_handshake(&self) {
+        let handshake = Handshake::new();
+        let message = json!({
+            "action": "handshake",
+            "public_key": to_hex(&handshake.public_key),
+            "encrypt": self.encrypt,
+        });
+
+        *self.session.lock().unwrap() = None;
+        *self.handshake.lock().unwrap() = Some(handshake);
+
+        self.send(&message);
+    }
+
+    // Derive the session from the server answer, returns whether it worked
+    pub fn complete_handshake(&self, message: &Value) -> bool {
+        let Some(server_key) = message["public_key"].as_str().and_then(key_from_hex) else {
+            return false;
+        };
+        let Some(handshake) = self.handshake.lock().unwrap().take() else {
+            return false;
+        };
+
+        let encrypted = message["encrypt"].as_bool().unwrap_or(false);
+        *self.session.lock().unwrap() =
+            Some(handshake.complete(Role::Client, server_key, encrypted));
+
+        true
+    }
+
+    // Verify a packet from the server. Plain packets are only accepted before the
+    // session is set up, after that they could come from anyone.
+    pub fn open(&self, packet: &[u8]) -> Option<Vec<u8>> {
+        let mut session = self.session.lock().unwrap();
+
+        match (session.as_mut(), is_secure(packet)) {
+            (Some(session), true) => match session.open(packet) {
+                Ok(payload) => Some(payload),
+                Err(e) => {
+                    println!("Dropped packet from the server: {}", e);
+                    None
+                }
+            },
+            (None, false) => Some(packet.to_vec()),
+            _ => None,
+        }
+    }
+
+    pub fn decode(&self, payload: &[u8]) -> Option<Value> {
+        match self.decoder.lock().unwrap().decode(payload) {
+            Ok(message) => Some(message),
+            Err(e) => {
+                println!("Invalid message received from the server: {}", e);
+                None
+            }
+        }
+    }
+}
+
+// Every message received from the server, decoded to JSON, read by the game systems
+#[derive(Event)]
+pub struct ServerMessage(pub Value);
+
+#[derive(Resource)]
+pub struct MatchInfo {
+    pub room_id: String,
+    pub seat: Seat,
+    pub formation: Formation,
+
+    // Set when the server plays the empty seats
+    pub ai: Option<Difficulty>,
+
+    // Watching from the room browser, the seat is only there to have one
+    pub spectating: bool,
+}
+
+// A match found while the last one was still on screen, like the next one of
+// a tournament. Started once we are back in the lobby.
+#[derive(Resource)]
+pub struct PendingMatch(pub Value);
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClockSample {
+    pub rtt: f64,
+    pub offset: f64,
+}
+
+// Estimate of the server clock, built from ping/pong timestamps like NTP.
+// All times are in milliseconds.
+#[derive(Resource)]
+pub struct ClockSync {
+    pub rtt: f64,
+
+    // Add to the client time to get the server time
+    pub offset: f64,
+
+    // Average variation between two consecutive round trips
+    pub jitter: f64,
+
+    // Smoothed estimate of the tick the room is simulating right now
+    pub server_tick: Option<f64>,
+
+    // Server time at tick 0 of the current room
+    pub tick_origin: Option<f64>,
+
+    pub samples: VecDeque<ClockSample>,
+    pub ping_timer: Timer,
+}
+
+impl Default for ClockSync {
+    fn default() -> Self {
+        // Start elapsed so the first ping goes out right away
+        let mut ping_timer = Timer::from_seconds(PING_INTERVAL, TimerMode::Repeating);
+        ping_timer.set_elapsed(Duration::from_secs_f32(PING_INTERVAL));
+
+        Self {
+            rtt: 0.0,
+            offset: 0.0,
+            jitter: 0.0,
+            server_tick: None,
+            tick_origin: None,
+            samples: VecDeque::new(),
+            ping_timer,
+        }
+    }
+}
+
+impl ClockSync {
+    pub fn synced(&self) -> bool {
+        !self.samples.is_empty()
+    }
+
+    // t0: ping sent, t1: ping received by the server,
+    // t2: pong sent by the server, t3: pong received
+    pub fn add_sample(&mut self, t0: f64, t1: f64, t2: f64, t3: f64) {
+        let rtt = ((t3 - t0) - (t2 - t1)).max(0.0);
+        let offset = ((t1 - t0) + (t2 - t3)) / 2.0;
+
+        if self.synced() {
+            self.jitter += ((rtt - self.rtt).abs() - self.jitter) / 16.0;
+            self.rtt += (rtt - self.rtt) / 8.0;
+        } else {
+            self.rtt = rtt;
+        }
+
+        self.samples.push_back(ClockSample { rtt, offset });
+        if self.samples.len() > CLOCK_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        // The sample with the shortest round trip has the least asymmetry in it
+        if let Some(best) = self.samples.iter().min_by(|a, b| a.rtt.total_cmp(&b.rtt)) {
+            self.offset = best.offset;
+        }
+    }
+
+    pub fn server_time(&self, client_time: f64) -> f64 {
+        client_time + self.offset
+    }
+
+    pub fn estimate_tick(&self, client_time: f64) -> Option<f64> {
+        let tick_origin = self.tick_origin?;
+
+        Some((self.server_time(client_time) - tick_origin) * TICK_RATE as f64 / 1000.0)
+    }
+}
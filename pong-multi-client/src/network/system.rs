@@ -0,0 +1,353 @@
+use std::net::UdpSocket;
+
+use bevy::prelude::*;
+use pong_multi_shared::{
+    game::{
+        ai::Difficulty,
+        formation::{Formation, GameMode, Seat},
+        Side, TICK_RATE,
+    },
+    netsim::NetworkConditions,
+    protocol::{WireFormat, PROTOCOL_VERSION},
+};
+use serde_json::{json, Value};
+
+use crate::{
+    user_interface::welcome::{system::add_opponent, SelectedMode, SelectedOpponent},
+    AppState,
+};
+
+use super::resource::{
+    ClockSync, MatchInfo, PendingMatch, ServerConnection, ServerMessage, DEFAULT_SERVER_ADDR,
+};
+
+// Jump straight to the estimated server tick when drifting further than this
+const RESYNC_TICKS: f64 = 30.0;
+
+// Fraction of the drift corrected per second
+const CATCH_UP_RATE: f64 = 2.0;
+
+pub fn connect_to_server(mut commands: Commands) {
+    let server_addr =
+        std::env::var("PONG_SERVER_ADDR").unwrap_or_else(|_| DEFAULT_SERVER_ADDR.to_string());
+
+    let socket = UdpSocket::bind("0.0.0.0:0").expect("Failed to bind socket");
+    socket
+        .connect(&server_addr)
+        .expect("Failed to connect to the server");
+    socket
+        .set_nonblocking(true)
+        .expect("Failed to set the socket non-blocking");
+
+    let encrypt = std::env::var("PONG_ENCRYPT").is_ok_and(|value| value == "1");
+
+    // Simulated network conditions, the debug menu can change them later
+    let conditions = std::env::var("PONG_NETSIM")
+        .ok()
+        .and_then(|spec| match spec.parse::<NetworkConditions>() {
+            Ok(conditions) => Some(conditions),
+            Err(e) => {
+                eprintln!("Ignoring PONG_NETSIM: {e}");
+                None
+            }
+        })
+        .unwrap_or_default();
+
+    // JSON is easier to read in a packet capture
+    let wire_format = std::env::var("PONG_WIRE")
+        .ok()
+        .and_then(|format| match format.parse::<WireFormat>() {
+            Ok(format) => Some(format),
+            Err(e) => {
+                eprintln!("Ignoring PONG_WIRE: {e}");
+                None
+            }
+        })
+        .unwrap_or_default();
+
+    commands.insert_resource(ServerConnection::new(
+        socket,
+        encrypt,
+        conditions,
+        wire_format,
+    ));
+}
+
+// Drain everything the server sent since the last frame
+pub fn receive_server_messages(
+    connection: Res<ServerConnection>,
+    mut message_writer: EventWriter<ServerMessage>,
+) {
+    for packet in connection.receive() {
+        let Some(payload) = connection.open(&packet) else {
+            continue;
+        };
+
+        let Some(json) = connection.decode(&payload) else {
+            continue;
+        };
+
+        // Tell the server we have it, later snapshots are sent as deltas against it
+        if json["action"].as_str() == Some("snapshot") {
+            if let Some(tick) = json["tick"].as_u64() {
+                connection.send(&json!({ "action": "ack", "tick": tick }));
+            }
+        }
+
+        message_writer.send(ServerMessage(json));
+    }
+}
+
+// Send what the simulated network held back and is now due
+pub fn flush_server_messages(connection: Res<ServerConnection>) {
+    connection.flush();
+}
+
+// The server registered us, set up the session and then ask for a match,
+// unless we are in the lobby to make a party first.
+// Everything after the handshake is authenticated.
+pub fn handle_handshake(
+    mut message_reader: EventReader<ServerMessage>,
+    state: Res<State<AppState>>,
+    connection: Res<ServerConnection>,
+    selected_mode: Res<SelectedMode>,
+    selected_opponent: Res<SelectedOpponent>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        match json["action"].as_str() {
+            Some("entered") => {
+                // Servers from before the negotiation only speak the first version
+                let protocol = json["protocol"].as_u64().unwrap_or(1);
+                connection.set_protocol(protocol.min(PROTOCOL_VERSION as u64) as u8);
+                connection.start_handshake();
+            }
+
+            Some("handshake") => {
+                if !connection.complete_handshake(json) {
+                    println!("Invalid handshake received from the server");
+                } else if *state.get() == AppState::Matching {
+                    let mut join = json!({
+                        "action": "join",
+                        "mode": selected_mode.0.as_str(),
+                    });
+                    add_opponent(&mut join, selected_opponent.0);
+                    connection.send(&join);
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+// A match found at the end of another one waits for everything of the old
+// one to be gone with the InGame state
+pub fn handle_match_found(
+    mut commands: Commands,
+    mut message_reader: EventReader<ServerMessage>,
+    pending: Option<Res<PendingMatch>>,
+    state: Res<State<AppState>>,
+    mut clock: ResMut<ClockSync>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut found: Vec<Value> = message_reader
+        .read()
+        .filter(|ServerMessage(json)| json["action"].as_str() == Some("match_found"))
+        .map(|ServerMessage(json)| json.clone())
+        .collect();
+
+    if *state.get() == AppState::InGame {
+        if let Some(json) = found.pop() {
+            commands.insert_resource(PendingMatch(json));
+            next_state.set(AppState::Lobby);
+        }
+        return;
+    }
+
+    if let Some(pending) = pending {
+        found.insert(0, pending.0.clone());
+        commands.remove_resource::<PendingMatch>();
+    }
+
+    for json in &found {
+        let (Some(room_id), Some(side)) = (
+            json["room_id"].as_str(),
+            json["side"].as_str().and_then(Side::parse),
+        ) else {
+            continue;
+        };
+
+        let Some(formation) = read_formation(json) else {
+            continue;
+        };
+
+        let slot = json["slot"].as_u64().unwrap_or(0) as usize;
+        if slot >= formation.team_size() {
+            continue;
+        }
+        let seat = Seat::new(side, slot);
+
+        println!(
+            "Joined room {} ({}) on the {} paddle",
+            room_id,
+            formation.mode,
+            seat.key()
+        );
+
+        // Ticks restart from zero in every room
+        clock.tick_origin = None;
+        clock.server_tick = None;
+
+        commands.insert_resource(MatchInfo {
+            room_id: room_id.to_string(),
+            seat,
+            formation,
+            ai: json["ai"].as_str().and_then(Difficulty::parse),
+            spectating: false,
+        });
+        next_state.set(AppState::InGame);
+    }
+}
+
+// Watching a match picked in the room browser, every paddle is someone else's
+pub fn handle_spectating(
+    mut commands: Commands,
+    mut message_reader: EventReader<ServerMessage>,
+    mut clock: ResMut<ClockSync>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("spectating") {
+            continue;
+        }
+
+        let (Some(room_id), Some(formation)) = (json["room_id"].as_str(), read_formation(json))
+        else {
+            continue;
+        };
+        println!("Watching room {} ({})", room_id, formation.mode);
+
+        clock.tick_origin = None;
+        clock.server_tick = None;
+
+        commands.insert_resource(MatchInfo {
+            room_id: room_id.to_string(),
+            seat: Seat::new(Side::Left, 0),
+            formation,
+            ai: json["ai"].as_str().and_then(Difficulty::parse),
+            spectating: true,
+        });
+        next_state.set(AppState::InGame);
+    }
+}
+
+// Only doubles say where the paddles stand, singles are the same as ever
+fn read_formation(json: &Value) -> Option<Formation> {
+    match json["mode"].as_str().and_then(GameMode::parse) {
+        Some(GameMode::Doubles) => {
+            let depths = json["depths"].as_array()?;
+            let [back, front] = [depths.first()?.as_f64()?, depths.get(1)?.as_f64()?];
+            Some(Formation::doubles([back as f32, front as f32]))
+        }
+        _ => Some(Formation::singles()),
+    }
+}
+
+pub fn handle_kicked(mut message_reader: EventReader<ServerMessage>) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() == Some("kicked") {
+            println!(
+                "Kicked by the server: {}",
+                json["reason"].as_str().unwrap_or("no reason given")
+            );
+        }
+    }
+}
+
+fn client_time_ms(time: &Time<Real>) -> f64 {
+    time.elapsed_secs_f64() * 1000.0
+}
+
+pub fn send_ping(
+    time: Res<Time<Real>>,
+    connection: Res<ServerConnection>,
+    mut clock: ResMut<ClockSync>,
+) {
+    if !clock.ping_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    connection.send(&json!({
+        "action": "ping",
+        "client_time": client_time_ms(&time),
+    }));
+}
+
+pub fn handle_pong(
+    time: Res<Time<Real>>,
+    mut message_reader: EventReader<ServerMessage>,
+    mut clock: ResMut<ClockSync>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("pong") {
+            continue;
+        }
+
+        let (Some(t0), Some(t1), Some(t2)) = (
+            json["client_time"].as_f64(),
+            json["server_receive"].as_f64(),
+            json["server_send"].as_f64(),
+        ) else {
+            continue;
+        };
+
+        clock.add_sample(t0, t1, t2, client_time_ms(&time));
+    }
+}
+
+// Follow the server tick between snapshots, easing towards the estimate
+// from the clock offset so jitter doesn't make it jump around
+pub fn update_server_tick(
+    time: Res<Time<Real>>,
+    mut message_reader: EventReader<ServerMessage>,
+    mut clock: ResMut<ClockSync>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("snapshot") {
+            continue;
+        }
+
+        let (Some(tick), Some(server_time)) = (json["tick"].as_u64(), json["time"].as_f64()) else {
+            continue;
+        };
+
+        let tick_origin = server_time - tick as f64 * 1000.0 / TICK_RATE as f64;
+        clock.tick_origin = Some(match clock.tick_origin {
+            Some(current) => current + (tick_origin - current) / 8.0,
+            None => tick_origin,
+        });
+    }
+
+    if !clock.synced() {
+        return;
+    }
+
+    let Some(estimate) = clock.estimate_tick(client_time_ms(&time)) else {
+        return;
+    };
+
+    let dt = time.delta_secs_f64();
+    clock.server_tick = Some(match clock.server_tick {
+        Some(server_tick) => {
+            let server_tick = server_tick + dt * TICK_RATE as f64;
+            let drift = estimate - server_tick;
+
+            if drift.abs() > RESYNC_TICKS {
+                estimate
+            } else {
+                server_tick + drift * (CATCH_UP_RATE * dt).min(1.0)
+            }
+        }
+        None => estimate,
+    });
+}
@@ -0,0 +1,46 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+// Lines kept on screen, older ones scroll out
+pub const MAX_CHAT_LINES: usize = 6;
+
+#[derive(Component)]
+pub struct ChatBox {}
+
+#[derive(Component)]
+pub struct ChatText {}
+
+#[derive(Component)]
+pub struct DraftText {}
+
+pub struct ChatLine {
+    pub text: String,
+
+    // Seconds since startup, the line fades out a while after it
+    pub received_at: f32,
+}
+
+// What was said in our room or the lobby, newest last
+#[derive(Resource, Default)]
+pub struct ChatLog {
+    pub lines: VecDeque<ChatLine>,
+}
+
+impl ChatLog {
+    pub fn push(&mut self, text: String, received_at: f32) {
+        self.lines.push_back(ChatLine { text, received_at });
+        if self.lines.len() > MAX_CHAT_LINES {
+            self.lines.pop_front();
+        }
+    }
+}
+
+// Set while typing a line, the keyboard doesn't steer the paddle meanwhile
+#[derive(Resource, Default)]
+pub struct ChatDraft(pub String);
+
+// A line starting with a slash, handled by the screen that knows the command
+// instead of going to the server
+#[derive(Event)]
+pub struct ChatCommand(pub String);
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use components::{ChatCommand, ChatLog};
+use system::{receive_chat, send_emotes, show_chat, show_chat_box, spawn_chat, type_chat};
+
+use crate::{game::local::resource::LocalMatch, AppState};
+
+pub mod components;
+pub mod system;
+
+// Chat with the room during a match and with the lobby while waiting for one.
+// Enter types a line, the function keys send the quick emotes. Lines starting
+// with a slash are commands, like the party ones in the lobby.
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatLog>()
+            .add_event::<ChatCommand>()
+            .add_systems(Startup, spawn_chat)
+            .add_systems(
+                Update,
+                (receive_chat, type_chat, send_emotes)
+                    .chain()
+                    .run_if(chat_available),
+            )
+            .add_systems(
+                Update,
+                (show_chat_box, show_chat.run_if(chat_available))
+                    .chain()
+                    .after(send_emotes),
+            );
+    }
+}
+
+// Only with a server to talk to, local matches have nobody to chat with
+pub fn chat_available(state: Res<State<AppState>>, local: Option<Res<LocalMatch>>) -> bool {
+    matches!(
+        state.get(),
+        AppState::Lobby | AppState::Matching | AppState::InGame
+    ) && local.is_none()
+}
@@ -0,0 +1,217 @@
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+use pong_multi_shared::chat::{ChatContent, Emote, MAX_CHAT_LENGTH};
+use serde_json::json;
+
+use crate::{
+    game::local::resource::LocalMatch,
+    network::resource::{ServerConnection, ServerMessage},
+    AppState,
+};
+
+use super::{
+    chat_available,
+    components::{ChatBox, ChatCommand, ChatDraft, ChatLog, ChatText, DraftText},
+};
+
+const CHAT_COLOR: Color = Color::WHITE;
+const DRAFT_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
+
+// How long a line stays up when nobody is typing
+const CHAT_LINE_SECONDS: f32 = 10.0;
+
+const EMOTE_KEYS: [(KeyCode, Emote); 4] = [
+    (KeyCode::F5, Emote::GoodGame),
+    (KeyCode::F6, Emote::NiceShot),
+    (KeyCode::F7, Emote::WellPlayed),
+    (KeyCode::F8, Emote::Oops),
+];
+
+pub fn spawn_chat(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn((
+            ChatBox {},
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                left: Val::Px(8.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            // Over the welcome screen, which stays up in the lobby
+            GlobalZIndex(1),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ChatText {},
+                Text::new(""),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(CHAT_COLOR),
+            ));
+            parent.spawn((
+                DraftText {},
+                Text::new(""),
+                TextFont {
+                    font,
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(DRAFT_COLOR),
+            ));
+        });
+}
+
+// Lines from the server, in the room they come with the side of the sender
+pub fn receive_chat(
+    time: Res<Time>,
+    mut log: ResMut<ChatLog>,
+    mut message_reader: EventReader<ServerMessage>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"] != "chat" {
+            continue;
+        }
+
+        let Some(content) = ChatContent::from_json(json) else {
+            continue;
+        };
+        let from = json["from"].as_str().unwrap_or("?");
+
+        let line = match json["side"].as_str() {
+            Some(side) => format!("[{side}] {from}: {}", content.text()),
+            None => format!("{from}: {}", content.text()),
+        };
+        log.push(line, time.elapsed_secs());
+    }
+}
+
+// Enter starts a line and sends it, Escape drops it. Commands stay on this side.
+pub fn type_chat(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut key_events: EventReader<KeyboardInput>,
+    connection: Res<ServerConnection>,
+    mut command_writer: EventWriter<ChatCommand>,
+    draft: Option<ResMut<ChatDraft>>,
+) {
+    let Some(mut draft) = draft else {
+        if keyboard.just_pressed(KeyCode::Enter) {
+            commands.init_resource::<ChatDraft>();
+        }
+        key_events.clear();
+        return;
+    };
+
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                let text = draft.0.trim();
+                if text.starts_with('/') {
+                    command_writer.send(ChatCommand(text.to_string()));
+                } else if !text.is_empty() {
+                    let mut message = json!({ "action": "chat" });
+                    ChatContent::Text(text.to_string()).add_to(&mut message);
+                    connection.send(&message);
+                }
+                commands.remove_resource::<ChatDraft>();
+                return;
+            }
+            Key::Escape => {
+                commands.remove_resource::<ChatDraft>();
+                return;
+            }
+            Key::Backspace => {
+                draft.0.pop();
+            }
+            Key::Space => push_limited(&mut draft.0, " "),
+            Key::Character(chars) => push_limited(&mut draft.0, chars),
+            _ => {}
+        }
+    }
+}
+
+fn push_limited(draft: &mut String, chars: &str) {
+    for c in chars.chars().filter(|c| !c.is_control()) {
+        if draft.chars().count() >= MAX_CHAT_LENGTH {
+            return;
+        }
+        draft.push(c);
+    }
+}
+
+pub fn send_emotes(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    connection: Res<ServerConnection>,
+    draft: Option<Res<ChatDraft>>,
+) {
+    if draft.is_some() {
+        return;
+    }
+
+    for (key, emote) in EMOTE_KEYS {
+        if keyboard.just_pressed(key) {
+            let mut message = json!({ "action": "chat" });
+            ChatContent::Emote(emote).add_to(&mut message);
+            connection.send(&message);
+        }
+    }
+}
+
+pub fn show_chat_box(
+    state: Res<State<AppState>>,
+    local: Option<Res<LocalMatch>>,
+    mut box_query: Query<&mut Visibility, With<ChatBox>>,
+) {
+    let visible = chat_available(state, local);
+    for mut visibility in box_query.iter_mut() {
+        *visibility = if visible {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+// Recent lines and the one being typed, everything stays up while typing
+pub fn show_chat(
+    time: Res<Time>,
+    log: Res<ChatLog>,
+    draft: Option<Res<ChatDraft>>,
+    mut chat_query: Query<&mut Text, (With<ChatText>, Without<DraftText>)>,
+    mut draft_query: Query<&mut Text, (With<DraftText>, Without<ChatText>)>,
+) {
+    let now = time.elapsed_secs();
+    let lines: Vec<&str> = log
+        .lines
+        .iter()
+        .filter(|line| draft.is_some() || now - line.received_at < CHAT_LINE_SECONDS)
+        .map(|line| line.text.as_str())
+        .collect();
+
+    for mut text in chat_query.iter_mut() {
+        **text = lines.join("\n");
+    }
+
+    for mut text in draft_query.iter_mut() {
+        **text = match &draft {
+            Some(draft) => format!("> {}_", draft.0),
+            None => "Enter: chat  F5-F8: GG, nice shot, well played, oops".to_string(),
+        };
+    }
+}
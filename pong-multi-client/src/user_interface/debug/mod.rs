@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+use system::{
+    cycle_network_conditions, spawn_network_debug, toggle_network_debug, update_network_debug,
+};
+
+pub mod components;
+pub mod system;
+
+// F3 shows the network debug overlay, F4 cycles through the simulated network presets
+pub struct NetworkDebugPlugin;
+
+#[derive(Resource, Default)]
+pub struct NetworkDebug {
+    // Index in NetworkConditions::PRESETS of the last preset picked
+    pub preset: usize,
+}
+
+impl Plugin for NetworkDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetworkDebug>()
+            .add_systems(Startup, spawn_network_debug)
+            .add_systems(
+                Update,
+                (
+                    toggle_network_debug,
+                    cycle_network_conditions,
+                    update_network_debug,
+                )
+                    .chain(),
+            );
+    }
+}
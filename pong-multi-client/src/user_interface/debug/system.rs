@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use pong_multi_shared::netsim::NetworkConditions;
+
+use crate::network::resource::{ClockSync, ServerConnection};
+
+use super::{components::NetworkDebugText, NetworkDebug};
+
+const DEBUG_TEXT_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
+
+pub fn spawn_network_debug(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands.spawn((
+        NetworkDebugText {},
+        Text::new(""),
+        TextFont {
+            font,
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(DEBUG_TEXT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            ..default()
+        },
+        // Drawn over every screen
+        GlobalZIndex(i32::MAX),
+        Visibility::Hidden,
+    ));
+}
+
+pub fn toggle_network_debug(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut query: Query<&mut Visibility, With<NetworkDebugText>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    for mut visibility in query.iter_mut() {
+        visibility.toggle_visible_hidden();
+    }
+}
+
+pub fn cycle_network_conditions(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    connection: Res<ServerConnection>,
+    mut debug: ResMut<NetworkDebug>,
+) {
+    if !keyboard.just_pressed(KeyCode::F4) {
+        return;
+    }
+
+    debug.preset = (debug.preset + 1) % NetworkConditions::PRESETS.len();
+    let name = NetworkConditions::PRESETS[debug.preset];
+
+    if let Some(conditions) = NetworkConditions::preset(name) {
+        connection.set_conditions(conditions);
+        println!("Simulated network set to {} ({})", name, conditions);
+    }
+}
+
+pub fn update_network_debug(
+    connection: Res<ServerConnection>,
+    clock: Res<ClockSync>,
+    mut query: Query<(&mut Text, &Visibility), With<NetworkDebugText>>,
+) {
+    for (mut text, visibility) in query.iter_mut() {
+        if visibility == Visibility::Hidden {
+            continue;
+        }
+
+        text.0 = format!(
+            "rtt {:.0} ms  jitter {:.1} ms\nsimulated: {}\nF4: next preset",
+            clock.rtt,
+            clock.jitter,
+            connection.conditions()
+        );
+    }
+}
@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct ScoreText {}
+
+#[derive(Component)]
+pub struct ModeText {}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+use system::{label_paddles, spawn_hud, update_score};
+
+use crate::AppState;
+
+pub mod components;
+pub mod system;
+
+// Score and mode at the top of the field, and who is who above the paddles
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), spawn_hud)
+            .add_systems(
+                Update,
+                (update_score, label_paddles).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
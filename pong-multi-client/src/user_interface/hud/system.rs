@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::{formation::GameMode, paddle::PADDLE_HEIGHT, Side};
+
+use crate::{
+    game::{
+        interpolation::resource::SnapshotBuffer,
+        local::resource::LocalMatch,
+        player::component::{Player, RemotePaddle},
+    },
+    network::resource::MatchInfo,
+    AppState,
+};
+
+use super::components::{ModeText, ScoreText};
+
+const HUD_COLOR: Color = Color::WHITE;
+const LABEL_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
+
+pub fn spawn_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    match_info: Res<MatchInfo>,
+    local: Option<Res<LocalMatch>>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    let mut mode = match match_info.formation.mode {
+        GameMode::Singles => "1 vs 1".to_string(),
+        GameMode::Doubles => "2 vs 2".to_string(),
+    };
+    if let Some(difficulty) = match_info.ai {
+        mode.push_str(&format!(" - {} AI", difficulty));
+    }
+    if match_info.spectating {
+        mode.push_str(" - watching, Tab: back to the lobby");
+    } else if local.is_none() {
+        mode.push_str(" - P: pause, Shift+P: pause after the point");
+    }
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            StateScoped(AppState::InGame),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ScoreText {},
+                Text::new("0 - 0"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(HUD_COLOR),
+            ));
+            parent.spawn((
+                ModeText {},
+                Text::new(mode),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(HUD_COLOR),
+            ));
+        });
+}
+
+// The score of the snapshot being rendered, so it changes when the ball is seen going out
+pub fn update_score(buffer: Res<SnapshotBuffer>, mut query: Query<&mut Text, With<ScoreText>>) {
+    let Some(snapshot) = buffer.snapshots.front() else {
+        return;
+    };
+
+    for mut text in query.iter_mut() {
+        **text = format!("{} - {}", snapshot.score[0], snapshot.score[1]);
+    }
+}
+
+// Our own paddle, and in doubles our teammate's, get a name above them.
+// Playing hot seat, the two people are told apart instead.
+pub fn label_paddles(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    match_info: Res<MatchInfo>,
+    local: Option<Res<LocalMatch>>,
+    player_query: Query<(Entity, &Player), Added<Player>>,
+    remote_query: Query<(Entity, &RemotePaddle), Added<RemotePaddle>>,
+) {
+    let hot_seat = local.is_some_and(|local| local.is_hot_seat());
+
+    let teammates = remote_query
+        .iter()
+        .filter(|(_, paddle)| {
+            !hot_seat && !match_info.spectating && paddle.seat.side == match_info.seat.side
+        })
+        .map(|(entity, _)| (entity, "MATE"));
+    let labels = player_query
+        .iter()
+        .map(|(entity, player)| {
+            let label = match (hot_seat, player.seat.side) {
+                (false, _) => "YOU",
+                (true, Side::Left) => "P1",
+                (true, Side::Right) => "P2",
+            };
+            (entity, label)
+        })
+        .chain(teammates);
+
+    for (entity, label) in labels {
+        commands.entity(entity).with_child((
+            Text2d::new(label),
+            TextFont {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(LABEL_COLOR),
+            Transform::from_xyz(0.0, PADDLE_HEIGHT / 2.0 + 12.0, 0.0),
+        ));
+    }
+}
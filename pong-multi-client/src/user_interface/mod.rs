@@ -1 +1,10 @@
+pub mod chat;
+pub mod debug;
+pub mod hud;
+pub mod party;
+pub mod pause;
+pub mod results;
+pub mod rooms;
+pub mod tournament;
+pub mod vote;
 pub mod welcome;
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct PartyText {}
+
+// The party we are in, as the server last described it
+#[derive(Resource)]
+pub struct Party {
+    pub code: String,
+
+    // Names, the leader first
+    pub members: Vec<String>,
+    pub leading: bool,
+
+    // Shown in the room browser for anyone to join
+    pub listed: bool,
+}
+
+// The last invite received, /accept joins it
+#[derive(Resource)]
+pub struct PartyInvite {
+    pub from: String,
+    pub code: String,
+}
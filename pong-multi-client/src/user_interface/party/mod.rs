@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+use system::{
+    despawn_party_panel, receive_party, run_party_commands, show_party, spawn_party_panel,
+};
+
+use crate::AppState;
+
+pub mod components;
+pub mod system;
+
+// Parties are made in the lobby with chat commands, then their leader queues
+// everyone in them. The party stays together from one match to the next.
+pub struct PartyPlugin;
+
+impl Plugin for PartyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Lobby), spawn_party_panel)
+            .add_systems(OnExit(AppState::Lobby), despawn_party_panel)
+            .add_systems(Update, receive_party)
+            .add_systems(
+                Update,
+                (run_party_commands, show_party)
+                    .chain()
+                    .after(receive_party)
+                    .run_if(in_state(AppState::Lobby)),
+            );
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use serde_json::json;
+
+use crate::{
+    network::resource::{ServerConnection, ServerMessage},
+    user_interface::{
+        chat::components::{ChatCommand, ChatLog},
+        rooms::system::ROOM_COMMANDS,
+        tournament::system::TOURNAMENT_COMMANDS,
+        welcome::{system::add_opponent, SelectedMode, SelectedOpponent},
+    },
+};
+
+use super::components::{Party, PartyInvite, PartyText};
+
+const PARTY_COLOR: Color = Color::srgb(0.4, 0.7, 1.0);
+
+const PARTY_HELP: &str =
+    "/party [public]: new party  /invite <name>  /join <code>  /accept  /leave  /queue  /find <name>";
+
+pub fn spawn_party_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        PartyText {},
+        Text::new(""),
+        TextFont {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(PARTY_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+        // Over the welcome screen, which stays up in the lobby
+        GlobalZIndex(1),
+    ));
+}
+
+pub fn despawn_party_panel(mut commands: Commands, text_query: Query<Entity, With<PartyText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Kept during matches too, the party outlives them
+pub fn receive_party(mut commands: Commands, mut message_reader: EventReader<ServerMessage>) {
+    for ServerMessage(json) in message_reader.read() {
+        match json["action"].as_str() {
+            Some("party") => {
+                let Some(code) = json["code"].as_str() else {
+                    continue;
+                };
+
+                commands.insert_resource(Party {
+                    code: code.to_string(),
+                    members: json["members"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|member| member.as_str())
+                        .map(str::to_string)
+                        .collect(),
+                    leading: json["leading"].as_bool().unwrap_or(false),
+                    listed: json["listed"].as_bool().unwrap_or(false),
+                });
+                commands.remove_resource::<PartyInvite>();
+            }
+
+            Some("party_left") => commands.remove_resource::<Party>(),
+
+            Some("party_invite") => {
+                let (Some(from), Some(code)) = (json["from"].as_str(), json["code"].as_str())
+                else {
+                    continue;
+                };
+
+                commands.insert_resource(PartyInvite {
+                    from: from.to_string(),
+                    code: code.to_string(),
+                });
+            }
+
+            _ => {}
+        }
+    }
+}
+
+// Commands typed in the chat, anything unknown gets the list of them
+pub fn run_party_commands(
+    time: Res<Time>,
+    mut command_reader: EventReader<ChatCommand>,
+    connection: Res<ServerConnection>,
+    invite: Option<Res<PartyInvite>>,
+    selected_mode: Res<SelectedMode>,
+    selected_opponent: Res<SelectedOpponent>,
+    mut log: ResMut<ChatLog>,
+) {
+    for ChatCommand(line) in command_reader.read() {
+        let (command, argument) = line
+            .split_once(' ')
+            .map_or((line.as_str(), ""), |(command, argument)| {
+                (command, argument.trim())
+            });
+
+        let message = match (command, argument) {
+            ("/party", "public") => json!({ "action": "party_create", "listed": true }),
+            ("/party", _) => json!({ "action": "party_create" }),
+            ("/invite", name) if !name.is_empty() => {
+                json!({ "action": "party_invite", "name": name })
+            }
+            ("/join", code) if !code.is_empty() => json!({ "action": "party_join", "code": code }),
+            ("/accept", _) => match &invite {
+                Some(invite) => json!({ "action": "party_join", "code": invite.code }),
+                None => {
+                    log.push("Nobody invited you".to_string(), time.elapsed_secs());
+                    continue;
+                }
+            },
+            ("/leave", _) => json!({ "action": "party_leave" }),
+            ("/queue", _) => {
+                let mut join = json!({
+                    "action": "join",
+                    "mode": selected_mode.0.as_str(),
+                });
+                add_opponent(&mut join, selected_opponent.0);
+                join
+            }
+            (command, _)
+                if TOURNAMENT_COMMANDS.contains(&command) || ROOM_COMMANDS.contains(&command) =>
+            {
+                continue
+            }
+            _ => {
+                log.push(PARTY_HELP.to_string(), time.elapsed_secs());
+                continue;
+            }
+        };
+
+        connection.send(&message);
+    }
+}
+
+pub fn show_party(
+    party: Option<Res<Party>>,
+    invite: Option<Res<PartyInvite>>,
+    mut text_query: Query<&mut Text, With<PartyText>>,
+) {
+    let mut text = match &party {
+        Some(party) => {
+            let mut text = format!("Party {}", party.code);
+            if party.listed {
+                text.push_str(", listed");
+            }
+            if party.leading {
+                text.push_str(" (you lead, /queue when ready)");
+            }
+            for (index, member) in party.members.iter().enumerate() {
+                let marker = if index == 0 { "*" } else { " " };
+                text.push_str(&format!("\n{marker} {member}"));
+            }
+            text
+        }
+        None => "No party yet".to_string(),
+    };
+
+    if let Some(invite) = &invite {
+        text.push_str(&format!(
+            "\n{} invited you to {}, /accept to join",
+            invite.from, invite.code
+        ));
+    }
+    text.push_str(&format!("\n\nEnter then {PARTY_HELP}"));
+
+    for mut party_text in text_query.iter_mut() {
+        **party_text = text.clone();
+    }
+}
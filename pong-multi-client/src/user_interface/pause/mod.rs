@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use system::{clear_pause, receive_pause_state, request_pause, show_pause};
+
+use crate::{
+    game::local::playing_online,
+    network::spectating,
+    user_interface::{chat::components::ChatDraft, results::MatchResult},
+    AppState,
+};
+
+pub mod system;
+
+// Set while the match is paused, the simulation doesn't move
+#[derive(Resource)]
+pub struct Paused {}
+
+// Where a pause of an online match is at, as the server last told us.
+// Gone while playing.
+#[derive(Resource)]
+pub struct MatchPause {
+    pub state: String,
+
+    // Side of the player who asked for it
+    pub side: Option<String>,
+    pub yours: bool,
+    pub votes: u64,
+    pub needed: u64,
+
+    // Not told to spectators
+    pub pauses_left: Option<u64>,
+
+    // Seconds since startup when the pause or the countdown ends
+    pub ends_at: f32,
+}
+
+#[derive(Component)]
+pub struct PauseText {}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, show_pause)
+            .add_systems(
+                Update,
+                receive_pause_state.run_if(in_state(AppState::InGame).and(playing_online)),
+            )
+            .add_systems(
+                Update,
+                request_pause.run_if(
+                    in_state(AppState::InGame)
+                        .and(playing_online)
+                        .and(not(spectating))
+                        .and(not(resource_exists::<MatchResult>))
+                        .and(not(resource_exists::<ChatDraft>)),
+                ),
+            )
+            .add_systems(OnExit(AppState::InGame), clear_pause);
+    }
+}
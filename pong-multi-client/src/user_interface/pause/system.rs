@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use serde_json::json;
+
+use crate::{
+    network::resource::{MatchInfo, ServerConnection, ServerMessage},
+    user_interface::chat::components::ChatLog,
+};
+
+use super::{MatchPause, PauseText, Paused};
+
+const PAUSE_COLOR: Color = Color::WHITE;
+
+// The server counts down the pause and tells every change, the player who
+// asked for it can take it back or resume, anyone else votes
+pub fn receive_pause_state(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut log: ResMut<ChatLog>,
+    mut message_reader: EventReader<ServerMessage>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        match json["action"].as_str() {
+            Some("pause_state") => {}
+            Some("pause_refused") => {
+                let reason = match json["reason"].as_str() {
+                    Some("no_pauses_left") => "no pauses left",
+                    _ => "the match is already paused",
+                };
+                log.push(format!("Can't pause, {reason}"), time.elapsed_secs());
+                continue;
+            }
+            Some("match_over") => {
+                commands.remove_resource::<MatchPause>();
+                commands.remove_resource::<Paused>();
+                continue;
+            }
+            _ => continue,
+        }
+
+        let state = json["state"].as_str().unwrap_or("playing");
+        match state {
+            "paused" | "resuming" => commands.insert_resource(Paused {}),
+            _ => commands.remove_resource::<Paused>(),
+        }
+        if state == "playing" {
+            commands.remove_resource::<MatchPause>();
+            continue;
+        }
+
+        let seconds = json["seconds"].as_u64().unwrap_or(0) as f32;
+        commands.insert_resource(MatchPause {
+            state: state.to_string(),
+            side: json["side"].as_str().map(str::to_string),
+            yours: json["yours"].as_bool().unwrap_or(false),
+            votes: json["votes"].as_u64().unwrap_or(0),
+            needed: json["needed"].as_u64().unwrap_or(0),
+            pauses_left: json["pauses_left"].as_u64(),
+            ends_at: time.elapsed_secs() + seconds,
+        });
+    }
+}
+
+// P pauses right away, Shift+P once the point is over. While paused it
+// resumes, or votes for it when someone else paused.
+pub fn request_pause(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    connection: Res<ServerConnection>,
+    match_info: Res<MatchInfo>,
+    pause: Option<Res<MatchPause>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+
+    let action = match pause.as_deref() {
+        None => "pause",
+        Some(pause) if pause.state == "paused" || (pause.state == "pending" && pause.yours) => {
+            "resume"
+        }
+        Some(_) => return,
+    };
+
+    let mut message = json!({ "action": action, "room_id": match_info.room_id });
+    if action == "pause" && keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        message["after_point"] = json!(true);
+    }
+    connection.send(&message);
+}
+
+pub fn clear_pause(mut commands: Commands) {
+    commands.remove_resource::<MatchPause>();
+    commands.remove_resource::<Paused>();
+}
+
+pub fn show_pause(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    paused: Option<Res<Paused>>,
+    pause: Option<Res<MatchPause>>,
+    mut text_query: Query<(Entity, &mut Text), With<PauseText>>,
+) {
+    let text = match (&pause, &paused) {
+        (Some(pause), _) => Some(describe(pause, time.elapsed_secs())),
+        (None, Some(_)) => Some("Paused\nP: resume".to_string()),
+        (None, None) => None,
+    };
+
+    match (text, text_query.get_single_mut()) {
+        (Some(text), Err(_)) => {
+            commands.spawn((
+                PauseText {},
+                Text::new(text),
+                TextFont {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(PAUSE_COLOR),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(40.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+            ));
+        }
+        (Some(text), Ok((_, mut shown))) => {
+            if **shown != text {
+                **shown = text;
+            }
+        }
+        (None, Ok((entity, _))) => commands.entity(entity).despawn_recursive(),
+        (None, Err(_)) => {}
+    }
+}
+
+fn describe(pause: &MatchPause, now: f32) -> String {
+    let seconds = (pause.ends_at - now).ceil().max(0.0);
+    let by = match (&pause.side, pause.yours) {
+        (_, true) => "you".to_string(),
+        (Some(side), false) => format!("the {side} side"),
+        (None, false) => "someone".to_string(),
+    };
+    let left = match pause.pauses_left {
+        Some(1) => "\n1 pause left".to_string(),
+        Some(pauses_left) => format!("\n{pauses_left} pauses left"),
+        None => String::new(),
+    };
+
+    match pause.state.as_str() {
+        "pending" if pause.yours => format!("Pausing after this point{left}\nP: never mind"),
+        "pending" => format!("Pausing after this point, asked by {by}"),
+        "resuming" => format!("Resuming in {seconds}"),
+        _ => {
+            let mut text = format!("Paused by {by}, resuming by itself in {seconds}s");
+            if pause.yours {
+                text.push_str(&format!("{left}\nP: resume"));
+            } else if pause.pauses_left.is_some() {
+                text.push_str(&format!(
+                    "\nP: vote to resume ({} of {})",
+                    pause.votes, pause.needed
+                ));
+            }
+            text
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::Side;
+use system::{back_to_lobby, clear_results, handle_match_over, show_results};
+
+use crate::{
+    game::local::playing_online, network::spectating, user_interface::chat::components::ChatDraft,
+    AppState,
+};
+
+pub mod system;
+
+// How the match ended, shown over the field until it is gone
+#[derive(Resource)]
+pub struct MatchResult {
+    pub winner: Side,
+    pub score: [u32; 2],
+
+    // Only set when the match didn't end on points, like a forfeit
+    pub reason: Option<String>,
+
+    // Whether Enter starts another match
+    pub rematch: bool,
+}
+
+#[derive(Component)]
+pub struct ResultsText {}
+
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (handle_match_over, show_results)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            back_to_lobby.run_if(
+                in_state(AppState::InGame)
+                    .and(playing_online)
+                    .and(resource_exists::<MatchResult>.or(spectating))
+                    .and(not(resource_exists::<ChatDraft>)),
+            ),
+        )
+        .add_systems(OnExit(AppState::InGame), clear_results);
+    }
+}
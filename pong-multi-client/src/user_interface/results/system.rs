@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::Side;
+use serde_json::json;
+
+use crate::{
+    game::local::resource::LocalMatch,
+    network::resource::{MatchInfo, ServerConnection, ServerMessage},
+    AppState,
+};
+
+use super::{MatchResult, ResultsText};
+
+const WIN_COLOR: Color = Color::srgb(0.4, 1.0, 0.4);
+const LOSS_COLOR: Color = Color::srgb(1.0, 0.4, 0.4);
+
+pub fn handle_match_over(mut commands: Commands, mut message_reader: EventReader<ServerMessage>) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("match_over") {
+            continue;
+        }
+
+        let Some(winner) = json["winner"].as_str().and_then(Side::parse) else {
+            continue;
+        };
+        let score = [Side::Left, Side::Right]
+            .map(|side| json["score"][side.as_str()].as_u64().unwrap_or(0) as u32);
+
+        commands.insert_resource(MatchResult {
+            winner,
+            score,
+            reason: json["reason"].as_str().map(str::to_string),
+            rematch: false,
+        });
+    }
+}
+
+pub fn show_results(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    match_info: Res<MatchInfo>,
+    local: Option<Res<LocalMatch>>,
+    result: Option<Res<MatchResult>>,
+    text_query: Query<Entity, With<ResultsText>>,
+) {
+    let result = match (result, text_query.get_single()) {
+        (Some(result), Err(_)) => result,
+        (None, Ok(entity)) => {
+            commands.entity(entity).despawn_recursive();
+            return;
+        }
+        _ => return,
+    };
+
+    // Nobody lost on our side of the screen when both players sit at it,
+    // or when only watching
+    let hot_seat = local.as_ref().is_some_and(|local| local.is_hot_seat());
+    let neutral = hot_seat || match_info.spectating;
+    let won = neutral || result.winner == match_info.seat.side;
+    let headline = match (hot_seat, neutral, result.winner) {
+        (true, _, Side::Left) => "P1 wins!",
+        (true, _, Side::Right) => "P2 wins!",
+        (false, true, Side::Left) => "Left wins!",
+        (false, true, Side::Right) => "Right wins!",
+        (false, false, _) if won => "You win!",
+        (false, false, _) => "You lose",
+    };
+    let mut text = format!("{}\n{} - {}", headline, result.score[0], result.score[1]);
+    if let Some(reason) = &result.reason {
+        text.push_str(&format!("\n({reason})"));
+    }
+    if result.rematch {
+        text.push_str("\nEnter: play again");
+    }
+    if local.is_none() {
+        text.push_str("\nTab: back to the lobby");
+    }
+
+    commands.spawn((
+        ResultsText {},
+        Text::new(text),
+        TextFont {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 40.0,
+            ..default()
+        },
+        TextColor(if won { WIN_COLOR } else { LOSS_COLOR }),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(35.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        StateScoped(AppState::InGame),
+    ));
+}
+
+// Online the server is done with us once the match is over. Spectators can
+// leave at any time, the server stops sending them the match.
+pub fn back_to_lobby(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    connection: Res<ServerConnection>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Tab) {
+        connection.send(&json!({ "action": "leave" }));
+        next_state.set(AppState::Lobby);
+    }
+}
+
+pub fn clear_results(mut commands: Commands) {
+    commands.remove_resource::<MatchResult>();
+}
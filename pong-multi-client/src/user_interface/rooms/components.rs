@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::formation::GameMode;
+use serde_json::Value;
+
+// Seconds between two refreshes of the list while it is on screen
+pub const REFRESH_SECONDS: f32 = 3.0;
+
+#[derive(Component)]
+pub struct RoomBrowserPanel {}
+
+// Holds the rows, rebuilt with every page received
+#[derive(Component)]
+pub struct RoomList {}
+
+#[derive(Component)]
+pub struct PageText {}
+
+// A room of the list, clicking it sends the message to join or watch it
+#[derive(Component)]
+pub struct RoomRow(pub Value);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum BrowserButton {
+    Kind,
+    Mode,
+    Previous,
+    Next,
+    Refresh,
+}
+
+// The page on screen and what the list is narrowed to
+#[derive(Resource)]
+pub struct RoomBrowser {
+    // "open" or "live", both when None
+    pub kind: Option<&'static str>,
+    pub mode: Option<GameMode>,
+
+    // Set with /find in the chat
+    pub name: Option<String>,
+
+    pub page: usize,
+    pub pages: usize,
+    pub total: usize,
+
+    pub timer: Timer,
+
+    // Ask right away instead of waiting for the timer
+    pub stale: bool,
+}
+
+impl Default for RoomBrowser {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            mode: None,
+            name: None,
+            page: 0,
+            pages: 1,
+            total: 0,
+            timer: Timer::from_seconds(REFRESH_SECONDS, TimerMode::Repeating),
+            stale: true,
+        }
+    }
+}
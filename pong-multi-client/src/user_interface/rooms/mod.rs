@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+use components::RoomBrowser;
+use system::{
+    click_browser_buttons, click_rooms, label_browser_buttons, receive_rooms, refresh_rooms,
+    run_room_commands, spawn_room_browser,
+};
+
+use crate::AppState;
+
+pub mod components;
+pub mod system;
+
+// The rooms on the server, shown in the lobby: listed parties with room left
+// to join and matches being played to watch. Refreshed every few seconds.
+pub struct RoomsPlugin;
+
+impl Plugin for RoomsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoomBrowser>()
+            .add_systems(OnEnter(AppState::Lobby), spawn_room_browser)
+            .add_systems(
+                Update,
+                (
+                    run_room_commands,
+                    click_browser_buttons,
+                    click_rooms,
+                    refresh_rooms,
+                    receive_rooms,
+                    label_browser_buttons,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Lobby)),
+            );
+    }
+}
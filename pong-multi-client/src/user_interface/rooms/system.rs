@@ -0,0 +1,363 @@
+use bevy::prelude::*;
+use pong_multi_shared::game::formation::GameMode;
+use serde_json::{json, Value};
+
+use crate::{
+    network::resource::{ServerConnection, ServerMessage},
+    user_interface::chat::components::ChatCommand,
+    AppState,
+};
+
+use super::components::{
+    BrowserButton, PageText, RoomBrowser, RoomBrowserPanel, RoomList, RoomRow,
+};
+
+// Left to the party commands to answer anything else
+pub const ROOM_COMMANDS: [&str; 1] = ["/find"];
+
+const BROWSER_COLOR: Color = Color::srgb(0.6, 0.9, 1.0);
+const ROW_COLOR: Color = Color::srgb(0.15, 0.15, 0.2);
+
+const BROWSER_BUTTONS: [BrowserButton; 5] = [
+    BrowserButton::Kind,
+    BrowserButton::Mode,
+    BrowserButton::Previous,
+    BrowserButton::Next,
+    BrowserButton::Refresh,
+];
+
+pub fn spawn_room_browser(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut browser: ResMut<RoomBrowser>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+    browser.stale = true;
+
+    commands
+        .spawn((
+            RoomBrowserPanel {},
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                right: Val::Px(8.0),
+                width: Val::Px(440.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            // Over the welcome screen, which stays up in the lobby
+            GlobalZIndex(1),
+            StateScoped(AppState::Lobby),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for button in BROWSER_BUTTONS {
+                        parent
+                            .spawn((
+                                button,
+                                Button,
+                                Node {
+                                    padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(ROW_COLOR),
+                            ))
+                            .with_child((
+                                Text::new(""),
+                                TextFont {
+                                    font: font.clone(),
+                                    font_size: 14.0,
+                                    ..default()
+                                },
+                                TextColor(BROWSER_COLOR),
+                            ));
+                    }
+                });
+
+            parent.spawn((
+                PageText {},
+                Text::new(""),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(BROWSER_COLOR),
+            ));
+
+            parent.spawn((
+                RoomList {},
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                },
+            ));
+        });
+}
+
+// /find <name> shows only the rooms someone of that name is in, /find alone all of them
+pub fn run_room_commands(
+    mut command_reader: EventReader<ChatCommand>,
+    mut browser: ResMut<RoomBrowser>,
+) {
+    for ChatCommand(line) in command_reader.read() {
+        let Some(name) = line.strip_prefix("/find") else {
+            continue;
+        };
+        if !name.is_empty() && !name.starts_with(' ') {
+            continue;
+        }
+
+        let name = name.trim();
+        browser.name = (!name.is_empty()).then(|| name.to_string());
+        browser.page = 0;
+        browser.stale = true;
+    }
+}
+
+pub fn click_browser_buttons(
+    interaction_query: Query<(&Interaction, &BrowserButton), Changed<Interaction>>,
+    mut browser: ResMut<RoomBrowser>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            BrowserButton::Kind => {
+                browser.kind = match browser.kind {
+                    None => Some("open"),
+                    Some("open") => Some("live"),
+                    Some(_) => None,
+                };
+                browser.page = 0;
+            }
+            BrowserButton::Mode => {
+                browser.mode = match browser.mode {
+                    None => Some(GameMode::Singles),
+                    Some(GameMode::Singles) => Some(GameMode::Doubles),
+                    Some(GameMode::Doubles) => None,
+                };
+                browser.page = 0;
+            }
+            BrowserButton::Previous => browser.page = browser.page.saturating_sub(1),
+            BrowserButton::Next => browser.page = (browser.page + 1).min(browser.pages - 1),
+            BrowserButton::Refresh => {}
+        }
+        browser.stale = true;
+    }
+}
+
+// Join a party or watch a match
+pub fn click_rooms(
+    interaction_query: Query<(&Interaction, &RoomRow), Changed<Interaction>>,
+    connection: Res<ServerConnection>,
+) {
+    for (interaction, row) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            connection.send(&row.0);
+        }
+    }
+}
+
+pub fn refresh_rooms(
+    time: Res<Time>,
+    connection: Res<ServerConnection>,
+    mut browser: ResMut<RoomBrowser>,
+) {
+    browser.timer.tick(time.delta());
+    if !browser.timer.just_finished() && !browser.stale {
+        return;
+    }
+    browser.stale = false;
+
+    let mut message = json!({ "action": "list_rooms", "page": browser.page });
+    if let Some(kind) = browser.kind {
+        message["kind"] = json!(kind);
+    }
+    if let Some(mode) = browser.mode {
+        message["mode"] = json!(mode.as_str());
+    }
+    if let Some(name) = &browser.name {
+        message["name"] = json!(name);
+    }
+
+    connection.send(&message);
+}
+
+pub fn receive_rooms(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut message_reader: EventReader<ServerMessage>,
+    mut browser: ResMut<RoomBrowser>,
+    list_query: Query<Entity, With<RoomList>>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("rooms") {
+            continue;
+        }
+
+        browser.pages = json["pages"].as_u64().unwrap_or(1).max(1) as usize;
+        browser.total = json["total"].as_u64().unwrap_or(0) as usize;
+
+        // Rooms closed while we were on the last page
+        if browser.page >= browser.pages {
+            browser.page = browser.pages - 1;
+            browser.stale = true;
+        }
+
+        let rows: Vec<(String, Value)> = json["rooms"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(describe_room)
+            .collect();
+
+        for list in list_query.iter() {
+            commands.entity(list).despawn_descendants();
+            commands.entity(list).with_children(|parent| {
+                for (label, message) in &rows {
+                    parent
+                        .spawn((
+                            RoomRow(message.clone()),
+                            Button,
+                            Node {
+                                padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                                ..default()
+                            },
+                            BackgroundColor(ROW_COLOR),
+                        ))
+                        .with_child((
+                            Text::new(label.clone()),
+                            TextFont {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(BROWSER_COLOR),
+                        ));
+                }
+            });
+        }
+    }
+}
+
+// What the row says, and the message clicking it sends
+fn describe_room(room: &Value) -> Option<(String, Value)> {
+    let names = |team: &Value| {
+        team.as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match room["kind"].as_str()? {
+        "open" => {
+            let code = room["code"].as_str()?;
+            let modes = room["modes"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|mode| match mode.as_str() {
+                    Some("doubles") => Some("2v2"),
+                    Some("singles") => Some("1v1"),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            let label = format!(
+                "Party {} {}: {} ({} free), join",
+                code,
+                modes,
+                names(&room["players"]),
+                room["slots"].as_u64().unwrap_or(0)
+            );
+            Some((label, json!({ "action": "party_join", "code": code })))
+        }
+
+        "live" => {
+            let room_id = room["room_id"].as_str()?;
+            let right = match room["ai"].as_str() {
+                Some(difficulty) if room["right"].as_array().is_none_or(Vec::is_empty) => {
+                    format!("{difficulty} AI")
+                }
+                _ => names(&room["right"]),
+            };
+            let mode = match room["mode"].as_str() {
+                Some("doubles") => "2v2",
+                _ => "1v1",
+            };
+            let label = format!(
+                "{} {} vs {} {} - {} ({} watching), watch",
+                mode,
+                names(&room["left"]),
+                right,
+                room["score"]["left"].as_u64().unwrap_or(0),
+                room["score"]["right"].as_u64().unwrap_or(0),
+                room["spectators"].as_u64().unwrap_or(0)
+            );
+            Some((label, json!({ "action": "spectate", "room_id": room_id })))
+        }
+
+        _ => None,
+    }
+}
+
+pub fn label_browser_buttons(
+    browser: Res<RoomBrowser>,
+    button_query: Query<(&BrowserButton, &Children)>,
+    mut text_query: Query<&mut Text, Without<PageText>>,
+    mut page_query: Query<&mut Text, With<PageText>>,
+) {
+    if !browser.is_changed() {
+        return;
+    }
+
+    for (button, children) in button_query.iter() {
+        let label = match button {
+            BrowserButton::Kind => match browser.kind {
+                Some("open") => "Show: parties".to_string(),
+                Some(_) => "Show: matches".to_string(),
+                None => "Show: all".to_string(),
+            },
+            BrowserButton::Mode => match browser.mode {
+                Some(GameMode::Singles) => "Mode: 1v1".to_string(),
+                Some(GameMode::Doubles) => "Mode: 2v2".to_string(),
+                None => "Mode: any".to_string(),
+            },
+            BrowserButton::Previous => "<".to_string(),
+            BrowserButton::Next => ">".to_string(),
+            BrowserButton::Refresh => "Refresh".to_string(),
+        };
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            if **text != label {
+                **text = label;
+            }
+        }
+    }
+
+    let mut page = format!(
+        "Rooms: {} - page {} of {}",
+        browser.total,
+        browser.page + 1,
+        browser.pages
+    );
+    if let Some(name) = &browser.name {
+        page.push_str(&format!(" - with \"{name}\" (/find to clear)"));
+    }
+    for mut text in page_query.iter_mut() {
+        if **text != page {
+            **text = page.clone();
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct BracketText {}
+
+pub struct BracketMatch {
+    pub stage: String,
+    pub round: u64,
+
+    // Names, None while waiting on another match
+    pub players: [Option<String>; 2],
+    pub winner: Option<usize>,
+    pub live: bool,
+}
+
+// The tournament as the server last described it, replaced on every change
+#[derive(Resource)]
+pub struct Bracket {
+    pub format: String,
+    pub state: String,
+
+    // With their rating, by seed once started
+    pub entrants: Vec<(String, i64)>,
+    pub matches: Vec<BracketMatch>,
+    pub champion: Option<String>,
+}
@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+use system::{receive_bracket, run_tournament_commands, show_bracket, spawn_bracket_panel};
+
+use crate::AppState;
+
+pub mod components;
+pub mod system;
+
+// Tournaments are opened, entered and started with chat commands in the
+// lobby. The bracket shows there and, holding B, during a match.
+pub struct TournamentPlugin;
+
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_bracket_panel)
+            .add_systems(Update, (receive_bracket, show_bracket).chain())
+            .add_systems(
+                Update,
+                run_tournament_commands.run_if(in_state(AppState::Lobby)),
+            );
+    }
+}
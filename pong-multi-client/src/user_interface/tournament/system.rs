@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+use serde_json::{json, Value};
+
+use crate::{
+    game::local::resource::LocalMatch,
+    network::resource::{ServerConnection, ServerMessage},
+    user_interface::chat::components::{ChatCommand, ChatDraft},
+    AppState,
+};
+
+use super::components::{Bracket, BracketMatch, BracketText};
+
+const BRACKET_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+
+// Left to the party commands to answer anything else
+pub const TOURNAMENT_COMMANDS: [&str; 4] = ["/tournament", "/register", "/unregister", "/start"];
+
+const TOURNAMENT_HELP: &str = "/tournament [double]  /register  /unregister  /start";
+
+// Results kept on screen, the bracket of a big tournament doesn't fit otherwise
+const SHOWN_RESULTS: usize = 4;
+
+pub fn spawn_bracket_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        BracketText {},
+        Text::new(""),
+        TextFont {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(BRACKET_COLOR),
+        TextLayout::new_with_justify(JustifyText::Right),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            ..default()
+        },
+        // Over the welcome screen, which stays up in the lobby
+        GlobalZIndex(1),
+        Visibility::Hidden,
+    ));
+}
+
+pub fn receive_bracket(mut commands: Commands, mut message_reader: EventReader<ServerMessage>) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("bracket") {
+            continue;
+        }
+
+        let text = |value: &Value| value.as_str().map(str::to_string);
+        let list = |value: &Value| value.as_array().cloned().unwrap_or_default();
+
+        commands.insert_resource(Bracket {
+            format: text(&json["format"]).unwrap_or_default(),
+            state: text(&json["state"]).unwrap_or_default(),
+            entrants: list(&json["entrants"])
+                .iter()
+                .filter_map(|entrant| {
+                    Some((text(&entrant["name"])?, entrant["rating"].as_f64()? as i64))
+                })
+                .collect(),
+            matches: list(&json["matches"])
+                .iter()
+                .map(|played| BracketMatch {
+                    stage: text(&played["stage"]).unwrap_or_default(),
+                    round: played["round"].as_u64().unwrap_or(0),
+                    players: [0, 1].map(|side| text(&played["players"][side])),
+                    winner: played["winner"].as_u64().map(|winner| winner as usize),
+                    live: played["live"].as_bool().unwrap_or(false),
+                })
+                .collect(),
+            champion: text(&json["champion"]),
+        });
+    }
+}
+
+pub fn run_tournament_commands(
+    mut command_reader: EventReader<ChatCommand>,
+    connection: Res<ServerConnection>,
+) {
+    for ChatCommand(line) in command_reader.read() {
+        let mut words = line.split_whitespace();
+
+        let message = match (words.next(), words.next()) {
+            (Some("/tournament"), Some("double")) => {
+                json!({ "action": "tournament_create", "format": "double" })
+            }
+            (Some("/tournament"), _) => json!({ "action": "tournament_create" }),
+            (Some("/register"), _) => json!({ "action": "tournament_register" }),
+            (Some("/unregister"), _) => json!({ "action": "tournament_unregister" }),
+            (Some("/start"), _) => json!({ "action": "tournament_start" }),
+            _ => continue,
+        };
+
+        connection.send(&message);
+    }
+}
+
+// Always up in the lobby, in a match only while B is held
+pub fn show_bracket(
+    state: Res<State<AppState>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    local: Option<Res<LocalMatch>>,
+    draft: Option<Res<ChatDraft>>,
+    bracket: Option<Res<Bracket>>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<BracketText>>,
+) {
+    let in_lobby = *state.get() == AppState::Lobby;
+    let visible = local.is_none()
+        && match state.get() {
+            AppState::Lobby | AppState::Matching => true,
+            AppState::InGame => draft.is_none() && keyboard.pressed(KeyCode::KeyB),
+            AppState::Welcome => false,
+        };
+
+    let text = match &bracket {
+        Some(bracket) => describe(bracket),
+        None => "No tournament yet".to_string(),
+    };
+    let text = if in_lobby {
+        format!("{text}\n\n{TOURNAMENT_HELP}")
+    } else {
+        text
+    };
+
+    for (mut bracket_text, mut visibility) in text_query.iter_mut() {
+        *visibility = if visible && (bracket.is_some() || in_lobby) {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+        if **bracket_text != text {
+            **bracket_text = text.clone();
+        }
+    }
+}
+
+fn describe(bracket: &Bracket) -> String {
+    let format = match bracket.format.as_str() {
+        "double" => "double elimination",
+        _ => "single elimination",
+    };
+    let mut text = format!("Tournament, {} ({})", format, bracket.state);
+
+    if bracket.state == "registering" {
+        for (name, rating) in &bracket.entrants {
+            text.push_str(&format!("\n{name} {rating}"));
+        }
+        if bracket.entrants.is_empty() {
+            text.push_str("\nNobody registered yet");
+        }
+        return text;
+    }
+
+    let (played, to_play): (Vec<&BracketMatch>, Vec<&BracketMatch>) = bracket
+        .matches
+        .iter()
+        .partition(|played| played.winner.is_some());
+
+    let skipped = played.len().saturating_sub(SHOWN_RESULTS);
+    if skipped > 0 {
+        text.push_str(&format!("\n{skipped} earlier matches"));
+    }
+    for played in played.iter().skip(skipped).chain(&to_play) {
+        text.push_str(&format!("\n{}", describe_match(played)));
+    }
+
+    if let Some(champion) = &bracket.champion {
+        text.push_str(&format!("\nChampion: {champion}"));
+    }
+
+    text
+}
+
+fn describe_match(played: &BracketMatch) -> String {
+    let stage = match played.stage.as_str() {
+        "losers" => format!("Losers {}", played.round),
+        "grand_final" => "Grand final".to_string(),
+        _ => format!("Winners {}", played.round),
+    };
+    let [first, second] = played
+        .players
+        .clone()
+        .map(|name| name.unwrap_or_else(|| "?".to_string()));
+
+    match played.winner {
+        Some(0) => format!("{stage}: {first} beat {second}"),
+        Some(_) => format!("{stage}: {second} beat {first}"),
+        None if played.live => format!("{stage}: {first} vs {second}, playing"),
+        None => format!("{stage}: {first} vs {second}"),
+    }
+}
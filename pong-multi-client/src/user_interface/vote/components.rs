@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct BallotText {}
+
+// The vote running in our room, until the server says which map won
+#[derive(Resource)]
+pub struct Ballot {
+    pub room_id: String,
+    pub maps: Vec<String>,
+    pub picked: Option<usize>,
+    pub seconds: u64,
+}
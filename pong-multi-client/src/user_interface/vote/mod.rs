@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+use system::{show_ballot, vote_with_keys};
+
+use super::chat::components::ChatDraft;
+
+pub mod components;
+pub mod system;
+
+// The maps to vote on before a match, picked with the number keys unless typing in the chat
+pub struct VotePlugin;
+
+impl Plugin for VotePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                show_ballot,
+                vote_with_keys.run_if(not(resource_exists::<ChatDraft>)),
+            )
+                .chain(),
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use serde_json::json;
+
+use crate::network::resource::{ServerConnection, ServerMessage};
+
+use super::components::{Ballot, BallotText};
+
+const BALLOT_COLOR: Color = Color::WHITE;
+const PICKED_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
+
+const NUMBER_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+pub fn show_ballot(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut message_reader: EventReader<ServerMessage>,
+    ballot_query: Query<Entity, With<BallotText>>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        match json["action"].as_str() {
+            Some("map_vote") => {
+                let (Some(room_id), Some(maps)) =
+                    (json["room_id"].as_str(), json["maps"].as_array())
+                else {
+                    continue;
+                };
+
+                let ballot = Ballot {
+                    room_id: room_id.to_string(),
+                    maps: maps
+                        .iter()
+                        .filter_map(|map| map.as_str())
+                        .map(str::to_string)
+                        .collect(),
+                    picked: None,
+                    seconds: json["seconds"].as_u64().unwrap_or(0),
+                };
+
+                commands.spawn((
+                    BallotText {},
+                    Text::new(ballot_text(&ballot)),
+                    TextFont {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(BALLOT_COLOR),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: Val::Percent(30.0),
+                        left: Val::Percent(40.0),
+                        ..default()
+                    },
+                ));
+                commands.insert_resource(ballot);
+            }
+
+            Some("map_picked") => {
+                for entity in ballot_query.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                commands.remove_resource::<Ballot>();
+            }
+
+            _ => {}
+        }
+    }
+}
+
+// Changing the vote is allowed until everyone has voted
+pub fn vote_with_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    connection: Res<ServerConnection>,
+    ballot: Option<ResMut<Ballot>>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<BallotText>>,
+) {
+    let Some(mut ballot) = ballot else {
+        return;
+    };
+
+    let Some(index) = NUMBER_KEYS
+        .iter()
+        .take(ballot.maps.len())
+        .position(|key| keyboard.just_pressed(*key))
+    else {
+        return;
+    };
+
+    connection.send(&json!({
+        "action": "vote",
+        "room_id": ballot.room_id,
+        "map": ballot.maps[index],
+    }));
+    ballot.picked = Some(index);
+
+    for (mut text, mut color) in text_query.iter_mut() {
+        text.0 = ballot_text(&ballot);
+        color.0 = PICKED_COLOR;
+    }
+}
+
+fn ballot_text(ballot: &Ballot) -> String {
+    let mut text = format!("Pick the map ({} s)\n", ballot.seconds);
+
+    for (index, map) in ballot.maps.iter().enumerate() {
+        let marker = if ballot.picked == Some(index) {
+            ">"
+        } else {
+            " "
+        };
+        text.push_str(&format!("{marker} {}: {map}\n", index + 1));
+    }
+
+    text
+}
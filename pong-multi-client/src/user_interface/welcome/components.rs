@@ -6,5 +6,29 @@ pub struct WelcomeScreen {}
 #[derive(Component)]
 pub struct EnterButton {}
 
+// Enters the server without queueing, to set up a party first
+#[derive(Component)]
+pub struct LobbyButton {}
+
 #[derive(Component)]
 pub struct ExitButton {}
+
+// Starts a match against the AI on this machine, no server needed
+#[derive(Component)]
+pub struct OfflineButton {}
+
+// Two people on this machine, one on each side
+#[derive(Component)]
+pub struct HotSeatButton {}
+
+// Switches between singles and doubles before entering
+#[derive(Component)]
+pub struct ModeButton {}
+
+// Cycles through who to play against: players, the AI after a wait, or practice
+#[derive(Component)]
+pub struct OpponentButton {}
+
+// Why the server refused us, empty until it does
+#[derive(Component)]
+pub struct RejectionText {}
@@ -1,5 +1,12 @@
 use bevy::prelude::*;
-use system::{button_system, exit_button_system, generate_random_name, spawn_welcome_screen};
+use pong_multi_shared::game::{ai::Difficulty, formation::GameMode};
+use system::{
+    button_system, despawn_welcome_screen, enter_button_system, exit_button_system,
+    generate_random_name, hot_seat_button_system, lobby_button_system, mode_button_system,
+    offline_button_system, opponent_button_system, show_rejection, spawn_welcome_screen,
+};
+
+use crate::AppState;
 
 pub mod components;
 pub mod styles;
@@ -8,15 +15,60 @@ pub mod system;
 pub struct WelcomePlugin;
 
 #[derive(Resource, Default)]
-pub struct PlayerName(String);
+pub struct PlayerName(pub String);
+
+// Mode asked for when joining the queue
+#[derive(Resource, Default)]
+pub struct SelectedMode(pub GameMode);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Opponent {
+    #[default]
+    Players,
+
+    // Play the AI if nobody shows up for a while
+    PlayersOrAi,
+    Practice(Difficulty),
+}
+
+impl Opponent {
+    pub const ALL: [Opponent; 5] = [
+        Opponent::Players,
+        Opponent::PlayersOrAi,
+        Opponent::Practice(Difficulty::Easy),
+        Opponent::Practice(Difficulty::Normal),
+        Opponent::Practice(Difficulty::Hard),
+    ];
+}
+
+// Who to play against, sent with the join request
+#[derive(Resource, Default)]
+pub struct SelectedOpponent(pub Opponent);
 
 impl Plugin for WelcomePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PlayerName>()
+            .init_resource::<SelectedMode>()
+            .init_resource::<SelectedOpponent>()
             .add_systems(
                 Startup,
                 (generate_random_name, spawn_welcome_screen).chain(),
             )
-            .add_systems(Update, (button_system, exit_button_system));
+            .add_systems(
+                Update,
+                (
+                    button_system,
+                    enter_button_system.run_if(in_state(AppState::Welcome)),
+                    lobby_button_system.run_if(in_state(AppState::Welcome)),
+                    mode_button_system.run_if(in_state(AppState::Welcome)),
+                    opponent_button_system.run_if(in_state(AppState::Welcome)),
+                    offline_button_system.run_if(in_state(AppState::Welcome)),
+                    hot_seat_button_system.run_if(in_state(AppState::Welcome)),
+                    exit_button_system,
+                    show_rejection
+                        .run_if(in_state(AppState::Matching).or(in_state(AppState::Lobby))),
+                ),
+            )
+            .add_systems(OnEnter(AppState::InGame), despawn_welcome_screen);
     }
 }
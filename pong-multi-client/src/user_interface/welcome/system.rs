@@ -1,10 +1,30 @@
+use std::time::Duration;
+
 use bevy::app::AppExit;
 use bevy::prelude::*;
+use pong_multi_shared::{
+    game::{
+        ai::Difficulty,
+        formation::{Formation, GameMode, Seat, DEFAULT_DOUBLES_DEPTHS},
+        Side, TICK_RATE,
+    },
+    protocol::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+};
 use rand::{rng, seq::IndexedRandom, Rng};
+use serde_json::json;
+
+use crate::{
+    game::{interpolation::resource::InterpolationSettings, local::resource::LocalMatch},
+    network::resource::{MatchInfo, ServerConnection, ServerMessage},
+    AppState,
+};
 
 use super::{
-    components::{EnterButton, ExitButton},
-    PlayerName,
+    components::{
+        EnterButton, ExitButton, HotSeatButton, LobbyButton, ModeButton, OfflineButton,
+        OpponentButton, RejectionText, WelcomeScreen,
+    },
+    Opponent, PlayerName, SelectedMode, SelectedOpponent,
 };
 
 pub fn generate_random_name(mut commands: Commands) {
@@ -29,6 +49,12 @@ const NORMAL_BUTTON: Color = Color::srgb(0., 1.0, 0.);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.75, 0.25);
 const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.85, 0.35);
 
+const REJECTION_COLOR: Color = Color::srgb(1.0, 0.4, 0.4);
+
+const MODE_BUTTON: Color = Color::srgb(0.4, 0.7, 1.0);
+
+const OFFLINE_BUTTON: Color = Color::srgb(1.0, 0.8, 0.2);
+
 // Exit Button Colors
 const EXIT_NORMAL: Color = Color::srgb(1.0, 0.0, 0.0);
 const EXIT_HOVERED: Color = Color::srgb(0.75, 0.25, 0.25);
@@ -43,6 +69,7 @@ pub fn spawn_welcome_screen(
 
     commands
         .spawn((
+            WelcomeScreen {},
             Node {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
@@ -122,6 +149,146 @@ pub fn spawn_welcome_screen(
                     TextColor(NORMAL_BUTTON),
                 ));
         })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    LobbyButton {},
+                    Button,
+                    Node {
+                        width: Val::Px(220.),
+                        height: Val::Px(50.),
+
+                        border: UiRect::all(Val::Px(5.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BorderColor(NORMAL_BUTTON),
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                ))
+                .with_child((
+                    Text::new("Party with friends"),
+                    TextFont {
+                        font: font.clone_weak(),
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                    TextColor(NORMAL_BUTTON),
+                ));
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    OfflineButton {},
+                    Button,
+                    Node {
+                        width: Val::Px(220.),
+                        height: Val::Px(50.),
+
+                        border: UiRect::all(Val::Px(5.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BorderColor(OFFLINE_BUTTON),
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                ))
+                .with_child((
+                    Text::new("Play offline"),
+                    TextFont {
+                        font: font.clone_weak(),
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                    TextColor(OFFLINE_BUTTON),
+                ));
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    HotSeatButton {},
+                    Button,
+                    Node {
+                        width: Val::Px(220.),
+                        height: Val::Px(50.),
+
+                        border: UiRect::all(Val::Px(5.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BorderColor(OFFLINE_BUTTON),
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                ))
+                .with_child((
+                    Text::new("2 players, 1 keyboard"),
+                    TextFont {
+                        font: font.clone_weak(),
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                    TextColor(OFFLINE_BUTTON),
+                ));
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ModeButton {},
+                    Button,
+                    Node {
+                        width: Val::Px(220.),
+                        height: Val::Px(50.),
+
+                        border: UiRect::all(Val::Px(5.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BorderColor(MODE_BUTTON),
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                ))
+                .with_child((
+                    Text::new(mode_label(GameMode::default())),
+                    TextFont {
+                        font: font.clone_weak(),
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                    TextColor(MODE_BUTTON),
+                ));
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    OpponentButton {},
+                    Button,
+                    Node {
+                        width: Val::Px(220.),
+                        height: Val::Px(50.),
+
+                        border: UiRect::all(Val::Px(5.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BorderColor(MODE_BUTTON),
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                ))
+                .with_child((
+                    Text::new(opponent_label(Opponent::default())),
+                    TextFont {
+                        font: font.clone_weak(),
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                    TextColor(MODE_BUTTON),
+                ));
+        })
         .with_children(|parent| {
             parent
                 .spawn((
@@ -149,9 +316,22 @@ pub fn spawn_welcome_screen(
                     },
                     TextColor(EXIT_NORMAL),
                 ));
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                RejectionText {},
+                Text::new(""),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(REJECTION_COLOR),
+            ));
         });
 }
 
+#[allow(clippy::type_complexity)]
 pub fn button_system(
     mut interaction_query: Query<
         (&Interaction, &mut BorderColor, &Children),
@@ -179,14 +359,250 @@ pub fn button_system(
     }
 }
 
-pub fn exit_button_system(
-    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ExitButton>)>,
-    mut exit_event_writer: EventWriter<AppExit>,
+// Register on the server, the handshake and the join request follow once it answers.
+// The versions we speak let the server pick one, or tell us to update.
+pub fn enter_button_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<EnterButton>)>,
+    mut rejection_query: Query<&mut Text, With<RejectionText>>,
+    connection: Res<ServerConnection>,
+    player_name: Res<PlayerName>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            for mut text in rejection_query.iter_mut() {
+                text.clear();
+            }
+
+            send_enter(&connection, &player_name);
+            next_state.set(AppState::Matching);
+        }
+    }
+}
+
+// Same as entering, but the handshake isn't followed by a join. Parties are
+// made in the lobby and their leader queues from there.
+pub fn lobby_button_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<LobbyButton>)>,
+    mut rejection_query: Query<&mut Text, With<RejectionText>>,
+    connection: Res<ServerConnection>,
+    player_name: Res<PlayerName>,
+    mut next_state: ResMut<NextState<AppState>>,
 ) {
     for interaction in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
-            println!("Exit button pressed! Quitting game...");
-            exit_event_writer.send(AppExit::Success);
+            for mut text in rejection_query.iter_mut() {
+                text.clear();
+            }
+
+            send_enter(&connection, &player_name);
+            next_state.set(AppState::Lobby);
+        }
+    }
+}
+
+fn send_enter(connection: &ServerConnection, player_name: &PlayerName) {
+    connection.send(&json!({
+        "action": "enter",
+        "name": player_name.0,
+        "protocol": PROTOCOL_VERSION,
+        "min_protocol": MIN_PROTOCOL_VERSION,
+        "build": env!("CARGO_PKG_VERSION"),
+    }));
+}
+
+// Everything runs here against the AI, at the practice difficulty if one is picked
+pub fn offline_button_system(
+    mut commands: Commands,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<OfflineButton>)>,
+    selected_mode: Res<SelectedMode>,
+    selected_opponent: Res<SelectedOpponent>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let difficulty = match selected_opponent.0 {
+            Opponent::Practice(difficulty) => difficulty,
+            _ => Difficulty::default(),
+        };
+        println!("Playing offline against the {} AI", difficulty);
+
+        let formation = local_formation(selected_mode.0);
+        let local = LocalMatch::against_ai(formation, difficulty);
+        start_local_match(&mut commands, local, Some(difficulty));
+        next_state.set(AppState::InGame);
+    }
+}
+
+// Left against right on the same keyboard, the AI plays the teammates in doubles
+pub fn hot_seat_button_system(
+    mut commands: Commands,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<HotSeatButton>)>,
+    selected_mode: Res<SelectedMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        println!("Playing hot seat, W/S against the arrows");
+
+        let formation = local_formation(selected_mode.0);
+        let ai = (formation.mode == GameMode::Doubles).then(Difficulty::default);
+        start_local_match(&mut commands, LocalMatch::hot_seat(formation), ai);
+        next_state.set(AppState::InGame);
+    }
+}
+
+fn local_formation(mode: GameMode) -> Formation {
+    match mode {
+        GameMode::Singles => Formation::singles(),
+        GameMode::Doubles => Formation::doubles(DEFAULT_DOUBLES_DEPTHS),
+    }
+}
+
+// Snapshots come from the local simulation every tick, no need to render in the past
+fn start_local_match(commands: &mut Commands, local: LocalMatch, ai: Option<Difficulty>) {
+    commands.insert_resource(MatchInfo {
+        room_id: "local".to_string(),
+        seat: Seat::new(Side::Left, 0),
+        formation: local.state.formation.clone(),
+        ai,
+        spectating: false,
+    });
+    commands.insert_resource(InterpolationSettings {
+        delay: Duration::from_secs_f64(1.0 / TICK_RATE as f64),
+        max_extrapolation: Duration::ZERO,
+    });
+    commands.insert_resource(local);
+}
+
+// The server won't let us in, say why and go back to the welcome screen
+pub fn show_rejection(
+    mut message_reader: EventReader<ServerMessage>,
+    mut rejection_query: Query<&mut Text, With<RejectionText>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for ServerMessage(json) in message_reader.read() {
+        if json["action"].as_str() != Some("rejected") {
+            continue;
+        }
+
+        let reason = json["reason"]
+            .as_str()
+            .unwrap_or("The server refused the connection");
+        println!("Rejected by the server: {}", reason);
+
+        for mut text in rejection_query.iter_mut() {
+            **text = reason.to_string();
+        }
+        next_state.set(AppState::Welcome);
+    }
+}
+
+fn mode_label(mode: GameMode) -> String {
+    match mode {
+        GameMode::Singles => "Mode: 1 vs 1".to_string(),
+        GameMode::Doubles => "Mode: 2 vs 2".to_string(),
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn mode_button_system(
+    interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<ModeButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut selected_mode: ResMut<SelectedMode>,
+) {
+    for (interaction, children) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        selected_mode.0 = match selected_mode.0 {
+            GameMode::Singles => GameMode::Doubles,
+            GameMode::Doubles => GameMode::Singles,
+        };
+
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            **text = mode_label(selected_mode.0);
+        }
+    }
+}
+
+fn opponent_label(opponent: Opponent) -> String {
+    match opponent {
+        Opponent::Players => "Vs: players".to_string(),
+        Opponent::PlayersOrAi => "Vs: players or AI".to_string(),
+        Opponent::Practice(difficulty) => format!("Practice: {} AI", difficulty),
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn opponent_button_system(
+    interaction_query: Query<
+        (&Interaction, &Children),
+        (Changed<Interaction>, With<OpponentButton>),
+    >,
+    mut text_query: Query<&mut Text>,
+    mut selected_opponent: ResMut<SelectedOpponent>,
+) {
+    for (interaction, children) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let index = Opponent::ALL
+            .iter()
+            .position(|opponent| *opponent == selected_opponent.0)
+            .unwrap_or(0);
+        selected_opponent.0 = Opponent::ALL[(index + 1) % Opponent::ALL.len()];
+
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            **text = opponent_label(selected_opponent.0);
+        }
+    }
+}
+
+// Fields of the join request for the opponent picked
+pub fn add_opponent(message: &mut serde_json::Value, opponent: Opponent) {
+    match opponent {
+        Opponent::Players => {}
+        Opponent::PlayersOrAi => message["ai_fallback"] = json!(Difficulty::default().as_str()),
+        Opponent::Practice(difficulty) => message["practice"] = json!(difficulty.as_str()),
+    }
+}
+
+pub fn despawn_welcome_screen(
+    mut commands: Commands,
+    welcome_query: Query<Entity, With<WelcomeScreen>>,
+) {
+    for entity in welcome_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn exit_button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut BorderColor),
+        (Changed<Interaction>, With<ExitButton>),
+    >,
+    mut exit_event_writer: EventWriter<AppExit>,
+) {
+    for (interaction, mut border_color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => border_color.0 = EXIT_HOVERED,
+            Interaction::Pressed => {
+                border_color.0 = EXIT_PRESSED;
+
+                println!("Exit button pressed! Quitting game...");
+                exit_event_writer.send(AppExit::Success);
+            }
+            Interaction::None => border_color.0 = EXIT_NORMAL,
         }
     }
 }
@@ -0,0 +1,518 @@
+// Headless bots that play against a running server and report how it holds up.
+//
+//   cargo run --release --bin load_test -- --clients 2000 --ramp-up 20 --play 30
+//
+// Each bot does enter -> handshake -> join -> plays random moves -> leave over its own UDP socket.
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use pong_multi_server::network::room::SNAPSHOT_INTERVAL;
+use pong_multi_shared::{
+    fragment::{reassembler::Reassembler, Fragmenter, MAX_PACKET_SIZE},
+    game::TICK_DT,
+    protocol::{Decoder, Encoder, WireFormat, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+    security::{
+        key_from_hex,
+        packet::{is_secure, Session},
+        to_hex, Handshake, Role,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::{json, Value};
+use tokio::{net::UdpSocket, time::timeout};
+
+// How often a bot measures its round trip time
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+// How many times the unacknowledged steps of the connection are sent again
+const RETRIES: u32 = 3;
+
+#[derive(Debug, Clone)]
+struct Config {
+    server: SocketAddr,
+    clients: usize,
+    ramp_up: Duration,
+    play: Duration,
+    match_timeout: Duration,
+    reply_timeout: Duration,
+    encrypt: bool,
+}
+
+impl Config {
+    fn from_args() -> Result<Self, String> {
+        let mut config = Config {
+            server: "127.0.0.1:8090".parse().unwrap(),
+            clients: 100,
+            ramp_up: Duration::from_secs(10),
+            play: Duration::from_secs(30),
+            match_timeout: Duration::from_secs(30),
+            reply_timeout: Duration::from_secs(2),
+            encrypt: false,
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--encrypt" {
+                config.encrypt = true;
+                continue;
+            }
+
+            let value = args.next().ok_or(format!("Missing value for {arg}"))?;
+            let invalid = || format!("Invalid value for {arg}: {value}");
+
+            match arg.as_str() {
+                "--server" => config.server = value.parse().map_err(|_| invalid())?,
+                "--clients" => config.clients = value.parse().map_err(|_| invalid())?,
+                "--ramp-up" => config.ramp_up = parse_secs(&value).ok_or_else(invalid)?,
+                "--play" => config.play = parse_secs(&value).ok_or_else(invalid)?,
+                "--match-timeout" => {
+                    config.match_timeout = parse_secs(&value).ok_or_else(invalid)?
+                }
+                _ => return Err(format!("Unknown option {arg}")),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_secs(value: &str) -> Option<Duration> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+        * 1000.0
+}
+
+// What a single bot saw during its run
+#[derive(Debug, Default)]
+struct Report {
+    matched: bool,
+    matchmaking_ms: Option<f64>,
+    round_trips_ms: Vec<f64>,
+    snapshots_received: u64,
+    snapshots_expected: u64,
+    errors: Vec<String>,
+}
+
+struct Bot {
+    socket: UdpSocket,
+    session: Option<Session>,
+    fragmenter: Fragmenter,
+    reassembler: Reassembler,
+    encoder: Encoder,
+    decoder: Decoder,
+    rng: StdRng,
+    report: Report,
+}
+
+impl Bot {
+    async fn connect(config: &Config) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .await
+            .map_err(|e| format!("bind failed: {}", e.kind()))?;
+        socket
+            .connect(config.server)
+            .await
+            .map_err(|e| format!("connect failed: {}", e.kind()))?;
+
+        Ok(Self {
+            socket,
+            session: None,
+            fragmenter: Fragmenter::default(),
+            reassembler: Reassembler::default(),
+            encoder: Encoder::new(WireFormat::Binary),
+            decoder: Decoder::default(),
+            rng: StdRng::from_os_rng(),
+            report: Report::default(),
+        })
+    }
+
+    async fn send(&mut self, message: &Value) {
+        let payload = self.encoder.encode(message);
+        let packet = match self.session.as_mut() {
+            Some(session) => session.seal(&payload),
+            None => payload,
+        };
+
+        for fragment in self.fragmenter.split(&packet) {
+            if let Err(e) = self.socket.send(&fragment).await {
+                self.report
+                    .errors
+                    .push(format!("send failed: {}", e.kind()));
+            }
+        }
+    }
+
+    // Next message from the server, None if nothing came in time
+    async fn receive(&mut self, wait: Duration) -> Option<Value> {
+        let mut buf = [0; MAX_PACKET_SIZE];
+        let deadline = Instant::now() + wait;
+
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let len = match timeout(left, self.socket.recv(&mut buf)).await {
+                Ok(Ok(len)) => len,
+                Ok(Err(e)) => {
+                    self.report
+                        .errors
+                        .push(format!("receive failed: {}", e.kind()));
+                    continue;
+                }
+                Err(_) => return None,
+            };
+
+            let Some(packet) = self
+                .reassembler
+                .receive(Instant::now(), buf[..len].to_vec())
+            else {
+                continue;
+            };
+
+            let payload = match self.session.as_mut() {
+                Some(session) if is_secure(&packet) => match session.open(&packet) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        self.report.errors.push(format!("bad packet: {}", e));
+                        continue;
+                    }
+                },
+                _ => packet,
+            };
+
+            match self.decoder.decode(&payload) {
+                Ok(message) => return Some(message),
+                Err(e) => self.report.errors.push(format!("bad message: {}", e)),
+            }
+        }
+    }
+
+    // Wait for a given action, ignoring anything else the server sends meanwhile
+    async fn expect(&mut self, action: &str, wait: Duration) -> Result<Value, String> {
+        let deadline = Instant::now() + wait;
+
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let Some(message) = self.receive(left).await else {
+                return Err(format!("{action} timed out"));
+            };
+
+            match message["action"].as_str() {
+                Some(received) if received == action => return Ok(message),
+                Some("kicked") => return Err(format!("kicked: {}", message["reason"])),
+                _ => {}
+            }
+        }
+    }
+
+    // Send a message until the expected answer comes back
+    async fn request(
+        &mut self,
+        message: &Value,
+        action: &str,
+        wait: Duration,
+    ) -> Result<Value, String> {
+        let mut result = Err(format!("{action} timed out"));
+        for _ in 0..RETRIES {
+            self.send(message).await;
+            result = self.expect(action, wait).await;
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    async fn run(mut self, config: &Config) -> Report {
+        if let Err(e) = self.play(config).await {
+            self.report.errors.push(e);
+        }
+
+        // Be a good citizen even after an error, the server can free the room right away
+        if self.session.is_some() {
+            self.send(&json!({ "action": "leave" })).await;
+        }
+
+        self.report
+    }
+
+    async fn play(&mut self, config: &Config) -> Result<(), String> {
+        let enter = json!({
+            "action": "enter",
+            "protocol": PROTOCOL_VERSION,
+            "min_protocol": MIN_PROTOCOL_VERSION,
+            "build": env!("CARGO_PKG_VERSION"),
+        });
+        let entered = self
+            .request(&enter, "entered", config.reply_timeout)
+            .await?;
+        if let Some(protocol) = entered["protocol"].as_u64() {
+            self.encoder.version = protocol as u8;
+        }
+
+        let handshake = Handshake::new();
+        let request = json!({
+            "action": "handshake",
+            "public_key": to_hex(&handshake.public_key),
+            "encrypt": config.encrypt,
+        });
+        let reply = self
+            .request(&request, "handshake", config.reply_timeout)
+            .await?;
+        let server_key = reply["public_key"]
+            .as_str()
+            .and_then(key_from_hex)
+            .ok_or("invalid handshake reply")?;
+        let encrypted = reply["encrypt"].as_bool().unwrap_or(false);
+        self.session = Some(handshake.complete(Role::Client, server_key, encrypted));
+
+        let joined_at = Instant::now();
+        self.send(&json!({ "action": "join" })).await;
+        let found = self.expect("match_found", config.match_timeout).await?;
+        let room_id = found["room_id"].as_str().unwrap_or_default().to_string();
+
+        self.report.matched = true;
+        self.report.matchmaking_ms = Some(joined_at.elapsed().as_secs_f64() * 1000.0);
+
+        self.play_match(config, &room_id).await
+    }
+
+    async fn play_match(&mut self, config: &Config, room_id: &str) -> Result<(), String> {
+        let end = Instant::now() + config.play;
+        let mut next_tick = Instant::now();
+        let mut next_ping = Instant::now();
+
+        let mut seq = 0u32;
+        let mut direction = 0i8;
+        let mut server_tick = 0u64;
+        let mut first_snapshot: Option<u64> = None;
+
+        while Instant::now() < end {
+            let now = Instant::now();
+
+            if now >= next_tick {
+                next_tick += Duration::from_secs_f32(TICK_DT);
+
+                // Hold a direction for a while like a person would
+                if self.rng.random_bool(0.05) {
+                    direction = self.rng.random_range(-1..=1);
+                }
+
+                if direction != 0 {
+                    seq += 1;
+                    let message = json!({
+                        "action": "move",
+                        "room_id": room_id,
+                        "seq": seq,
+                        "direction": direction,
+                        "tick": server_tick,
+                    });
+                    self.send(&message).await;
+                }
+            }
+
+            if now >= next_ping {
+                next_ping += PING_INTERVAL;
+                self.send(&json!({ "action": "ping", "client_time": now_ms() }))
+                    .await;
+            }
+
+            let wait = next_tick.min(next_ping).saturating_duration_since(now);
+            let Some(message) = self.receive(wait).await else {
+                continue;
+            };
+
+            match message["action"].as_str() {
+                Some("snapshot") => {
+                    let Some(tick) = message["tick"].as_u64() else {
+                        continue;
+                    };
+                    let first = *first_snapshot.get_or_insert(tick);
+
+                    self.report.snapshots_received += 1;
+                    self.report.snapshots_expected = (tick - first) / SNAPSHOT_INTERVAL + 1;
+                    server_tick = server_tick.max(tick);
+
+                    // Like the game client, so the server sends deltas
+                    self.send(&json!({ "action": "ack", "tick": tick })).await;
+                }
+                Some("pong") => {
+                    if let Some(sent) = message["client_time"].as_f64() {
+                        self.report.round_trips_ms.push(now_ms() - sent);
+                    }
+                }
+
+                // Pick any map right away, the match only starts once both bots voted
+                Some("map_vote") => {
+                    let maps = message["maps"].as_array().cloned().unwrap_or_default();
+                    if !maps.is_empty() {
+                        let map = &maps[self.rng.random_range(0..maps.len())];
+                        self.send(&json!({ "action": "vote", "room_id": room_id, "map": map }))
+                            .await;
+                    }
+                }
+
+                // The other bot left first
+                Some("match_over") => return Ok(()),
+                Some("kicked") => return Err(format!("kicked: {}", message["reason"])),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index]
+}
+
+fn print_distribution(name: &str, mut values: Vec<f64>) {
+    if values.is_empty() {
+        println!("{name}: no samples");
+        return;
+    }
+
+    values.sort_by(f64::total_cmp);
+    println!(
+        "{name} (ms): p50 {:.1}  p90 {:.1}  p99 {:.1}  max {:.1}  ({} samples)",
+        percentile(&values, 0.5),
+        percentile(&values, 0.9),
+        percentile(&values, 0.99),
+        values[values.len() - 1],
+        values.len()
+    );
+}
+
+fn print_summary(config: &Config, reports: Vec<Report>, elapsed: Duration) {
+    let matched = reports.iter().filter(|report| report.matched).count();
+    let failed = reports
+        .iter()
+        .filter(|report| !report.errors.is_empty())
+        .count();
+
+    println!();
+    println!(
+        "{} clients against {} in {:.1}s: {} matched, {} with errors",
+        config.clients,
+        config.server,
+        elapsed.as_secs_f64(),
+        matched,
+        failed
+    );
+
+    let mut round_trips = Vec::new();
+    let mut matchmaking = Vec::new();
+    let mut received = 0;
+    let mut expected = 0;
+    let mut errors: HashMap<String, usize> = HashMap::new();
+
+    for report in reports {
+        round_trips.extend(report.round_trips_ms);
+        matchmaking.extend(report.matchmaking_ms);
+        received += report.snapshots_received;
+        expected += report.snapshots_expected;
+
+        for error in report.errors {
+            *errors.entry(error).or_insert(0) += 1;
+        }
+    }
+
+    print_distribution("Round trip", round_trips);
+    print_distribution("Matchmaking", matchmaking);
+
+    let lost = expected.saturating_sub(received);
+    let loss = if expected > 0 {
+        lost as f64 / expected as f64 * 100.0
+    } else {
+        0.0
+    };
+    println!("Snapshot loss: {loss:.2}% ({lost} of {expected})");
+
+    if errors.is_empty() {
+        println!("No errors");
+        return;
+    }
+
+    let mut errors: Vec<(String, usize)> = errors.into_iter().collect();
+    errors.sort_by_key(|(_, count)| Reverse(*count));
+
+    println!("Errors:");
+    for (error, count) in errors {
+        println!("  {count:>6}  {error}");
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let config = match Config::from_args() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!(
+                "Usage: load_test [--server ADDR] [--clients N] [--ramp-up SECS] [--play SECS] [--match-timeout SECS] [--encrypt]"
+            );
+            std::process::exit(2);
+        }
+    };
+
+    println!(
+        "Starting {} clients against {} over {:.1}s",
+        config.clients,
+        config.server,
+        config.ramp_up.as_secs_f64()
+    );
+
+    let started = Instant::now();
+    let mut bots = Vec::with_capacity(config.clients);
+
+    for i in 0..config.clients {
+        // Spread the clients evenly over the ramp-up
+        let start_at = config.ramp_up.mul_f64(i as f64 / config.clients as f64);
+        tokio::time::sleep_until((started + start_at).into()).await;
+
+        let config = config.clone();
+        bots.push(tokio::spawn(async move {
+            match Bot::connect(&config).await {
+                Ok(bot) => bot.run(&config).await,
+                Err(e) => Report {
+                    errors: vec![e],
+                    ..Default::default()
+                },
+            }
+        }));
+    }
+
+    println!("All clients started, waiting for them to finish");
+
+    let mut reports = Vec::with_capacity(bots.len());
+    for bot in bots {
+        match bot.await {
+            Ok(report) => reports.push(report),
+            Err(e) => reports.push(Report {
+                errors: vec![format!("bot crashed: {e}")],
+                ..Default::default()
+            }),
+        }
+    }
+
+    print_summary(&config, reports, started.elapsed());
+}
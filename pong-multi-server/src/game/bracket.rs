@@ -0,0 +1,301 @@
+use std::fmt;
+
+// Knockout formats a tournament can be played in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BracketFormat {
+    #[default]
+    SingleElimination,
+
+    // Losing once drops a player to the losers bracket, whose winner meets the
+    // winner of the winners bracket in a single grand final
+    DoubleElimination,
+}
+
+impl BracketFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BracketFormat::SingleElimination => "single",
+            BracketFormat::DoubleElimination => "double",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "single" => Some(BracketFormat::SingleElimination),
+            "double" => Some(BracketFormat::DoubleElimination),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BracketFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Winners,
+    Losers,
+    GrandFinal,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Winners => "winners",
+            Stage::Losers => "losers",
+            Stage::GrandFinal => "grand_final",
+        }
+    }
+}
+
+// Where a player in a bracket match comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    // Index in the seeding, 0 is the top seed
+    Seed(usize),
+    Winner(usize),
+    Loser(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    // Waiting on a match that isn't over
+    Pending,
+
+    // Index of the entrant in the seeding
+    Player(usize),
+
+    // Nobody will come, the other player goes through
+    Bye,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub winner: Slot,
+    pub loser: Slot,
+}
+
+#[derive(Debug, Clone)]
+pub struct BracketMatch {
+    pub stage: Stage,
+    pub round: usize,
+    pub sources: [Source; 2],
+    pub outcome: Option<Outcome>,
+}
+
+// Every match of a knockout tournament, played in the order they become
+// ready. Byes are handed out to the top seeds and resolve by themselves.
+#[derive(Debug, Clone)]
+pub struct Bracket {
+    pub format: BracketFormat,
+    pub entrants: usize,
+
+    // The last one decides the tournament
+    pub matches: Vec<BracketMatch>,
+}
+
+impl Bracket {
+    pub fn new(format: BracketFormat, entrants: usize) -> Self {
+        let size = entrants.max(2).next_power_of_two();
+        let mut bracket = Self {
+            format,
+            entrants,
+            matches: Vec::new(),
+        };
+
+        // Round one pairs the top seeds with the bottom ones
+        let seeds = seed_order(size);
+        let mut winners: Vec<usize> = seeds
+            .chunks(2)
+            .map(|pair| {
+                bracket.add(
+                    Stage::Winners,
+                    1,
+                    [Source::Seed(pair[0]), Source::Seed(pair[1])],
+                )
+            })
+            .collect();
+        let mut rounds = vec![winners.clone()];
+
+        let mut round = 1;
+        while winners.len() > 1 {
+            round += 1;
+            winners = winners
+                .chunks(2)
+                .map(|pair| {
+                    bracket.add(
+                        Stage::Winners,
+                        round,
+                        [Source::Winner(pair[0]), Source::Winner(pair[1])],
+                    )
+                })
+                .collect();
+            rounds.push(winners.clone());
+        }
+
+        if format == BracketFormat::DoubleElimination {
+            bracket.add_losers_bracket(&rounds);
+        }
+
+        bracket.resolve_byes();
+        bracket
+    }
+
+    fn add(&mut self, stage: Stage, round: usize, sources: [Source; 2]) -> usize {
+        self.matches.push(BracketMatch {
+            stage,
+            round,
+            sources,
+            outcome: None,
+        });
+        self.matches.len() - 1
+    }
+
+    // The losers of the first round play each other, then every round of the
+    // losers bracket alternates between its own survivors meeting and them
+    // meeting the players who just dropped from the winners bracket
+    fn add_losers_bracket(&mut self, winners_rounds: &[Vec<usize>]) {
+        let mut survivors: Vec<Source> = winners_rounds[0]
+            .iter()
+            .map(|index| Source::Loser(*index))
+            .collect();
+        let mut round = 0;
+
+        for (number, dropping) in winners_rounds.iter().enumerate().skip(1) {
+            if survivors.len() > 1 {
+                round += 1;
+                survivors = survivors
+                    .chunks(2)
+                    .map(|pair| Source::Winner(self.add(Stage::Losers, round, [pair[0], pair[1]])))
+                    .collect();
+            }
+
+            // Flipped every other round so players don't meet the same opponent again
+            let mut dropping: Vec<Source> =
+                dropping.iter().map(|index| Source::Loser(*index)).collect();
+            if number % 2 == 1 {
+                dropping.reverse();
+            }
+
+            round += 1;
+            survivors = survivors
+                .iter()
+                .zip(dropping)
+                .map(|(survivor, dropped)| {
+                    Source::Winner(self.add(Stage::Losers, round, [*survivor, dropped]))
+                })
+                .collect();
+        }
+
+        let winners_final = *winners_rounds
+            .last()
+            .and_then(|round| round.first())
+            .unwrap();
+        self.add(
+            Stage::GrandFinal,
+            1,
+            [Source::Winner(winners_final), survivors[0]],
+        );
+    }
+
+    pub fn slot(&self, source: Source) -> Slot {
+        match source {
+            Source::Seed(seed) if seed < self.entrants => Slot::Player(seed),
+            Source::Seed(_) => Slot::Bye,
+            Source::Winner(index) => self.matches[index]
+                .outcome
+                .map_or(Slot::Pending, |outcome| outcome.winner),
+            Source::Loser(index) => self.matches[index]
+                .outcome
+                .map_or(Slot::Pending, |outcome| outcome.loser),
+        }
+    }
+
+    pub fn players(&self, index: usize) -> [Slot; 2] {
+        self.matches[index].sources.map(|source| self.slot(source))
+    }
+
+    // Matches with a bye on one side are won by the other one without playing
+    fn resolve_byes(&mut self) {
+        loop {
+            let resolved = (0..self.matches.len()).find_map(|index| {
+                if self.matches[index].outcome.is_some() {
+                    return None;
+                }
+                match self.players(index) {
+                    [Slot::Bye, other] | [other, Slot::Bye] if other != Slot::Pending => {
+                        Some((index, other))
+                    }
+                    _ => None,
+                }
+            });
+
+            let Some((index, winner)) = resolved else {
+                return;
+            };
+            self.matches[index].outcome = Some(Outcome {
+                winner,
+                loser: Slot::Bye,
+            });
+        }
+    }
+
+    // Matches waiting to be played, both players known
+    pub fn ready(&self) -> Vec<usize> {
+        (0..self.matches.len())
+            .filter(|index| {
+                self.matches[*index].outcome.is_none()
+                    && matches!(self.players(*index), [Slot::Player(_), Slot::Player(_)])
+            })
+            .collect()
+    }
+
+    // `winner` is 0 or 1, the side of the match in `sources`. False for a
+    // match that isn't ready.
+    pub fn report(&mut self, index: usize, winner: usize) -> bool {
+        if !self.ready().contains(&index) || winner > 1 {
+            return false;
+        }
+
+        let players = self.players(index);
+        self.matches[index].outcome = Some(Outcome {
+            winner: players[winner],
+            loser: players[1 - winner],
+        });
+        self.resolve_byes();
+
+        true
+    }
+
+    pub fn champion(&self) -> Option<usize> {
+        match self.matches.last()?.outcome?.winner {
+            Slot::Player(seed) => Some(seed),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.matches
+            .last()
+            .is_some_and(|last| last.outcome.is_some())
+    }
+}
+
+// Seeds of the first round in bracket order, so the top two seeds can only
+// meet in the final: 0, 7, 3, 4, 1, 6, 2, 5 for eight players
+fn seed_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+
+    while order.len() < size {
+        let count = order.len() * 2;
+        order = order
+            .iter()
+            .flat_map(|seed| [*seed, count - 1 - seed])
+            .collect();
+    }
+
+    order
+}
@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+
+use pong_multi_shared::game::{ball::Ball, paddle::Paddle, state::GameState};
+
+// How far back a hit can be compensated (200 ms at 60 ticks per second)
+pub const MAX_COMPENSATION_TICKS: u64 = 12;
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub tick: u64,
+    pub ball: Ball,
+    pub paddles: Vec<Paddle>,
+}
+
+// The last few ticks of a room, used to rewind the ball to what a lagging player saw
+#[derive(Debug, Default)]
+pub struct StateHistory {
+    frames: VecDeque<Frame>,
+}
+
+impl StateHistory {
+    pub fn record(&mut self, state: &GameState) {
+        self.frames.push_back(Frame {
+            tick: state.tick,
+            ball: state.ball,
+            paddles: state.paddles.clone(),
+        });
+
+        while self.frames.len() as u64 > MAX_COMPENSATION_TICKS + 1 {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn at(&self, tick: u64) -> Option<&Frame> {
+        let first = self.frames.front()?.tick;
+
+        self.frames.get(tick.checked_sub(first)? as usize)
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
@@ -0,0 +1,5 @@
+pub mod bracket;
+pub mod history;
+pub mod pause;
+pub mod rating;
+pub mod vote;
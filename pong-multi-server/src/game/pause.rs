@@ -0,0 +1,214 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use pong_multi_shared::game::TICK_RATE;
+
+// How often players can stop a match and for how long
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PauseRules {
+    // Pauses each player can ask for during a match
+    pub per_player: u32,
+
+    // The countdown starts by itself once a pause lasted this long
+    pub max_length: Duration,
+
+    // Between the resume and the ball moving again
+    pub countdown: Duration,
+}
+
+impl Default for PauseRules {
+    fn default() -> Self {
+        Self {
+            per_player: 2,
+            max_length: Duration::from_secs(60),
+            countdown: Duration::from_secs(3),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PausePhase {
+    Playing,
+
+    // Asked for, starts once the point being played is over
+    Pending {
+        by: SocketAddr,
+    },
+    Paused {
+        by: SocketAddr,
+        ticks_left: u64,
+
+        // Everyone else who wants to go on
+        votes: HashSet<SocketAddr>,
+    },
+    Resuming {
+        ticks_left: u64,
+    },
+}
+
+impl PausePhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PausePhase::Playing => "playing",
+            PausePhase::Pending { .. } => "pending",
+            PausePhase::Paused { .. } => "paused",
+            PausePhase::Resuming { .. } => "resuming",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseRefused {
+    AlreadyPaused,
+    NoPausesLeft,
+}
+
+impl PauseRefused {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PauseRefused::AlreadyPaused => "already_paused",
+            PauseRefused::NoPausesLeft => "no_pauses_left",
+        }
+    }
+}
+
+// Where a match is between pauses, and how many each player already took
+#[derive(Debug)]
+pub struct Pause {
+    pub rules: PauseRules,
+    pub phase: PausePhase,
+    pub used: HashMap<SocketAddr, u32>,
+}
+
+impl Pause {
+    pub fn new(rules: PauseRules) -> Self {
+        Self {
+            rules,
+            phase: PausePhase::Playing,
+            used: HashMap::new(),
+        }
+    }
+
+    // The ball keeps moving until a pending pause starts
+    pub fn is_running(&self) -> bool {
+        matches!(self.phase, PausePhase::Playing | PausePhase::Pending { .. })
+    }
+
+    pub fn pauses_left(&self, addr: &SocketAddr) -> u32 {
+        let used = self.used.get(addr).copied().unwrap_or(0);
+        self.rules.per_player.saturating_sub(used)
+    }
+
+    // Right away, or once the point being played is over
+    pub fn request(&mut self, addr: SocketAddr, after_point: bool) -> Result<(), PauseRefused> {
+        if self.phase != PausePhase::Playing {
+            return Err(PauseRefused::AlreadyPaused);
+        }
+        if self.pauses_left(&addr) == 0 {
+            return Err(PauseRefused::NoPausesLeft);
+        }
+
+        *self.used.entry(addr).or_insert(0) += 1;
+        self.phase = if after_point {
+            PausePhase::Pending { by: addr }
+        } else {
+            self.paused(addr)
+        };
+        Ok(())
+    }
+
+    // True when a pending pause starts
+    pub fn point_over(&mut self) -> bool {
+        let PausePhase::Pending { by } = self.phase else {
+            return false;
+        };
+
+        self.phase = self.paused(by);
+        true
+    }
+
+    // The player who paused resumes straight away, or takes back a pause that
+    // didn't start yet. Anyone else votes, and a majority of the other
+    // `players` resumes too. True when there is something new to tell the room.
+    pub fn resume(&mut self, addr: SocketAddr, players: usize) -> bool {
+        match &mut self.phase {
+            PausePhase::Pending { by } if *by == addr => {
+                if let Some(used) = self.used.get_mut(&addr) {
+                    *used -= 1;
+                }
+                self.phase = PausePhase::Playing;
+                true
+            }
+            PausePhase::Paused { by, votes, .. } => {
+                if *by != addr && !votes.insert(addr) {
+                    return false;
+                }
+
+                if *by == addr || votes.len() >= Self::votes_needed(players) {
+                    self.phase = self.resuming();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Count down the pause or the resume, true when the phase changed
+    pub fn tick(&mut self) -> bool {
+        match &mut self.phase {
+            PausePhase::Paused { ticks_left, .. } => {
+                *ticks_left = ticks_left.saturating_sub(1);
+                if *ticks_left == 0 {
+                    self.phase = self.resuming();
+                    return true;
+                }
+            }
+            PausePhase::Resuming { ticks_left } => {
+                *ticks_left = ticks_left.saturating_sub(1);
+                if *ticks_left == 0 {
+                    self.phase = PausePhase::Playing;
+                    return true;
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    // Until the countdown starts while paused, until the ball moves while resuming
+    pub fn seconds_left(&self) -> u64 {
+        match self.phase {
+            PausePhase::Paused { ticks_left, .. } | PausePhase::Resuming { ticks_left } => {
+                ticks_left.div_ceil(TICK_RATE as u64)
+            }
+            _ => 0,
+        }
+    }
+
+    // Votes needed to resume without the player who paused, who can't vote
+    pub fn votes_needed(players: usize) -> usize {
+        players.saturating_sub(1) / 2 + 1
+    }
+
+    fn paused(&self, by: SocketAddr) -> PausePhase {
+        PausePhase::Paused {
+            by,
+            ticks_left: ticks(self.rules.max_length),
+            votes: HashSet::new(),
+        }
+    }
+
+    fn resuming(&self) -> PausePhase {
+        PausePhase::Resuming {
+            ticks_left: ticks(self.rules.countdown),
+        }
+    }
+}
+
+// At least one, a phase with no ticks would never be seen
+fn ticks(duration: Duration) -> u64 {
+    ((duration.as_secs_f64() * TICK_RATE as f64).round() as u64).max(1)
+}
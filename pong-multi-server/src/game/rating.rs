@@ -0,0 +1,18 @@
+// Elo rating of every player, kept for as long as they are connected.
+// Used to seed tournaments.
+pub const DEFAULT_RATING: f32 = 1000.0;
+
+// How far a single match moves the ratings
+const K_FACTOR: f32 = 32.0;
+
+// Chance of a player rated `rating` beating one rated `opponent`
+pub fn expected_score(rating: f32, opponent: f32) -> f32 {
+    1.0 / (1.0 + 10f32.powf((opponent - rating) / 400.0))
+}
+
+// New ratings of the winner and the loser. Teams play with the average
+// rating of their players and each of them moves by the same amount.
+pub fn update(winner: f32, loser: f32) -> (f32, f32) {
+    let change = K_FACTOR * (1.0 - expected_score(winner, loser));
+    (winner + change, loser - change)
+}
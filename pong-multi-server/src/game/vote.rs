@@ -0,0 +1,64 @@
+use std::{collections::HashMap, net::SocketAddr};
+
+use pong_multi_shared::game::{map::Map, TICK_RATE};
+
+// How long players have to pick a map before the match starts (10 seconds)
+pub const VOTE_TICKS: u64 = TICK_RATE as u64 * 10;
+
+// The maps a room is choosing between and what each player picked.
+// Players can change their mind until the vote is over.
+#[derive(Debug)]
+pub struct MapVote {
+    pub options: Vec<Map>,
+    pub votes: HashMap<SocketAddr, usize>,
+    pub ticks_left: u64,
+}
+
+impl MapVote {
+    pub fn new(options: Vec<Map>) -> Self {
+        Self {
+            options,
+            votes: HashMap::new(),
+            ticks_left: VOTE_TICKS,
+        }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.options.iter().map(|map| map.name.as_str()).collect()
+    }
+
+    // False for a map that isn't one of the options
+    pub fn cast(&mut self, addr: SocketAddr, name: &str) -> bool {
+        let Some(index) = self.options.iter().position(|map| map.name == name) else {
+            return false;
+        };
+
+        self.votes.insert(addr, index);
+        true
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+    }
+
+    pub fn is_over(&self, voters: usize) -> bool {
+        self.ticks_left == 0 || self.votes.len() >= voters
+    }
+
+    // Most votes wins, ties go to the map listed first
+    pub fn winner(&self) -> &Map {
+        let mut counts = vec![0; self.options.len()];
+        for index in self.votes.values() {
+            counts[*index] += 1;
+        }
+
+        let best = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, count)| **count)
+            .map_or(0, |(index, _)| index);
+
+        &self.options[best]
+    }
+}
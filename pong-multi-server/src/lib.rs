@@ -0,0 +1,3 @@
+pub mod game;
+pub mod network;
+pub mod shared;
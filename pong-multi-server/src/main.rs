@@ -1,18 +1,187 @@
-use network::server::Server;
-use std::io;
+use pong_multi_server::network::{
+    chat::WordFilter,
+    console::run_console,
+    server::{Server, ServerConfig},
+    transport::{
+        conditioned::ConditionedTransport, udp::UdpTransport, websocket::WebSocketTransport,
+        Transport,
+    },
+};
+use pong_multi_shared::{
+    game::{map::Map, power_up::SpawnTable},
+    netsim::NetworkConditions,
+    protocol::{WireFormat, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+};
+use std::{io, time::Duration};
 
-pub mod game;
-pub mod network;
-pub mod shared;
+const DEFAULT_LISTEN_ADDR: &str = "0.0.0.0:8090";
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 4)]
 async fn main() -> io::Result<()> {
-    let server = Server::new("0.0.0.0:8090").await;
+    let addr =
+        std::env::var("PONG_LISTEN_ADDR").unwrap_or_else(|_| DEFAULT_LISTEN_ADDR.to_string());
+
+    // A perfect network unless asked otherwise, the console can change it later
+    let conditions = match std::env::var("PONG_NETSIM") {
+        Ok(spec) => spec
+            .parse::<NetworkConditions>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+        Err(_) => NetworkConditions::default(),
+    };
+
+    let mut config = ServerConfig::default();
+
+    // Binary unless asked for JSON to look at the traffic
+    if let Ok(format) = std::env::var("PONG_WIRE") {
+        config.wire_format = format
+            .parse::<WireFormat>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    }
+
+    // Raised to make clients older than that update
+    if let Ok(version) = std::env::var("PONG_MIN_PROTOCOL") {
+        config.min_protocol = version
+            .parse()
+            .ok()
+            .filter(|version| (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(version))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "PONG_MIN_PROTOCOL must be between {} and {}",
+                        MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+                    ),
+                )
+            })?;
+    }
+
+    // Where the back and front paddles of a doubles team stand, as "back,front"
+    if let Ok(depths) = std::env::var("PONG_DOUBLES_DEPTHS") {
+        config.doubles_depths = depths
+            .split_once(',')
+            .and_then(|(back, front)| Some([back.trim().parse().ok()?, front.trim().parse().ok()?]))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "PONG_DOUBLES_DEPTHS must be two distances like 608,400",
+                )
+            })?;
+    }
+
+    // Power-ups per mode, like "every=10,laser=1,star=1,key=0" or "off"
+    for (var, table) in [
+        ("PONG_POWER_UPS_SINGLES", &mut config.singles_power_ups),
+        ("PONG_POWER_UPS_DOUBLES", &mut config.doubles_power_ups),
+    ] {
+        if let Ok(spec) = std::env::var(var) {
+            *table = spec
+                .parse::<SpawnTable>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{var}: {e}")))?;
+        }
+    }
+
+    // Maps to vote on, every built-in one unless given a list of names or
+    // paths to map files like "classic,pillars,maps/custom.json"
+    config.maps = match std::env::var("PONG_MAPS") {
+        Ok(list) => list
+            .split(',')
+            .map(|entry| load_map(entry.trim()))
+            .collect::<io::Result<_>>()?,
+        Err(_) => Map::built_in(),
+    };
+    println!(
+        "Maps: {}",
+        config
+            .maps
+            .iter()
+            .map(|map| map.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    // How long players who accept the AI wait for a real opponent first
+    if let Ok(seconds) = std::env::var("PONG_AI_FALLBACK_SECONDS") {
+        config.ai_fallback_after = seconds.parse().map(Duration::from_secs).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "PONG_AI_FALLBACK_SECONDS must be a number of seconds",
+            )
+        })?;
+    }
+
+    // Words masked out of chat and names, like "darn,heck"
+    if let Ok(words) = std::env::var("PONG_CHAT_FILTER") {
+        config.chat_filter = WordFilter::new(words.split(','));
+    }
+
+    // Points that win a tournament match, and how long a match waits for a
+    // player who doesn't show up
+    if let Ok(points) = std::env::var("PONG_TOURNAMENT_POINTS") {
+        config.tournament_first_to = points
+            .parse()
+            .ok()
+            .filter(|points| *points > 0)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "PONG_TOURNAMENT_POINTS must be a number of points",
+                )
+            })?;
+    }
+    if let Ok(seconds) = std::env::var("PONG_NO_SHOW_SECONDS") {
+        config.no_show_after = seconds.parse().map(Duration::from_secs).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "PONG_NO_SHOW_SECONDS must be a number of seconds",
+            )
+        })?;
+    }
+
+    let wire_format = config.wire_format;
+
+    // UDP for the desktop client, WebSocket for browser builds
+    let server = match std::env::var("PONG_TRANSPORT").as_deref() {
+        Ok("websocket") => start(WebSocketTransport::bind(&addr).await?, conditions, config)?,
+        Ok("udp") | Err(_) => start(UdpTransport::bind(&addr).await?, conditions, config)?,
+        Ok(other) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown transport {other}, expected udp or websocket"),
+            ))
+        }
+    };
+    println!("Listening on {} ({} messages)", server.addr, wire_format);
 
     tokio::signal::ctrl_c()
         .await
         .expect("Failed to listen for the shutdown signal");
     println!("Shutting down....");
 
+    server.shutdown().await;
+
     Ok(())
 }
+
+fn load_map(entry: &str) -> io::Result<Map> {
+    if !entry.ends_with(".json") {
+        return Map::by_name(entry).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown map {entry}"))
+        });
+    }
+
+    let json = serde_json::from_str(&std::fs::read_to_string(entry)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{entry}: {e}")))?;
+    Map::from_json(&json)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{entry}: {e}")))
+}
+
+fn start(
+    transport: impl Transport,
+    conditions: NetworkConditions,
+    config: ServerConfig,
+) -> io::Result<Server> {
+    let transport = ConditionedTransport::new(transport, conditions);
+    tokio::spawn(run_console(transport.control()));
+
+    Server::with_config(transport, config)
+}
@@ -0,0 +1,45 @@
+use std::{fs::OpenOptions, io::Write, net::SocketAddr, path::PathBuf};
+
+use uuid::Uuid;
+
+use super::clock::server_time_ms;
+
+pub const AUDIT_LOG_PATH: &str = "audit.log";
+
+// Append-only record of cheating attempts and kicks, only printed without a path
+#[derive(Debug)]
+pub struct AuditLog {
+    path: Option<PathBuf>,
+}
+
+impl AuditLog {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path }
+    }
+
+    pub fn record(&self, addr: &SocketAddr, player_id: Option<Uuid>, event: &str) {
+        let player_id = player_id.map_or_else(|| "-".to_string(), |id| id.to_string());
+        let line = format!(
+            "{:.0} {} {:?} {}\n",
+            server_time_ms(),
+            player_id,
+            addr,
+            event
+        );
+
+        eprint!("[audit] {}", line);
+
+        let Some(path) = &self.path else {
+            return;
+        };
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()));
+
+        if let Err(e) = written {
+            eprintln!("Error writing the audit log: {:?}", e);
+        }
+    }
+}
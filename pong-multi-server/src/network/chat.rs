@@ -0,0 +1,47 @@
+// Words masked out of chat lines, empty lets everything through.
+// A word containing a banned one is masked too, so suffixes don't get around it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WordFilter {
+    words: Vec<String>,
+}
+
+impl WordFilter {
+    pub fn new<S: AsRef<str>>(words: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            words: words
+                .into_iter()
+                .map(|word| word.as_ref().trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    // Replace every letter of a banned word with an asterisk, the spacing stays the same
+    pub fn apply(&self, text: &str) -> String {
+        if self.is_empty() {
+            return text.to_string();
+        }
+
+        text.split_inclusive(char::is_whitespace)
+            .map(|piece| {
+                let word = piece.trim_end().to_lowercase();
+                if self
+                    .words
+                    .iter()
+                    .any(|banned| word.contains(banned.as_str()))
+                {
+                    piece
+                        .chars()
+                        .map(|c| if c.is_whitespace() { c } else { '*' })
+                        .collect()
+                } else {
+                    piece.to_string()
+                }
+            })
+            .collect()
+    }
+}
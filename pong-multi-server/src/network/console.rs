@@ -0,0 +1,37 @@
+use pong_multi_shared::netsim::NetworkConditions;
+use tokio::io::{stdin, AsyncBufReadExt, BufReader};
+
+use super::transport::conditioned::NetworkControl;
+
+// Admin commands typed on the server standard input
+pub async fn run_console(network: NetworkControl) {
+    let mut lines = BufReader::new(stdin()).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+
+        match command {
+            "" => {}
+
+            "netsim" if args.is_empty() => println!("Network conditions: {}", network.conditions()),
+
+            "netsim" => match args.parse::<NetworkConditions>() {
+                Ok(conditions) => {
+                    network.set(conditions);
+                    println!("Network conditions set to {}", conditions);
+                }
+                Err(e) => eprintln!("{e}"),
+            },
+
+            "help" => {
+                println!("netsim                    show the simulated network conditions");
+                println!(
+                    "netsim PRESET|KEY=VALUE…  one of {} or latency, jitter, loss, duplicate, reorder, bandwidth",
+                    NetworkConditions::PRESETS.join(", ")
+                );
+            }
+
+            _ => eprintln!("Unknown command {command}, try help"),
+        }
+    }
+}
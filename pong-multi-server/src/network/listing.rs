@@ -0,0 +1,104 @@
+use std::{collections::HashSet, net::SocketAddr};
+
+use pong_multi_shared::game::{ai::Difficulty, formation::GameMode};
+use serde_json::Value;
+
+// Entries per page of the room browser when the client doesn't ask for a size
+pub const ROOMS_PER_PAGE: usize = 8;
+
+// Keeps a page in a single datagram or two
+pub const MAX_ROOMS_PER_PAGE: usize = 20;
+
+// A match being played, as the room browser shows it
+#[derive(Debug, Clone)]
+pub struct RoomListing {
+    pub mode: GameMode,
+    pub ai: Option<Difficulty>,
+
+    // Left then right, seats played by the AI are left out
+    pub teams: [Vec<SocketAddr>; 2],
+    pub score: [u32; 2],
+    pub spectators: HashSet<SocketAddr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingKind {
+    // Private rooms, parties, marked as listed with room for more players
+    Open,
+
+    // Matches being played, they can be watched
+    Live,
+}
+
+impl ListingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingKind::Open => "open",
+            ListingKind::Live => "live",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(ListingKind::Open),
+            "live" => Some(ListingKind::Live),
+            _ => None,
+        }
+    }
+}
+
+// What a list_rooms request narrows the list to, everything by default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoomFilter {
+    pub kind: Option<ListingKind>,
+    pub mode: Option<GameMode>,
+
+    // Part of the name of someone in the room, any case
+    pub name: Option<String>,
+}
+
+impl RoomFilter {
+    // The value that didn't make sense on error
+    pub fn from_json(json: &Value) -> Result<Self, String> {
+        let kind = match json["kind"].as_str() {
+            None => None,
+            Some(kind) => Some(ListingKind::parse(kind).ok_or(kind)?),
+        };
+        let mode = match json["mode"].as_str() {
+            None => None,
+            Some(mode) => Some(GameMode::parse(mode).ok_or(mode)?),
+        };
+        let name = json["name"]
+            .as_str()
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty());
+
+        Ok(Self { kind, mode, name })
+    }
+
+    // `modes` are the ones the room can be played in, `names` the people in it
+    pub fn wants(&self, kind: ListingKind, modes: &[GameMode], names: &[&str]) -> bool {
+        self.kind.is_none_or(|wanted| wanted == kind)
+            && self.mode.is_none_or(|wanted| modes.contains(&wanted))
+            && self.name.as_ref().is_none_or(|wanted| {
+                names
+                    .iter()
+                    .any(|name| name.to_lowercase().contains(wanted.as_str()))
+            })
+    }
+}
+
+// The entries of the page asked for, the first one is 0, and how many pages
+// there are. Pages past the end come back empty.
+pub fn page<T>(entries: Vec<T>, page: usize, per_page: usize) -> (Vec<T>, usize) {
+    let per_page = per_page.clamp(1, MAX_ROOMS_PER_PAGE);
+    let pages = entries.len().div_ceil(per_page).max(1);
+
+    let entries = entries
+        .into_iter()
+        .skip(page.saturating_mul(per_page))
+        .take(per_page)
+        .collect();
+
+    (entries, pages)
+}
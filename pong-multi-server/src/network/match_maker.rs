@@ -1,93 +1,301 @@
 use std::{
     collections::{HashMap, VecDeque},
     net::SocketAddr,
-    sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use tokio::sync::mpsc;
-use uuid::Uuid;
+use pong_multi_shared::game::{
+    ai::Difficulty,
+    formation::{Formation, GameMode, Seat},
+    Side,
+};
+use tokio::{sync::mpsc, time::Instant};
 
 use super::{
-    player::{Player, PlayerStatus},
-    room::Room,
+    message::{MatchMakerCommand, ServerEvent},
+    room::{Room, RoomSettings},
+    server::ServerConfig,
 };
 
+// How often the queues are checked for players who waited long enough for the AI
+const AI_FALLBACK_CHECK: Duration = Duration::from_millis(250);
+
+// Players queued together, a single player or a party that plays on the same team
+#[derive(Debug, Clone)]
+pub struct Ticket {
+    pub players: Vec<SocketAddr>,
+    pub queued_at: Instant,
+
+    // Play the AI at this difficulty rather than wait past the configured time
+    pub ai_fallback: Option<Difficulty>,
+}
+
+// Owns the queues of players waiting for a match, one per mode, runs as its own task
 #[derive(Debug)]
 pub struct MatchMaker {
-    pub queue: Arc<Mutex<VecDeque<SocketAddr>>>,
-    pub rooms: Arc<Mutex<HashMap<Uuid, Arc<Mutex<Room>>>>>,
-    pub players: Arc<Mutex<HashMap<SocketAddr, Arc<Mutex<Player>>>>>,
-    pub tx: mpsc::Sender<(usize, SocketAddr, Vec<u8>)>,
-
-    pub player_room_map: Arc<Mutex<HashMap<SocketAddr, Uuid>>>,
+    pub queues: HashMap<GameMode, VecDeque<Ticket>>,
+    pub events: mpsc::Sender<ServerEvent>,
+    pub config: ServerConfig,
 }
 
 impl MatchMaker {
-    pub fn new(
-        players: Arc<Mutex<HashMap<SocketAddr, Arc<Mutex<Player>>>>>,
-        tx: mpsc::Sender<(usize, SocketAddr, Vec<u8>)>,
-        rooms: Arc<Mutex<HashMap<Uuid, Arc<Mutex<Room>>>>>,
-        player_room_map: Arc<Mutex<HashMap<SocketAddr, Uuid>>>,
-    ) -> Self {
+    pub fn spawn(
+        events: mpsc::Sender<ServerEvent>,
+        config: ServerConfig,
+    ) -> mpsc::Sender<MatchMakerCommand> {
+        let (tx, rx) = mpsc::channel(1000);
+        let match_maker = Self::new(events, config);
+
+        tokio::spawn(match_maker.run(rx));
+
+        tx
+    }
+
+    pub fn new(events: mpsc::Sender<ServerEvent>, config: ServerConfig) -> Self {
         Self {
-            players,
-            queue: Arc::new(Mutex::new(VecDeque::new())),
-            rooms,
-            tx,
-            player_room_map,
+            queues: HashMap::new(),
+            events,
+            config,
+        }
+    }
+
+    async fn run(mut self, mut rx: mpsc::Receiver<MatchMakerCommand>) {
+        let mut check = tokio::time::interval(AI_FALLBACK_CHECK);
+
+        loop {
+            tokio::select! {
+                command = rx.recv() => match command {
+                    Some(command) => self.handle(command).await,
+                    None => return,
+                },
+                _ = check.tick() => self.expire_tickets().await,
+            }
+        }
+    }
+
+    pub async fn handle(&mut self, command: MatchMakerCommand) {
+        match command {
+            MatchMakerCommand::Join {
+                players,
+                mode,
+                ai_fallback,
+            } => {
+                let ticket = Ticket {
+                    players: players.clone(),
+                    queued_at: Instant::now(),
+                    ai_fallback,
+                };
+                if self.add_to_queue(ticket, mode) {
+                    self.try_create_rooms(mode).await;
+                } else {
+                    self.refuse(players).await;
+                }
+            }
+            MatchMakerCommand::Practice {
+                players,
+                mode,
+                difficulty,
+            } => {
+                if self.is_queued(&players) || players.len() > mode.team_size() {
+                    self.refuse(players).await;
+                    return;
+                }
+                self.create_room(mode, [players, Vec::new()], Some(difficulty))
+                    .await;
+            }
+            MatchMakerCommand::Versus {
+                teams,
+                mode,
+                first_to,
+            } => {
+                if self.is_queued(&teams.concat())
+                    || teams.iter().any(|team| team.len() != mode.team_size())
+                {
+                    self.refuse(teams.concat()).await;
+                    return;
+                }
+                self.create_room_to(mode, teams, None, first_to).await;
+            }
+            MatchMakerCommand::Leave(addr) => self.remove_from_queue(&addr),
+        }
+    }
+
+    // Players already waiting on another ticket stay queued
+    async fn refuse(&self, players: Vec<SocketAddr>) {
+        let players: Vec<SocketAddr> = players
+            .into_iter()
+            .filter(|addr| !self.is_queued(&[*addr]))
+            .collect();
+        if players.is_empty() {
+            return;
+        }
+
+        println!("Players {:?} couldn't be matched", players);
+        let _ = self.events.send(ServerEvent::JoinRefused { players }).await;
+    }
+
+    fn is_queued(&self, players: &[SocketAddr]) -> bool {
+        self.queues
+            .values()
+            .flatten()
+            .any(|queued| queued.players.iter().any(|addr| players.contains(addr)))
+    }
+
+    fn add_to_queue(&mut self, ticket: Ticket, mode: GameMode) -> bool {
+        if ticket.players.is_empty()
+            || ticket.players.len() > mode.team_size()
+            || self.is_queued(&ticket.players)
+        {
+            return false;
         }
+
+        println!("Players {:?} added to the {} queue", ticket.players, mode);
+        self.queues.entry(mode).or_default().push_back(ticket);
+
+        true
     }
 
-    pub fn add_to_queue(&self, addr: &SocketAddr) {
-        let mut queue = self.queue.lock().unwrap();
-        queue.push_back(*addr);
+    // The rest of a party stays queued without the player
+    fn remove_from_queue(&mut self, addr: &SocketAddr) {
+        for queue in self.queues.values_mut() {
+            for ticket in queue.iter_mut() {
+                ticket.players.retain(|queued| queued != addr);
+            }
+            queue.retain(|ticket| !ticket.players.is_empty());
+        }
+    }
+
+    // Players who asked for the AI and waited long enough play it now
+    pub async fn expire_tickets(&mut self) {
+        let wait = self.config.ai_fallback_after;
+        let mut expired = Vec::new();
+
+        for (mode, queue) in self.queues.iter_mut() {
+            let (ready, waiting) = queue.drain(..).partition(|ticket: &Ticket| {
+                ticket.ai_fallback.is_some() && ticket.queued_at.elapsed() >= wait
+            });
+            *queue = waiting;
+            expired.extend(ready.into_iter().map(|ticket| (*mode, ticket)));
+        }
+
+        for (mode, ticket) in expired {
+            println!(
+                "Players {:?} waited {:?} in the {} queue, matched against the AI",
+                ticket.players, wait, mode
+            );
+            self.create_room(mode, [ticket.players, Vec::new()], ticket.ai_fallback)
+                .await;
+        }
+    }
 
-        println!("Player {:?} added to the matchmaking queue", addr);
+    async fn try_create_rooms(&mut self, mode: GameMode) {
+        while let Some(teams) = self.take_teams(mode) {
+            if !self.create_room(mode, teams, None).await {
+                return;
+            }
+        }
+    }
 
-        drop(queue);
-        self.try_create_room();
+    async fn create_room(
+        &mut self,
+        mode: GameMode,
+        teams: [Vec<SocketAddr>; 2],
+        ai: Option<Difficulty>,
+    ) -> bool {
+        self.create_room_to(mode, teams, ai, None).await
     }
 
-    fn try_create_room(&self) {
-        let mut queue = self.queue.lock().unwrap();
-        let mut rooms = self.rooms.lock().unwrap();
-        let mut player_room_map = self.player_room_map.lock().unwrap();
-        let players_map = self.players.lock().unwrap();
+    // Seats the teams, the AI takes the seats left empty. Returns false once the
+    // router is gone.
+    async fn create_room_to(
+        &mut self,
+        mode: GameMode,
+        teams: [Vec<SocketAddr>; 2],
+        ai: Option<Difficulty>,
+        first_to: Option<u32>,
+    ) -> bool {
+        let formation = match mode {
+            GameMode::Singles => Formation::singles(),
+            GameMode::Doubles => Formation::doubles(self.config.doubles_depths),
+        };
 
-        if queue.len() >= 2 {
-            let addr1 = queue.pop_front().unwrap();
-            let addr2 = queue.pop_front().unwrap();
+        // The first team complete plays on the left, in the order they queued
+        let players: Vec<(SocketAddr, Seat)> = [Side::Left, Side::Right]
+            .into_iter()
+            .zip(&teams)
+            .flat_map(|(side, team)| {
+                team.iter()
+                    .enumerate()
+                    .map(move |(slot, addr)| (*addr, Seat::new(side, slot)))
+            })
+            .collect();
 
-            if let (Some(player1), Some(player2)) =
-                (players_map.get(&addr1), players_map.get(&addr2))
-            {
-                let mut p1 = player1.lock().unwrap();
-                let mut p2 = player2.lock().unwrap();
+        let settings = RoomSettings {
+            formation,
+            power_ups: self.config.power_ups(mode),
+            maps: self.config.maps.clone(),
+            ai,
+            first_to,
+            pauses: self.config.pauses,
+        };
+        let (room_id, room) = Room::spawn(players.clone(), settings, self.events.clone());
 
-                p1.status = PlayerStatus::InMatch;
-                p2.status = PlayerStatus::InMatch;
+        match ai {
+            Some(difficulty) => println!(
+                "Room {} created for {} with {:?} against the {} AI",
+                room_id, mode, teams[0], difficulty
+            ),
+            None => println!(
+                "Room {} created for {} with {:?} against {:?}",
+                room_id, mode, teams[0], teams[1]
+            ),
+        }
 
-                // Create new room
-                let (id, room) = Room::new(player1.clone(), player2.clone());
+        let created = ServerEvent::RoomCreated {
+            room_id,
+            players,
+            mode,
+            ai,
+            room,
+        };
+        self.events.send(created).await.is_ok()
+    }
+
+    // Fill two teams with the oldest tickets that fit, parties are never split.
+    // The tickets used are taken out of the queue.
+    fn take_teams(&mut self, mode: GameMode) -> Option<[Vec<SocketAddr>; 2]> {
+        let queue = self.queues.get_mut(&mode)?;
+        let team_size = mode.team_size();
 
-                // Add the room to room_manegement
-                rooms.insert(id, room.clone());
+        let mut teams: [Vec<usize>; 2] = Default::default();
+        let mut sizes = [0; 2];
 
-                // Add player address and room_id to navigate later
-                player_room_map.insert(addr1, id);
-                player_room_map.insert(addr2, id);
+        for (index, ticket) in queue.iter().enumerate() {
+            let size = ticket.players.len();
+            if let Some(team) = (0..2).find(|team| sizes[*team] + size <= team_size) {
+                teams[team].push(index);
+                sizes[team] += size;
+            }
+
+            if sizes == [team_size; 2] {
+                let taken = teams.concat();
+                let players = teams.map(|team| {
+                    team.iter()
+                        .flat_map(|index| queue[*index].players.clone())
+                        .collect()
+                });
 
-                drop(player_room_map);
+                *queue = queue
+                    .drain(..)
+                    .enumerate()
+                    .filter(|(index, _)| !taken.contains(index))
+                    .map(|(_, ticket)| ticket)
+                    .collect();
 
-                let room = room.lock().unwrap();
-                println!(
-                    "Room {} created with player {:?} and {:?}",
-                    room.id, addr1, addr2
-                );
-                drop(room);
+                return Some(players);
             }
         }
-        drop(queue);
-        drop(rooms);
+
+        None
     }
 }
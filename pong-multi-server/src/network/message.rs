@@ -0,0 +1,124 @@
+use std::net::SocketAddr;
+
+use pong_multi_shared::game::{
+    ai::Difficulty,
+    formation::{GameMode, Seat},
+};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::validation::Violation;
+
+// Everything the router task reacts to. Packets from the socket and messages
+// from the rooms and the match maker all go through the same queue.
+#[derive(Debug)]
+pub enum ServerEvent {
+    Packet {
+        addr: SocketAddr,
+        buf: Vec<u8>,
+        received_at: f64,
+    },
+
+    // Sealed with the session of the player before it goes out
+    Send {
+        addr: SocketAddr,
+        message: Value,
+    },
+    RoomCreated {
+        room_id: Uuid,
+        players: Vec<(SocketAddr, Seat)>,
+        mode: GameMode,
+        ai: Option<Difficulty>,
+        room: mpsc::Sender<RoomCommand>,
+    },
+
+    // After every point, for the room browser
+    Scored {
+        room_id: Uuid,
+        score: [u32; 2],
+    },
+
+    // The room task stopped, its remaining players go back to the lobby.
+    // No result when the server stopped before the match was decided.
+    RoomClosed {
+        room_id: Uuid,
+        players: Vec<SocketAddr>,
+        result: Option<MatchResult>,
+    },
+    Violation {
+        addr: SocketAddr,
+        violation: Violation,
+    },
+
+    // The match maker couldn't queue or seat these players, they go back to the lobby
+    JoinRefused {
+        players: Vec<SocketAddr>,
+    },
+}
+
+// Who won a match, forfeits included. Seats played by the AI are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchResult {
+    pub winners: Vec<SocketAddr>,
+    pub losers: Vec<SocketAddr>,
+}
+
+#[derive(Debug)]
+pub enum RoomCommand {
+    Input {
+        addr: SocketAddr,
+        seq: u32,
+        direction: i8,
+        view_tick: Option<u64>,
+    },
+    Vote {
+        addr: SocketAddr,
+        map: String,
+    },
+    Forfeit(SocketAddr),
+
+    // Right away or once the point is over, then resumed by the same player
+    // or a vote of the others
+    Pause {
+        addr: SocketAddr,
+        after_point: bool,
+    },
+    Resume(SocketAddr),
+
+    // Watching the match from the lobby, or done with it
+    Spectate(SocketAddr),
+    StopSpectating(SocketAddr),
+
+    // Already checked and filtered, the room adds the side of the player
+    Chat {
+        addr: SocketAddr,
+        message: Value,
+    },
+}
+
+#[derive(Debug)]
+pub enum MatchMakerCommand {
+    // Players joining together always end up on the same team
+    // With a fallback, the AI fills the room if nobody shows up in time
+    Join {
+        players: Vec<SocketAddr>,
+        mode: GameMode,
+        ai_fallback: Option<Difficulty>,
+    },
+
+    // Straight into a room against the AI, without queueing
+    Practice {
+        players: Vec<SocketAddr>,
+        mode: GameMode,
+        difficulty: Difficulty,
+    },
+    // Two teams that play each other without queueing, a party split in two
+    // or a tournament match. Tournament matches end once a team has `first_to` points.
+    Versus {
+        teams: [Vec<SocketAddr>; 2],
+        mode: GameMode,
+        first_to: Option<u32>,
+    },
+    Leave(SocketAddr),
+}
@@ -1,4 +1,15 @@
+pub mod audit;
+pub mod chat;
+pub mod clock;
+pub mod console;
+pub mod listing;
 pub mod match_maker;
+pub mod message;
+pub mod party;
 pub mod player;
 pub mod room;
+pub mod router;
 pub mod server;
+pub mod tournament;
+pub mod transport;
+pub mod validation;
@@ -0,0 +1,103 @@
+use std::net::SocketAddr;
+
+use pong_multi_shared::game::formation::GameMode;
+use rand::{seq::IndexedRandom, Rng};
+
+// Enough for a doubles match between friends
+pub const MAX_PARTY_SIZE: usize = 4;
+
+// Letters and digits that can't be mistaken for each other when read out
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+pub const CODE_LENGTH: usize = 6;
+
+// Friends who queue together. The leader is the only one who can queue,
+// for everyone, and the party stays together between matches. Parties are
+// the private rooms of the server, only joined with their code unless listed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Party {
+    pub code: String,
+
+    // The leader first, then the others in the order they joined
+    pub members: Vec<SocketAddr>,
+
+    // Private unless asked for on creation, then shown in the room browser
+    // for anyone to join
+    pub listed: bool,
+}
+
+// How a party plays in a mode
+#[derive(Debug, PartialEq)]
+pub enum Lineup {
+    // On the same team, against whoever the match maker finds
+    Team(Vec<SocketAddr>),
+
+    // Split in two halves playing each other
+    Versus([Vec<SocketAddr>; 2]),
+}
+
+impl Party {
+    pub fn new(code: String, leader: SocketAddr) -> Self {
+        Self {
+            code,
+            members: vec![leader],
+            listed: false,
+        }
+    }
+
+    pub fn leader(&self) -> SocketAddr {
+        self.members[0]
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.members.len() >= MAX_PARTY_SIZE
+    }
+
+    pub fn add(&mut self, addr: SocketAddr) -> bool {
+        if self.is_full() || self.members.contains(&addr) {
+            return false;
+        }
+
+        self.members.push(addr);
+        true
+    }
+
+    // The next member to have joined leads once the leader is gone
+    pub fn remove(&mut self, addr: &SocketAddr) {
+        self.members.retain(|member| member != addr);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    // A team if everyone fits on one, otherwise two full teams against each
+    // other. None for a party that can't be seated in this mode.
+    pub fn lineup(&self, mode: GameMode) -> Option<Lineup> {
+        let team_size = mode.team_size();
+
+        if self.members.len() <= team_size {
+            return Some(Lineup::Team(self.members.clone()));
+        }
+
+        if self.members.len() == team_size * 2 {
+            let (left, right) = self.members.split_at(team_size);
+            return Some(Lineup::Versus([left.to_vec(), right.to_vec()]));
+        }
+
+        None
+    }
+}
+
+pub fn new_code(rng: &mut impl Rng) -> String {
+    (0..CODE_LENGTH)
+        .map(|_| *CODE_ALPHABET.choose(rng).unwrap() as char)
+        .collect()
+}
+
+// Codes are read out loud and typed in, so case and spaces don't matter
+pub fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
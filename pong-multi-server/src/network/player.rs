@@ -1,31 +1,113 @@
-use std::{
-    net::SocketAddr,
-    sync::{Arc, Mutex},
+use pong_multi_shared::{
+    protocol::{Encoder, WireFormat},
+    security::packet::Session,
 };
+use serde_json::Value;
+use std::net::SocketAddr;
 use uuid::Uuid;
 
-#[derive(Debug, Default)]
+use crate::game::rating::DEFAULT_RATING;
+
+use super::{
+    chat::WordFilter,
+    validation::{ChatRate, MessageRate},
+};
+
+// For clients that don't send a name with enter
+pub const DEFAULT_NAME: &str = "Player";
+
+// Longest name kept, in characters
+pub const MAX_NAME_LENGTH: usize = 24;
+
+#[derive(Debug, Default, PartialEq)]
 pub enum PlayerStatus {
     #[default]
     Available,
+    Queued,
     InMatch,
+
+    // Watching a match from the room browser, still in the lobby for everything else
+    Spectating,
 }
 
 #[derive(Debug)]
 pub struct Player {
     pub id: Uuid,
+
+    // Picked by the player, shown next to their chat lines
+    pub name: String,
     pub addr: SocketAddr,
     pub position: (f32, f32),
     pub status: PlayerStatus,
+    pub room_id: Option<Uuid>,
+
+    // Code of the party the player is in
+    pub party: Option<String>,
+
+    // Elo rating, from the matches played since connecting
+    pub rating: f32,
+
+    // Anti-cheat state
+    pub strikes: u32,
+    pub message_rate: MessageRate,
+    pub chat_rate: ChatRate,
+
+    // Set by the handshake, every packet after it is authenticated
+    pub session: Option<Session>,
+
+    // Public key the session was made with, a plain handshake has to repeat it
+    pub client_key: Option<[u8; 32]>,
+
+    // Client version sent with enter, for the logs
+    pub build: String,
+
+    // Remembers the snapshots the player acknowledged to send deltas against
+    pub encoder: Encoder,
 }
 
 impl Player {
-    pub fn new(addr: SocketAddr) -> Arc<Mutex<Self>> {
-        Arc::new(Mutex::new(Self {
+    pub fn new(addr: SocketAddr, wire_format: WireFormat) -> Self {
+        Self {
             id: Uuid::new_v4(),
+            name: DEFAULT_NAME.to_string(),
             addr,
             status: PlayerStatus::default(),
+            room_id: None,
+            party: None,
+            rating: DEFAULT_RATING,
             position: (0.0, 0.0),
-        }))
+            strikes: 0,
+            message_rate: MessageRate::default(),
+            chat_rate: ChatRate::default(),
+            session: None,
+            client_key: None,
+            build: String::new(),
+            encoder: Encoder::new(wire_format),
+        }
+    }
+
+    // Names are shown to other players, so they get the same treatment as chat
+    pub fn set_name(&mut self, name: &str, filter: &WordFilter) {
+        let name: String = name
+            .trim()
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(MAX_NAME_LENGTH)
+            .collect();
+
+        self.name = match name.trim() {
+            "" => DEFAULT_NAME.to_string(),
+            name => filter.apply(name),
+        };
+    }
+
+    // Encode a message for this player, secured once the session is set up
+    pub fn seal(&mut self, message: &Value) -> Vec<u8> {
+        let payload = self.encoder.encode(message);
+
+        match self.session.as_mut() {
+            Some(session) => session.seal(&payload),
+            None => payload,
+        }
     }
 }
@@ -1,41 +1,842 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::SocketAddr,
-    sync::{Arc, Mutex},
+    time::Duration,
 };
+
+use pong_multi_shared::game::{
+    ai::{AiController, Difficulty},
+    formation::{Formation, GameMode, Seat},
+    map::Map,
+    power_up::{Lock, PowerUp, SpawnTable, POWER_UP_SIZE},
+    state::GameState,
+    Side, FIELD_HEIGHT, FIELD_WIDTH, TICK_DT, TICK_RATE,
+};
+use rand::Rng;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
-use super::player::Player;
+use crate::game::{
+    history::{StateHistory, MAX_COMPENSATION_TICKS},
+    pause::{Pause, PausePhase, PauseRules},
+    vote::{MapVote, VOTE_TICKS},
+};
+
+use super::{
+    clock::server_time_ms,
+    message::{MatchResult, RoomCommand, ServerEvent},
+    validation::{check_tick, Throttle, TokenBucket, Violation, MAX_INPUT_BURST},
+};
+
+// Send a snapshot every N ticks (20 per second at 60 ticks per second)
+pub const SNAPSHOT_INTERVAL: u64 = 3;
+
+// Power-ups spawn this far from the center line at least, clear of the locked blocks
+const POWER_UP_MIN_X: f32 = 64.0;
+
+// What the match maker picked for a room
+#[derive(Debug, Clone)]
+pub struct RoomSettings {
+    pub formation: Formation,
+    pub power_ups: SpawnTable,
+
+    // Maps the players vote on, a single one is played without asking
+    pub maps: Vec<Map>,
+
+    // Plays every seat no player sits in
+    pub ai: Option<Difficulty>,
+
+    // Points that win the match, matches go on until someone leaves without it
+    pub first_to: Option<u32>,
+
+    pub pauses: PauseRules,
+}
 
 #[derive(Debug)]
 pub struct Room {
     pub id: Uuid,
-    pub players: HashMap<SocketAddr, Arc<Mutex<Player>>>,
+    pub seats: HashMap<SocketAddr, Seat>,
+
+    // Watching from the lobby, they get what the players see but can't play
+    pub spectators: HashSet<SocketAddr>,
+
+    // Paddles steered by the server, they send their input every tick
+    pub ai: Option<Difficulty>,
+    pub bots: Vec<(Seat, AiController)>,
+
+    // Sequence number of the last input applied for each player
+    pub last_inputs: HashMap<SocketAddr, u32>,
+
+    // How many ticks behind the server each player is seeing the ball
+    pub view_delays: HashMap<SocketAddr, u64>,
+
+    // One input allowed per simulated tick, so paddles can't move faster than normal
+    pub input_budgets: HashMap<SocketAddr, TokenBucket>,
+    pub state: GameState,
+    pub history: StateHistory,
+
+    pub power_ups: SpawnTable,
+
+    // Tick at which the next power-up shows up, if the field is empty by then
+    pub next_power_up: u64,
+
+    // Running until the players have picked the map, the match starts after it
+    pub vote: Option<MapVote>,
+
+    // The ball stays put while paused and during the countdown after it
+    pub pause: Pause,
+
+    pub first_to: Option<u32>,
+
+    // Who won, once the match is decided
+    pub result: Option<MatchResult>,
+
+    // Set when the match is over, stops the room task
+    pub closed: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum InputOutcome {
+    Applied,
+
+    // Duplicate, reordered or throttled input, dropped without a strike
+    Dropped,
+    Rejected(Violation),
 }
 
 impl Room {
-    pub fn new(
-        player1: Arc<Mutex<Player>>,
-        player2: Arc<Mutex<Player>>,
-    ) -> (Uuid, Arc<Mutex<Self>>) {
+    pub fn new(id: Uuid, players: &[(SocketAddr, Seat)], settings: RoomSettings) -> Self {
+        let seats: HashMap<SocketAddr, Seat> = players.iter().copied().collect();
+
+        let input_budgets = seats
+            .keys()
+            .map(|addr| (*addr, TokenBucket::new(MAX_INPUT_BURST)))
+            .collect();
+
+        let bots = match settings.ai {
+            Some(difficulty) => settings
+                .formation
+                .seats()
+                .filter(|seat| !seats.values().any(|taken| taken == seat))
+                .map(|seat| (seat, AiController::new(difficulty)))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut state = GameState::with_formation(settings.formation);
+        if settings.power_ups.has_locks() {
+            state.lock = Some(Lock::default());
+        }
+
+        let mut maps = settings.maps;
+        let vote = if maps.len() > 1 {
+            Some(MapVote::new(maps))
+        } else {
+            if let Some(map) = maps.pop() {
+                state.set_map(map);
+            }
+            None
+        };
+
+        Self {
+            id,
+            seats,
+            spectators: HashSet::new(),
+            ai: settings.ai,
+            bots,
+            last_inputs: HashMap::new(),
+            view_delays: HashMap::new(),
+            input_budgets,
+            state,
+            history: StateHistory::default(),
+            power_ups: settings.power_ups,
+            next_power_up: settings.power_ups.interval_ticks,
+            vote,
+            pause: Pause::new(settings.pauses),
+            first_to: settings.first_to,
+            result: None,
+            closed: false,
+        }
+    }
+
+    // Start the task that owns the room, it runs the authoritative simulation
+    // until the match is over and takes commands from the router
+    pub fn spawn(
+        players: Vec<(SocketAddr, Seat)>,
+        settings: RoomSettings,
+        events: mpsc::Sender<ServerEvent>,
+    ) -> (Uuid, mpsc::Sender<RoomCommand>) {
         let room_id = Uuid::new_v4();
-        let mut players: HashMap<SocketAddr, Arc<Mutex<Player>>> = HashMap::new();
+        let (tx, rx) = mpsc::channel(256);
+
+        let room = Self::new(room_id, &players, settings);
+        tokio::spawn(room.run(rx, events));
+
+        (room_id, tx)
+    }
+
+    async fn run(mut self, mut rx: mpsc::Receiver<RoomCommand>, events: mpsc::Sender<ServerEvent>) {
+        send_all(&events, self.match_found_messages()).await;
+        send_all(&events, self.map_messages()).await;
+
+        let mut interval = tokio::time::interval(Duration::from_secs_f32(TICK_DT));
+
+        while !self.closed {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if self.vote.is_some() {
+                        let messages = self.tick_vote();
+                        send_all(&events, messages).await;
+                    } else if !self.pause.is_running() {
+                        if self.pause.tick() {
+                            send_all(&events, self.pause_messages()).await;
+                        }
+                    } else if let Some(scorer) = self.step() {
+                        println!(
+                            "Room {}: {} scored ({} - {})",
+                            self.id,
+                            scorer.as_str(),
+                            self.state.score[0],
+                            self.state.score[1]
+                        );
+                        let scored = ServerEvent::Scored {
+                            room_id: self.id,
+                            score: self.state.score,
+                        };
+                        let _ = events.send(scored).await;
+                        send_all(&events, self.check_winner(scorer)).await;
+
+                        if !self.closed && self.pause.point_over() {
+                            send_all(&events, self.pause_messages()).await;
+                        }
+                    }
+
+                    // The tick doesn't move while paused, there is nothing new to send
+                    let running = self.vote.is_none() && self.pause.is_running();
+                    if running && self.state.tick.is_multiple_of(SNAPSHOT_INTERVAL) {
+                        send_all(&events, self.snapshot_messages()).await;
+                    }
+                }
+
+                command = rx.recv() => match command {
+                    Some(RoomCommand::Input { addr, seq, direction, view_tick }) => {
+                        let outcome = self.apply_input(&addr, seq, direction, view_tick);
+                        if let InputOutcome::Rejected(violation) = outcome {
+                            let _ = events.send(ServerEvent::Violation { addr, violation }).await;
+                        }
+                    }
+                    Some(RoomCommand::Vote { addr, map }) => {
+                        let messages = self.cast_vote(&addr, &map);
+                        send_all(&events, messages).await;
+                    }
+                    Some(RoomCommand::Chat { addr, message }) => {
+                        let messages = self.chat(&addr, message);
+                        send_all(&events, messages).await;
+                    }
+                    Some(RoomCommand::Pause { addr, after_point }) => {
+                        let messages = self.request_pause(&addr, after_point);
+                        send_all(&events, messages).await;
+                    }
+                    Some(RoomCommand::Resume(addr)) => {
+                        let messages = self.resume(&addr);
+                        send_all(&events, messages).await;
+                    }
+                    Some(RoomCommand::Forfeit(loser)) => {
+                        let messages = self.forfeit(&loser);
+                        send_all(&events, messages).await;
+                    }
+                    Some(RoomCommand::Spectate(addr)) => {
+                        let messages = self.spectate(addr);
+                        send_all(&events, messages).await;
+                    }
+                    Some(RoomCommand::StopSpectating(addr)) => {
+                        self.spectators.remove(&addr);
+                    }
+
+                    // The router is gone, so is the server
+                    None => return,
+                },
+            }
+        }
+
+        let closed = ServerEvent::RoomClosed {
+            room_id: self.id,
+            players: self.seats.keys().copied().collect(),
+            result: self.result.take(),
+        };
+        let _ = events.send(closed).await;
+    }
+
+    // Count down the vote, returns the picked map for everyone once it is over
+    fn tick_vote(&mut self) -> Vec<(SocketAddr, Value)> {
+        let Some(vote) = self.vote.as_mut() else {
+            return Vec::new();
+        };
+
+        vote.tick();
+        if !vote.is_over(self.seats.len()) {
+            return Vec::new();
+        }
+
+        self.finish_vote()
+    }
+
+    // Votes for maps that aren't on the ballot or after the vote are ignored
+    pub fn cast_vote(&mut self, addr: &SocketAddr, map: &str) -> Vec<(SocketAddr, Value)> {
+        let Some(vote) = self.vote.as_mut() else {
+            return Vec::new();
+        };
+
+        if !self.seats.contains_key(addr) || !vote.cast(*addr, map) {
+            println!("Room {}: ignored vote for {map} from {:?}", self.id, addr);
+            return Vec::new();
+        }
+
+        if !vote.is_over(self.seats.len()) {
+            return Vec::new();
+        }
+
+        self.finish_vote()
+    }
+
+    fn finish_vote(&mut self) -> Vec<(SocketAddr, Value)> {
+        let Some(vote) = self.vote.take() else {
+            return Vec::new();
+        };
+
+        let map = vote.winner().clone();
+        println!(
+            "Room {}: playing on {} ({} of {} voted)",
+            self.id,
+            map.name,
+            vote.votes.len(),
+            self.seats.len()
+        );
+        self.state.set_map(map);
+
+        self.map_messages()
+    }
+
+    // The ballot while voting, then the map picked. Matches on the classic field
+    // without a vote look the same as before maps existed.
+    fn map_messages(&self) -> Vec<(SocketAddr, Value)> {
+        let message = match &self.vote {
+            Some(vote) => json!({
+                "action": "map_vote",
+                "room_id": self.id.to_string(),
+                "maps": vote.names(),
+                "seconds": VOTE_TICKS / TICK_RATE as u64,
+            }),
+            None if self.state.map == Map::classic() => return Vec::new(),
+            None => json!({
+                "action": "map_picked",
+                "room_id": self.id.to_string(),
+                "map": self.state.map.to_json(),
+            }),
+        };
+
+        self.audience()
+            .map(|addr| (*addr, message.clone()))
+            .collect()
+    }
+
+    // Advance the simulation by one tick, returns the side that scored if any
+    fn step(&mut self) -> Option<Side> {
+        self.move_bots();
+        self.state.advance();
+
+        for budget in self.input_budgets.values_mut() {
+            budget.refill(1.0);
+        }
+
+        let seats: Vec<(SocketAddr, Seat)> = self
+            .seats
+            .iter()
+            .map(|(addr, seat)| (*addr, *seat))
+            .collect();
+        for (addr, seat) in seats {
+            self.compensate_hit(&addr, seat);
+        }
+
+        self.history.record(&self.state);
+
+        if let Some((seat, kind)) = self.state.collect_power_up() {
+            println!(
+                "Room {}: {} picked up a {}",
+                self.id,
+                seat.key(),
+                kind.as_str()
+            );
+        }
+        self.spawn_power_up();
+
+        let scorer = self.state.score_point();
+        if scorer.is_some() {
+            // The ball was served again, old frames would rewind into the previous point
+            self.history.clear();
+        }
+
+        scorer
+    }
+
+    // The AI gets the same paddle moves as a player, stuns included
+    fn move_bots(&mut self) {
+        let mut rng = rand::rng();
 
-        let p1 = player1.lock().unwrap();
-        let p2 = player2.lock().unwrap();
+        for (seat, bot) in &mut self.bots {
+            let direction = bot.direction(&self.state, *seat, &mut rng);
+            if !self.state.is_stunned(seat.side) {
+                self.state.paddle_mut(*seat).apply_input(direction);
+            }
+        }
+    }
+
+    // Put a power-up from the spawn table somewhere around the middle of the field,
+    // away from the walls and the center line
+    fn spawn_power_up(&mut self) {
+        if self.power_ups.is_off()
+            || self.state.power_up.is_some()
+            || self.state.tick < self.next_power_up
+        {
+            return;
+        }
+        self.next_power_up = self.state.tick + self.power_ups.interval_ticks;
+
+        let mut rng = rand::rng();
+        let Some(kind) = self
+            .power_ups
+            .pick(rng.random_range(0..self.power_ups.total_weight()))
+        else {
+            return;
+        };
+
+        let x = rng.random_range(POWER_UP_MIN_X..FIELD_WIDTH / 4.0);
+        let limit = FIELD_HEIGHT / 2.0 - POWER_UP_SIZE;
+        let power_up = PowerUp {
+            kind,
+            x: if rng.random_bool(0.5) { x } else { -x },
+            y: rng.random_range(-limit..limit),
+        };
+        self.state.power_up = Some(power_up);
+
+        println!(
+            "Room {}: {} spawned at ({:.0}, {:.0})",
+            self.id,
+            kind.as_str(),
+            power_up.x,
+            power_up.y
+        );
+    }
+
+    // The ball just went past a paddle. Check whether the player, who sees the ball
+    // a few ticks in the past, saw it touch their paddle and count the hit if so.
+    // Their own paddle is predicted on their side, so it is compared at its current position.
+    fn compensate_hit(&mut self, addr: &SocketAddr, seat: Seat) {
+        let side = seat.side;
+        let paddle = *self.state.paddle(seat);
+        if !self.state.ball.moving_towards(side) || !self.state.ball.behind(&paddle) {
+            return;
+        }
+
+        let delay = self.view_delays.get(addr).copied().unwrap_or(0);
+        if delay == 0 {
+            return;
+        }
+
+        // Allow one tick either way for the client interpolating between two ticks
+        let seen_tick = self.state.tick.saturating_sub(delay);
+        let hit = (seen_tick.saturating_sub(1)..=seen_tick + 1)
+            .filter_map(|tick| self.history.at(tick))
+            .find(|frame| frame.ball.moving_towards(side) && frame.ball.overlaps(&paddle))
+            .cloned();
+
+        let Some(frame) = hit else {
+            return;
+        };
+
+        // Bounce the rewound ball and bring it back to the present
+        let mut ball = frame.ball;
+        ball.bounce_off(&paddle);
+        for _ in frame.tick..self.state.tick {
+            ball.advance(TICK_DT);
+        }
+        self.state.ball = ball;
+
+        println!(
+            "Room {}: compensated hit for {:?} on the {} paddle ({} ticks back)",
+            self.id,
+            addr,
+            seat.key(),
+            self.state.tick - frame.tick
+        );
+    }
+
+    // Apply one tick of paddle movement for a player.
+    // Inputs older than the last applied one were reordered by the network and are dropped.
+    // `view_tick` is the server tick the player was looking at when sending the input.
+    pub fn apply_input(
+        &mut self,
+        addr: &SocketAddr,
+        seq: u32,
+        direction: i8,
+        view_tick: Option<u64>,
+    ) -> InputOutcome {
+        let Some(seat) = self.seats.get(addr).copied() else {
+            return InputOutcome::Dropped;
+        };
+
+        // Nothing moves before the map is picked or while paused
+        if self.vote.is_some() || !self.pause.is_running() {
+            return InputOutcome::Dropped;
+        }
+
+        if let Some(view_tick) = view_tick {
+            if let Err(violation) = check_tick(view_tick, self.state.tick) {
+                return InputOutcome::Rejected(violation);
+            }
+        }
+
+        let last = self.last_inputs.entry(*addr).or_insert(0);
+        if seq <= *last {
+            return InputOutcome::Dropped;
+        }
+
+        if let Some(budget) = self.input_budgets.get_mut(addr) {
+            match budget.take() {
+                Throttle::Allowed => {}
+                Throttle::Refused { first: true } => {
+                    return InputOutcome::Rejected(Violation::PaddleSpeed)
+                }
+                Throttle::Refused { first: false } => return InputOutcome::Dropped,
+            }
+        }
+
+        *last = seq;
+
+        if let Some(view_tick) = view_tick {
+            let delay = self
+                .state
+                .tick
+                .saturating_sub(view_tick)
+                .min(MAX_COMPENSATION_TICKS);
+            self.view_delays.insert(*addr, delay);
+        }
+
+        // Stunned paddles stay put, the input still counts so the client
+        // stops replaying it once the snapshot acknowledges it
+        if !self.state.is_stunned(seat.side) {
+            self.state.paddle_mut(seat).apply_input(direction);
+        }
+
+        InputOutcome::Applied
+    }
+
+    // End the match once the team that scored has enough points
+    pub fn check_winner(&mut self, scorer: Side) -> Vec<(SocketAddr, Value)> {
+        let Some(first_to) = self.first_to else {
+            return Vec::new();
+        };
+        if self.state.score[scorer.index()] < first_to {
+            return Vec::new();
+        }
+
+        self.closed = true;
+        self.result = Some(self.result_for(scorer, None));
+
+        let message = json!({
+            "action": "match_over",
+            "winner": scorer.as_str(),
+            "score": {
+                "left": self.state.score[Side::Left.index()],
+                "right": self.state.score[Side::Right.index()],
+            },
+        });
+
+        self.audience()
+            .map(|addr| (*addr, message.clone()))
+            .collect()
+    }
+
+    // The players of each team, `gone` is a loser no longer seated
+    fn result_for(&self, winner: Side, gone: Option<SocketAddr>) -> MatchResult {
+        let team = |side: Side| -> Vec<SocketAddr> {
+            self.seats
+                .iter()
+                .filter(|(_, seat)| seat.side == side)
+                .map(|(addr, _)| *addr)
+                .collect()
+        };
+
+        let mut losers = team(winner.opponent());
+        losers.extend(gone);
+
+        MatchResult {
+            winners: team(winner),
+            losers,
+        }
+    }
+
+    // End the match in favour of the other team, returns the messages for the remaining players
+    pub fn forfeit(&mut self, loser: &SocketAddr) -> Vec<(SocketAddr, Value)> {
+        self.closed = true;
+
+        let Some(loser_seat) = self.seats.remove(loser) else {
+            return Vec::new();
+        };
+
+        // The first to leave loses, leaving a match that is already over changes nothing
+        if self.result.is_none() {
+            let winner = loser_seat.side.opponent();
+            self.result = Some(self.result_for(winner, Some(*loser)));
+        }
+
+        let message = json!({
+            "action": "match_over",
+            "winner": loser_seat.side.opponent().as_str(),
+            "reason": "forfeit",
+            "score": {
+                "left": self.state.score[Side::Left.index()],
+                "right": self.state.score[Side::Right.index()],
+            },
+        });
 
-        players.insert(p1.addr, player1.clone());
-        players.insert(p2.addr, player2.clone());
+        self.audience()
+            .map(|addr| (*addr, message.clone()))
+            .collect()
+    }
+
+    // A chat line for everyone in the room, the sender included, with the
+    // side they play on
+    pub fn chat(&self, addr: &SocketAddr, mut message: Value) -> Vec<(SocketAddr, Value)> {
+        let Some(seat) = self.seats.get(addr) else {
+            return Vec::new();
+        };
+        message["side"] = json!(seat.side.as_str());
+
+        self.audience()
+            .map(|addr| (*addr, message.clone()))
+            .collect()
+    }
+
+    // Paused straight away or after the point, refused once the player used
+    // up their pauses or the match is already paused
+    pub fn request_pause(
+        &mut self,
+        addr: &SocketAddr,
+        after_point: bool,
+    ) -> Vec<(SocketAddr, Value)> {
+        if !self.seats.contains_key(addr) || self.vote.is_some() || self.closed {
+            println!("Room {}: ignored pause from {:?}", self.id, addr);
+            return Vec::new();
+        }
+
+        if let Err(refused) = self.pause.request(*addr, after_point) {
+            let message = json!({
+                "action": "pause_refused",
+                "reason": refused.as_str(),
+            });
+            return vec![(*addr, message)];
+        }
+
+        println!(
+            "Room {}: {:?} asked for a pause ({} left)",
+            self.id,
+            addr,
+            self.pause.pauses_left(addr)
+        );
+        self.pause_messages()
+    }
+
+    // The player who paused resumes, anyone else votes for it
+    pub fn resume(&mut self, addr: &SocketAddr) -> Vec<(SocketAddr, Value)> {
+        if !self.seats.contains_key(addr) || !self.pause.resume(*addr, self.seats.len()) {
+            return Vec::new();
+        }
+
+        self.pause_messages()
+    }
+
+    // Where the pause is at for everyone in the room. Players also learn
+    // whether they asked for it and how many pauses they have left.
+    pub fn pause_messages(&self) -> Vec<(SocketAddr, Value)> {
+        let mut message = json!({
+            "action": "pause_state",
+            "state": self.pause.phase.as_str(),
+        });
+
+        let by = match &self.pause.phase {
+            PausePhase::Pending { by } => Some(*by),
+            PausePhase::Paused { by, votes, .. } => {
+                message["votes"] = json!(votes.len());
+                message["needed"] = json!(Pause::votes_needed(self.seats.len()));
+                Some(*by)
+            }
+            _ => None,
+        };
+        if let Some(seat) = by.and_then(|by| self.seats.get(&by)) {
+            message["side"] = json!(seat.side.as_str());
+        }
+        if matches!(
+            self.pause.phase,
+            PausePhase::Paused { .. } | PausePhase::Resuming { .. }
+        ) {
+            message["seconds"] = json!(self.pause.seconds_left());
+        }
+
+        self.audience()
+            .map(|addr| {
+                let mut message = message.clone();
+                if self.seats.contains_key(addr) {
+                    message["yours"] = json!(by == Some(*addr));
+                    message["pauses_left"] = json!(self.pause.pauses_left(addr));
+                }
+                (*addr, message)
+            })
+            .collect()
+    }
+
+    // Players and spectators, who get everything about the match
+    fn audience(&self) -> impl Iterator<Item = &SocketAddr> {
+        self.seats.keys().chain(&self.spectators)
+    }
+
+    // Start sending the match to someone watching from the lobby, with what
+    // the players were told about the field when it started
+    pub fn spectate(&mut self, addr: SocketAddr) -> Vec<(SocketAddr, Value)> {
+        if self.seats.contains_key(&addr) || !self.spectators.insert(addr) {
+            return Vec::new();
+        }
 
-        drop(p1);
-        drop(p2);
+        let formation = &self.state.formation;
+        let mut message = json!({
+            "action": "spectating",
+            "room_id": self.id.to_string(),
+            "mode": formation.mode.as_str(),
+        });
+        if formation.mode != GameMode::Singles {
+            message["depths"] = json!(formation.depths);
+        }
+        if let Some(difficulty) = self.ai {
+            message["ai"] = json!(difficulty.as_str());
+        }
 
-        let room = Arc::new(Mutex::new(Self {
-            id: room_id,
-            players,
-        }));
+        // Nothing to vote on, they get the map once it is picked
+        let mut messages = vec![(addr, message)];
+        if self.vote.is_none() {
+            messages.extend(self.map_messages());
+        }
+        if self.pause.phase != PausePhase::Playing {
+            messages.extend(self.pause_messages());
+        }
+        messages.retain(|(to, _)| *to == addr);
+        messages
+    }
+
+    // Singles keep the message they had before doubles, doubles add where the
+    // paddles of each team stand
+    fn match_found_messages(&self) -> Vec<(SocketAddr, Value)> {
+        let formation = &self.state.formation;
+
+        self.seats
+            .iter()
+            .map(|(addr, seat)| {
+                let mut message = json!({
+                    "action": "match_found",
+                    "room_id": self.id.to_string(),
+                    "side": seat.side.as_str(),
+                });
+                if formation.mode != GameMode::Singles {
+                    message["mode"] = json!(formation.mode.as_str());
+                    message["slot"] = json!(seat.slot);
+                    message["depths"] = json!(formation.depths);
+                }
+                if let Some(difficulty) = self.ai {
+                    message["ai"] = json!(difficulty.as_str());
+                }
+                (*addr, message)
+            })
+            .collect()
+    }
+
+    // Every player gets the same state, plus the last input of theirs it
+    // includes. Spectators never sent any.
+    fn snapshot_messages(&self) -> Vec<(SocketAddr, Value)> {
+        let state = &self.state;
+        let time = server_time_ms();
+
+        let paddles: serde_json::Map<String, Value> = state
+            .formation
+            .seats()
+            .zip(&state.paddles)
+            .map(|(seat, paddle)| (seat.key(), json!(paddle.y)))
+            .collect();
+
+        self.audience()
+            .map(|addr| {
+                let mut message = json!({
+                    "action": "snapshot",
+                    "tick": state.tick,
+                    "time": time,
+                    "ack": self.last_inputs.get(addr).copied().unwrap_or(0),
+                    "ball": {
+                        "x": state.ball.x,
+                        "y": state.ball.y,
+                        "vx": state.ball.vx,
+                        "vy": state.ball.vy,
+                    },
+                    "paddles": paddles,
+                    "score": {
+                        "left": state.score[Side::Left.index()],
+                        "right": state.score[Side::Right.index()],
+                    },
+                });
+                add_power_ups(state, &mut message);
+                (*addr, message)
+            })
+            .collect()
+    }
+}
+
+// Only what is going on, so snapshots of matches without power-ups stay the same
+fn add_power_ups(state: &GameState, message: &mut Value) {
+    if let Some(extra) = &state.extra_ball {
+        message["extra_ball"] = json!({
+            "x": extra.ball.x,
+            "y": extra.ball.y,
+            "vx": extra.ball.vx,
+            "vy": extra.ball.vy,
+        });
+    }
+
+    if let Some(power_up) = &state.power_up {
+        message["power_up"] = json!({
+            "kind": power_up.kind.id(),
+            "x": power_up.x,
+            "y": power_up.y,
+        });
+    }
+
+    for side in [Side::Left, Side::Right] {
+        if state.is_stunned(side) {
+            message["stunned"][side.as_str()] = json!(state.stunned[side.index()]);
+        }
+    }
+
+    if let Some(lock) = &state.lock {
+        message["lock"] = json!({ "open": lock.open_ticks });
+    }
+}
 
-        (room_id, room)
+// Hand messages to the router, which secures and sends them
+pub async fn send_all(events: &mpsc::Sender<ServerEvent>, messages: Vec<(SocketAddr, Value)>) {
+    for (addr, message) in messages {
+        if events
+            .send(ServerEvent::Send { addr, message })
+            .await
+            .is_err()
+        {
+            return;
+        }
     }
-    pub async fn start(self: Arc<Self>) {}
 }
@@ -0,0 +1,1318 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+
+use pong_multi_shared::{
+    chat::ChatContent,
+    game::{
+        ai::Difficulty,
+        formation::{GameMode, Seat},
+        Side,
+    },
+    protocol::{decode, encode, negotiate, WireFormat, PROTOCOL_VERSION},
+    security::{key_from_hex, packet::is_secure, to_hex, Handshake, Role},
+};
+use serde_json::{json, Value};
+use tokio::{
+    sync::{mpsc, watch},
+    time::Instant,
+};
+use uuid::Uuid;
+
+use crate::game::{bracket::BracketFormat, rating};
+
+use super::{
+    audit::AuditLog,
+    clock::server_time_ms,
+    listing::{page, ListingKind, RoomFilter, RoomListing, ROOMS_PER_PAGE},
+    message::{MatchMakerCommand, MatchResult, RoomCommand, ServerEvent},
+    party::{new_code, normalize_code, Lineup, Party, MAX_PARTY_SIZE},
+    player::{Player, PlayerStatus},
+    server::ServerConfig,
+    tournament::{no_show_winner, Entrant, Tournament},
+    transport::Transport,
+    validation::{Throttle, Violation, MAX_STRIKES},
+};
+
+// Actions accepted before the session is set up
+const PLAIN_ACTIONS: [&str; 3] = ["enter", "handshake", "ping"];
+
+// How often tournament matches are checked for players who became free or didn't show up
+const TOURNAMENT_CHECK: Duration = Duration::from_secs(1);
+
+// Owns the players and their sessions, and knows which room each of them is in.
+// Everything here is only touched by the router task, one event at a time.
+#[derive(Debug)]
+pub struct Router<T: Transport> {
+    pub transport: Arc<T>,
+    pub players: HashMap<SocketAddr, Player>,
+    pub rooms: HashMap<Uuid, mpsc::Sender<RoomCommand>>,
+
+    // What the room browser shows of each room
+    pub listings: HashMap<Uuid, RoomListing>,
+
+    // By code, a party lasts until its last member leaves
+    pub parties: HashMap<String, Party>,
+
+    // The last tournament hosted, kept once finished to show its bracket
+    pub tournament: Option<Tournament>,
+    pub match_maker: mpsc::Sender<MatchMakerCommand>,
+    pub audit_log: AuditLog,
+
+    pub config: ServerConfig,
+}
+
+impl<T: Transport> Router<T> {
+    pub fn new(
+        transport: Arc<T>,
+        match_maker: mpsc::Sender<MatchMakerCommand>,
+        audit_log: AuditLog,
+        config: ServerConfig,
+    ) -> Self {
+        Self {
+            transport,
+            players: HashMap::new(),
+            rooms: HashMap::new(),
+            listings: HashMap::new(),
+            parties: HashMap::new(),
+            tournament: None,
+            match_maker,
+            audit_log,
+            config,
+        }
+    }
+
+    pub async fn run(
+        mut self,
+        mut events: mpsc::Receiver<ServerEvent>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        let mut tournament_check = tokio::time::interval(TOURNAMENT_CHECK);
+
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = tournament_check.tick() => {
+                    self.run_tournament().await;
+                    continue;
+                }
+                _ = shutdown.changed() => return,
+            };
+            let Some(event) = event else {
+                return;
+            };
+
+            match event {
+                ServerEvent::Packet {
+                    addr,
+                    buf,
+                    received_at,
+                } => self.process(addr, buf, received_at).await,
+
+                ServerEvent::Send { addr, message } => self.send_to(&addr, &message).await,
+
+                ServerEvent::RoomCreated {
+                    room_id,
+                    players,
+                    mode,
+                    ai,
+                    room,
+                } => self.handle_room_created(room_id, players, mode, ai, room),
+
+                ServerEvent::Scored { room_id, score } => {
+                    if let Some(listing) = self.listings.get_mut(&room_id) {
+                        listing.score = score;
+                    }
+                }
+
+                ServerEvent::RoomClosed {
+                    room_id,
+                    players,
+                    result,
+                } => self.handle_room_closed(room_id, players, result).await,
+
+                ServerEvent::Violation { addr, violation } => self.flag(&addr, violation).await,
+
+                ServerEvent::JoinRefused { players } => self.handle_join_refused(&players),
+            }
+        }
+    }
+
+    // Process user request
+    async fn process(&mut self, addr: SocketAddr, buf: Vec<u8>, received_at: f64) {
+        println!("Bytes received: {:?} from {:?}", buf.len(), addr);
+
+        // Secured packets are checked against the session of the address they come from
+        let authenticated = is_secure(&buf);
+        let payload = if authenticated {
+            match self.open_packet(&addr, &buf) {
+                Some(payload) => payload,
+                None => return,
+            }
+        } else {
+            buf
+        };
+
+        // Clients may send JSON or binary, whatever we send them
+        let message = decode(&payload);
+
+        match &message {
+            Ok(json) => println!("Message received: {}", json),
+            Err(e) => println!("Invalid message received from {:?}: {}", addr, e),
+        }
+
+        // Only authenticated messages count, so nobody can spend another player's budget
+        if authenticated && !self.check_message_rate(&addr).await {
+            return;
+        }
+
+        let Ok(json) = message else {
+            return;
+        };
+        let Some(action) = json["action"].as_str() else {
+            return;
+        };
+
+        if !authenticated && !PLAIN_ACTIONS.contains(&action) {
+            println!("Unauthenticated {action} from {:?} dropped", addr);
+            return;
+        }
+
+        match action {
+            "enter" => self.handle_enter(&addr, &json, authenticated).await,
+
+            "handshake" => self.handle_handshake(&addr, &json, authenticated).await,
+
+            "join" => self.handle_join(&addr, &json),
+
+            "leave" => self.handle_leave(&addr),
+
+            "move" => self.handle_move(&addr, &json).await,
+
+            "vote" => self.handle_vote(&addr, &json).await,
+
+            "pause" | "resume" => self.handle_pause(&addr, action, &json).await,
+
+            "chat" => self.handle_chat(&addr, &json).await,
+
+            "party_create" => self.handle_party_create(&addr, &json).await,
+
+            "party_invite" => self.handle_party_invite(&addr, &json).await,
+
+            "party_join" => self.handle_party_join(&addr, &json).await,
+
+            "party_leave" => self.handle_party_leave(&addr).await,
+
+            "tournament_create" => self.handle_tournament_create(&addr, &json).await,
+
+            "tournament_register" => self.handle_tournament_register(&addr).await,
+
+            "tournament_unregister" => self.handle_tournament_unregister(&addr).await,
+
+            "tournament_start" => self.handle_tournament_start(&addr).await,
+
+            "list_rooms" => self.handle_list_rooms(&addr, &json).await,
+
+            "spectate" => self.handle_spectate(&addr, &json),
+
+            "ack" => self.handle_ack(&addr, &json),
+
+            "ping" => self.handle_ping(&addr, &json, received_at).await,
+
+            _ => println!("Unknow action : {action}"),
+        }
+    }
+
+    fn open_packet(&mut self, addr: &SocketAddr, buf: &[u8]) -> Option<Vec<u8>> {
+        let session = self.players.get_mut(addr)?.session.as_mut()?;
+
+        match session.open(buf) {
+            Ok(payload) => Some(payload),
+            Err(e) => {
+                println!("Dropped packet from {:?}: {}", addr, e);
+                None
+            }
+        }
+    }
+
+    // Send a message, secured with the session of the player if there is one
+    async fn send_to(&mut self, addr: &SocketAddr, message: &Value) {
+        let packet = match self.players.get_mut(addr) {
+            Some(player) => player.seal(message),
+            None => encode(message, self.config.wire_format),
+        };
+
+        self.send_packet(addr, &packet).await;
+    }
+
+    async fn send_packet(&self, addr: &SocketAddr, packet: &[u8]) {
+        if let Err(e) = self.transport.send_to(packet, *addr).await {
+            eprintln!("Error sending packet to {:?}: {:?}", addr, e);
+        }
+    }
+
+    // Agree on a protocol version, or tell the client to update. Clients from
+    // before the negotiation don't send a version and speak the first one.
+    async fn handle_enter(&mut self, addr: &SocketAddr, json: &Value, authenticated: bool) {
+        // Once there is a session, only its owner can change the version or the name
+        let has_session = self
+            .players
+            .get(addr)
+            .is_some_and(|player| player.session.is_some());
+        if has_session && !authenticated {
+            println!(
+                "Plain enter for a player with a session from {:?} dropped",
+                addr
+            );
+            return;
+        }
+
+        let version = |field: &str, default: u8| {
+            json[field]
+                .as_u64()
+                .map_or(default, |version| version.min(u8::MAX as u64) as u8)
+        };
+        let client_protocol = version("protocol", 1);
+        let client_min = version("min_protocol", client_protocol);
+        let build = json["build"].as_str().unwrap_or("unknown");
+
+        let protocol = match negotiate(
+            client_min..=client_protocol,
+            self.config.min_protocol..=PROTOCOL_VERSION,
+        ) {
+            Ok(protocol) => protocol,
+            Err(mismatch) => {
+                println!(
+                    "Refused {:?} with protocol {}-{} (build {}): {:?}",
+                    addr, client_min, client_protocol, build, mismatch
+                );
+
+                // Whatever the client speaks, it can read this
+                let message = json!({
+                    "action": "rejected",
+                    "reason": mismatch.to_string(),
+                    "min_protocol": self.config.min_protocol,
+                    "max_protocol": PROTOCOL_VERSION,
+                });
+                self.send_packet(addr, &encode(&message, WireFormat::Json))
+                    .await;
+                return;
+            }
+        };
+
+        let wire_format = self.config.wire_format;
+        let player = self.players.entry(*addr).or_insert_with(|| {
+            println!("New player connected: {:?}", addr);
+            Player::new(*addr, wire_format)
+        });
+        player.encoder.version = protocol;
+        player.build = build.to_string();
+        player.set_name(
+            json["name"].as_str().unwrap_or_default(),
+            &self.config.chat_filter,
+        );
+        let player_id = player.id;
+
+        if protocol < client_protocol {
+            println!(
+                "Player {:?} (build {}) downgraded to protocol {}",
+                addr, build, protocol
+            );
+        }
+
+        // The client waits for this before the handshake
+        self.send_to(
+            addr,
+            &json!({
+                "action": "entered",
+                "player_id": player_id.to_string(),
+                "protocol": protocol,
+            }),
+        )
+        .await;
+    }
+
+    // Key exchange that sets up the session of a player. The answer goes out in the clear.
+    async fn handle_handshake(&mut self, addr: &SocketAddr, json: &Value, authenticated: bool) {
+        let Some(player) = self.players.get_mut(addr) else {
+            return;
+        };
+
+        let Some(client_key) = json["public_key"].as_str().and_then(key_from_hex) else {
+            println!("Invalid handshake received from {:?}", addr);
+            return;
+        };
+        let encrypted = json["encrypt"].as_bool().unwrap_or(false);
+
+        // Once there is a session, only its owner can replace it: with a
+        // packet of the session, or by sending the same key again before using
+        // it, when our answer got lost
+        if !authenticated && player.session.is_some() {
+            let resent = player.client_key == Some(client_key)
+                && !player.session.as_ref().is_some_and(|s| s.established());
+            if !resent {
+                println!("Handshake for an existing session from {:?} dropped", addr);
+                return;
+            }
+        }
+
+        let handshake = Handshake::new();
+        let message = json!({
+            "action": "handshake",
+            "public_key": to_hex(&handshake.public_key),
+            "encrypt": encrypted,
+        });
+
+        let packet = player.encoder.encode(&message);
+        player.session = Some(handshake.complete(Role::Server, client_key, encrypted));
+        player.client_key = Some(client_key);
+
+        self.send_packet(addr, &packet).await;
+    }
+
+    // Singles unless the player picked another mode. Players can ask to practice
+    // against the AI right away, or to play it if nobody else shows up.
+    // The leader of a party queues everyone in it, as a team or split in two
+    // teams playing each other when there are too many for one.
+    fn handle_join(&mut self, addr: &SocketAddr, json: &Value) {
+        let mode = match json["mode"].as_str() {
+            None => GameMode::Singles,
+            Some(mode) => match GameMode::parse(mode) {
+                Some(mode) => mode,
+                None => {
+                    println!("Unknown mode {mode} requested by {:?}", addr);
+                    return;
+                }
+            },
+        };
+
+        let difficulty = |field: &str| match json[field].as_str() {
+            None => Ok(None),
+            Some(value) => Difficulty::parse(value).map(Some).ok_or(value),
+        };
+        let (practice, ai_fallback) = match (difficulty("practice"), difficulty("ai_fallback")) {
+            (Ok(practice), Ok(ai_fallback)) => (practice, ai_fallback),
+            (Err(value), _) | (_, Err(value)) => {
+                println!("Unknown difficulty {value} requested by {:?}", addr);
+                return;
+            }
+        };
+
+        let Some(player) = self.players.get(addr) else {
+            return;
+        };
+
+        let lineup = match player
+            .party
+            .as_ref()
+            .and_then(|code| self.parties.get(code))
+        {
+            Some(party) if party.members.len() > 1 => {
+                if party.leader() != *addr {
+                    println!("Only the leader of party {} can queue", party.code);
+                    return;
+                }
+                match party.lineup(mode) {
+                    Some(lineup) => lineup,
+                    None => {
+                        println!(
+                            "Party {} of {} doesn't fit in {}",
+                            party.code,
+                            party.members.len(),
+                            mode
+                        );
+                        return;
+                    }
+                }
+            }
+            _ => Lineup::Team(vec![*addr]),
+        };
+
+        let members = match &lineup {
+            Lineup::Team(players) => players.clone(),
+            Lineup::Versus(teams) => teams.concat(),
+        };
+        let available = members.iter().all(|member| {
+            self.players
+                .get(member)
+                .is_some_and(|player| player.status == PlayerStatus::Available)
+        });
+        if !available {
+            return;
+        }
+
+        let join = match (lineup, practice) {
+            (Lineup::Versus(teams), _) => MatchMakerCommand::Versus {
+                teams,
+                mode,
+                first_to: None,
+            },
+            (Lineup::Team(players), Some(difficulty)) => MatchMakerCommand::Practice {
+                players,
+                mode,
+                difficulty,
+            },
+            (Lineup::Team(players), None) => MatchMakerCommand::Join {
+                players,
+                mode,
+                ai_fallback,
+            },
+        };
+        if self.match_maker.try_send(join).is_err() {
+            return;
+        }
+
+        for member in members {
+            if let Some(player) = self.players.get_mut(&member) {
+                player.status = PlayerStatus::Queued;
+            }
+        }
+    }
+
+    async fn handle_move(&mut self, addr: &SocketAddr, json: &Value) {
+        let (Some(seq), Some(direction)) = (json["seq"].as_u64(), json["direction"].as_i64())
+        else {
+            println!("Invalid move received from {:?}", addr);
+            return;
+        };
+
+        if !(-1..=1).contains(&direction) {
+            self.flag(addr, Violation::InvalidDirection(direction))
+                .await;
+            return;
+        }
+
+        let Some(room_id) = self.players.get(addr).and_then(|player| player.room_id) else {
+            return;
+        };
+
+        // A player can only steer a paddle in their own room
+        let claimed_room = json["room_id"].as_str().unwrap_or_default();
+        if claimed_room != room_id.to_string() {
+            self.flag(addr, Violation::WrongRoom(claimed_room.to_string()))
+                .await;
+            return;
+        }
+
+        let Some(room) = self.rooms.get(&room_id) else {
+            return;
+        };
+
+        // A room that can't keep up loses the input, the client sends the next one anyway
+        let _ = room.try_send(RoomCommand::Input {
+            addr: *addr,
+            seq: seq as u32,
+            direction: direction as i8,
+            view_tick: json["tick"].as_u64(),
+        });
+    }
+
+    async fn handle_vote(&mut self, addr: &SocketAddr, json: &Value) {
+        let Some(map) = json["map"].as_str() else {
+            println!("Invalid vote received from {:?}", addr);
+            return;
+        };
+
+        let Some(room_id) = self.players.get(addr).and_then(|player| player.room_id) else {
+            return;
+        };
+
+        let claimed_room = json["room_id"].as_str().unwrap_or_default();
+        if claimed_room != room_id.to_string() {
+            self.flag(addr, Violation::WrongRoom(claimed_room.to_string()))
+                .await;
+            return;
+        }
+
+        if let Some(room) = self.rooms.get(&room_id) {
+            let _ = room.try_send(RoomCommand::Vote {
+                addr: *addr,
+                map: map.to_string(),
+            });
+        }
+    }
+
+    // The room decides whether the match can be paused or resumed
+    async fn handle_pause(&mut self, addr: &SocketAddr, action: &str, json: &Value) {
+        let Some(room_id) = self.players.get(addr).and_then(|player| player.room_id) else {
+            return;
+        };
+
+        let claimed_room = json["room_id"].as_str().unwrap_or_default();
+        if claimed_room != room_id.to_string() {
+            self.flag(addr, Violation::WrongRoom(claimed_room.to_string()))
+                .await;
+            return;
+        }
+
+        let command = match action {
+            "pause" => RoomCommand::Pause {
+                addr: *addr,
+                after_point: json["after_point"].as_bool().unwrap_or(false),
+            },
+            _ => RoomCommand::Resume(*addr),
+        };
+        if let Some(room) = self.rooms.get(&room_id) {
+            let _ = room.try_send(command);
+        }
+    }
+
+    // Players in a match talk to their room, everyone else to the lobby
+    async fn handle_chat(&mut self, addr: &SocketAddr, json: &Value) {
+        let Some(mut content) = ChatContent::from_json(json) else {
+            println!("Invalid chat received from {:?}", addr);
+            return;
+        };
+
+        let Some(player) = self.players.get_mut(addr) else {
+            return;
+        };
+
+        if !player.chat_rate.check() {
+            println!("Chat from {:?} dropped, too many lines", addr);
+            return;
+        }
+
+        if let ChatContent::Text(text) = &content {
+            content = ChatContent::Text(self.config.chat_filter.apply(text));
+        }
+
+        let mut message = json!({ "action": "chat", "from": player.name });
+        content.add_to(&mut message);
+
+        if player.status == PlayerStatus::InMatch {
+            if let Some(room) = player.room_id.and_then(|room_id| self.rooms.get(&room_id)) {
+                let _ = room.try_send(RoomCommand::Chat {
+                    addr: *addr,
+                    message,
+                });
+            }
+            return;
+        }
+
+        // Only players done with the handshake can read it
+        let lobby: Vec<SocketAddr> = self
+            .players
+            .values()
+            .filter(|player| player.status != PlayerStatus::InMatch && player.session.is_some())
+            .map(|player| player.addr)
+            .collect();
+        for addr in lobby {
+            self.send_to(&addr, &message).await;
+        }
+    }
+
+    // A new party led by the player, who leaves the one they were in. Listed
+    // parties show in the room browser for anyone to join.
+    async fn handle_party_create(&mut self, addr: &SocketAddr, json: &Value) {
+        if !self.players.contains_key(addr) {
+            return;
+        }
+        self.leave_party(addr).await;
+
+        // The rng isn't Send, it has to be gone before the next await
+        let code = {
+            let mut rng = rand::rng();
+            loop {
+                let code = new_code(&mut rng);
+                if !self.parties.contains_key(&code) {
+                    break code;
+                }
+            }
+        };
+
+        let mut party = Party::new(code.clone(), *addr);
+        party.listed = json["listed"].as_bool().unwrap_or(false);
+        self.parties.insert(code.clone(), party);
+        if let Some(player) = self.players.get_mut(addr) {
+            player.party = Some(code.clone());
+        }
+
+        println!("Party {code} created by {:?}", addr);
+        self.send_party(&code).await;
+    }
+
+    // Hand the code of the party to a player found by name, they join with it
+    async fn handle_party_invite(&mut self, addr: &SocketAddr, json: &Value) {
+        let Some(name) = json["name"].as_str().map(str::trim) else {
+            println!("Invalid party invite received from {:?}", addr);
+            return;
+        };
+
+        let Some(inviter) = self.players.get(addr) else {
+            return;
+        };
+        let Some(code) = inviter.party.clone() else {
+            return;
+        };
+        let from = inviter.name.clone();
+
+        let invited = self
+            .players
+            .values()
+            .find(|player| {
+                player.addr != *addr
+                    && player.session.is_some()
+                    && player.party.as_ref() != Some(&code)
+                    && player.name.eq_ignore_ascii_case(name)
+            })
+            .map(|player| player.addr);
+        let Some(invited) = invited else {
+            println!("Nobody named {name} to invite to party {code}");
+            return;
+        };
+
+        let message = json!({ "action": "party_invite", "from": from, "code": code });
+        self.send_to(&invited, &message).await;
+    }
+
+    async fn handle_party_join(&mut self, addr: &SocketAddr, json: &Value) {
+        let Some(code) = json["code"].as_str().map(normalize_code) else {
+            println!("Invalid party join received from {:?}", addr);
+            return;
+        };
+
+        let Some(player) = self.players.get(addr) else {
+            return;
+        };
+        if player.party.as_ref() == Some(&code) {
+            return;
+        }
+        match self.parties.get(&code) {
+            Some(party) if !party.is_full() => {}
+            _ => {
+                println!("{:?} can't join party {code}", addr);
+                return;
+            }
+        }
+
+        self.leave_party(addr).await;
+
+        if let Some(party) = self.parties.get_mut(&code) {
+            party.add(*addr);
+        }
+        if let Some(player) = self.players.get_mut(addr) {
+            player.party = Some(code.clone());
+        }
+
+        self.send_party(&code).await;
+    }
+
+    async fn handle_party_leave(&mut self, addr: &SocketAddr) {
+        if self.leave_party(addr).await {
+            self.send_to(addr, &json!({ "action": "party_left" })).await;
+        }
+    }
+
+    // Take the player out of their party and tell the others who is left,
+    // returns whether they were in one
+    async fn leave_party(&mut self, addr: &SocketAddr) -> bool {
+        let Some(code) = self
+            .players
+            .get_mut(addr)
+            .and_then(|player| player.party.take())
+        else {
+            return false;
+        };
+
+        let Some(party) = self.parties.get_mut(&code) else {
+            return true;
+        };
+        party.remove(addr);
+
+        if party.is_empty() {
+            self.parties.remove(&code);
+            println!("Party {code} disbanded");
+        } else {
+            self.send_party(&code).await;
+        }
+
+        true
+    }
+
+    // Who is in the party, leader first, to each of its members
+    async fn send_party(&mut self, code: &str) {
+        let Some(party) = self.parties.get(code) else {
+            return;
+        };
+
+        let names: Vec<&str> = party
+            .members
+            .iter()
+            .filter_map(|member| self.players.get(member))
+            .map(|player| player.name.as_str())
+            .collect();
+        let mut message = json!({
+            "action": "party",
+            "code": code,
+            "members": names,
+        });
+        if party.listed {
+            message["listed"] = json!(true);
+        }
+
+        let (leader, members) = (party.leader(), party.members.clone());
+        for member in members {
+            let mut message = message.clone();
+            message["leading"] = json!(member == leader);
+            self.send_to(&member, &message).await;
+        }
+    }
+
+    // A page of the room browser: listed parties with room left, then the
+    // matches being played
+    async fn handle_list_rooms(&mut self, addr: &SocketAddr, json: &Value) {
+        let filter = match RoomFilter::from_json(json) {
+            Ok(filter) => filter,
+            Err(value) => {
+                println!("Invalid room filter {value} from {:?}", addr);
+                return;
+            }
+        };
+        let page_index = json["page"].as_u64().unwrap_or(0) as usize;
+        let per_page = json["per_page"]
+            .as_u64()
+            .map_or(ROOMS_PER_PAGE, |per_page| per_page as usize);
+
+        let name = |addr: &SocketAddr| self.players.get(addr).map(|player| player.name.as_str());
+        let names = |team: &[SocketAddr]| team.iter().filter_map(name).collect::<Vec<&str>>();
+
+        let mut parties: Vec<&Party> = self
+            .parties
+            .values()
+            .filter(|party| party.listed && !party.is_full())
+            .collect();
+        parties.sort_by(|a, b| a.code.cmp(&b.code));
+
+        let mut open = Vec::new();
+        for party in parties {
+            let members = names(&party.members);
+            let modes: Vec<GameMode> = GameMode::ALL
+                .into_iter()
+                .filter(|mode| party.lineup(*mode).is_some())
+                .collect();
+            if !filter.wants(ListingKind::Open, &modes, &members) {
+                continue;
+            }
+
+            open.push(json!({
+                "kind": ListingKind::Open.as_str(),
+                "code": party.code,
+                "players": members,
+                "slots": MAX_PARTY_SIZE - party.members.len(),
+                "modes": modes.iter().map(GameMode::as_str).collect::<Vec<_>>(),
+            }));
+        }
+
+        let mut listings: Vec<(&Uuid, &RoomListing)> = self.listings.iter().collect();
+        listings.sort_by_key(|(room_id, _)| **room_id);
+
+        let mut live = Vec::new();
+        for (room_id, listing) in listings {
+            let teams = listing.teams.each_ref().map(|team| names(team));
+            let everyone = teams.concat();
+            if !filter.wants(ListingKind::Live, &[listing.mode], &everyone) {
+                continue;
+            }
+
+            let mut entry = json!({
+                "kind": ListingKind::Live.as_str(),
+                "room_id": room_id.to_string(),
+                "mode": listing.mode.as_str(),
+                "left": teams[0],
+                "right": teams[1],
+                "score": {
+                    "left": listing.score[Side::Left.index()],
+                    "right": listing.score[Side::Right.index()],
+                },
+                "spectators": listing.spectators.len(),
+            });
+            if let Some(difficulty) = listing.ai {
+                entry["ai"] = json!(difficulty.as_str());
+            }
+            live.push(entry);
+        }
+
+        open.extend(live);
+        let total = open.len();
+        let (rooms, pages) = page(open, page_index, per_page);
+
+        let message = json!({
+            "action": "rooms",
+            "page": page_index,
+            "pages": pages,
+            "total": total,
+            "rooms": rooms,
+        });
+        self.send_to(addr, &message).await;
+    }
+
+    // Watch a match from the lobby, until it ends or the player leaves it
+    fn handle_spectate(&mut self, addr: &SocketAddr, json: &Value) {
+        let Some(room_id) = json["room_id"]
+            .as_str()
+            .and_then(|room_id| Uuid::parse_str(room_id).ok())
+        else {
+            println!("Invalid spectate request from {:?}", addr);
+            return;
+        };
+
+        let Some(player) = self.players.get_mut(addr) else {
+            return;
+        };
+        if player.status != PlayerStatus::Available {
+            println!("{:?} can't watch a match while busy", addr);
+            return;
+        }
+
+        let (Some(room), Some(listing)) =
+            (self.rooms.get(&room_id), self.listings.get_mut(&room_id))
+        else {
+            println!("No room {room_id} to watch for {:?}", addr);
+            return;
+        };
+        if room.try_send(RoomCommand::Spectate(*addr)).is_err() {
+            return;
+        }
+
+        listing.spectators.insert(*addr);
+        player.status = PlayerStatus::Spectating;
+        player.room_id = Some(room_id);
+    }
+
+    // The player has this snapshot, the next ones can be deltas against it
+    fn handle_ack(&mut self, addr: &SocketAddr, json: &Value) {
+        let (Some(player), Some(tick)) = (self.players.get_mut(addr), json["tick"].as_u64()) else {
+            return;
+        };
+
+        player.encoder.acknowledge(tick);
+    }
+
+    // Back to the lobby, leaving a match hands the win to the other player
+    fn handle_leave(&mut self, addr: &SocketAddr) {
+        let Some(player) = self.players.get_mut(addr) else {
+            return;
+        };
+
+        match player.status {
+            PlayerStatus::Available => return,
+            PlayerStatus::Queued => {
+                let _ = self.match_maker.try_send(MatchMakerCommand::Leave(*addr));
+            }
+            PlayerStatus::InMatch => {
+                if let Some(room) = player.room_id.and_then(|room_id| self.rooms.get(&room_id)) {
+                    let _ = room.try_send(RoomCommand::Forfeit(*addr));
+                }
+            }
+            PlayerStatus::Spectating => {
+                if let Some(room_id) = player.room_id {
+                    stop_spectating(&self.rooms, &mut self.listings, room_id, addr);
+                }
+            }
+        }
+
+        player.status = PlayerStatus::Available;
+        player.room_id = None;
+
+        println!("Player {:?} left", addr);
+    }
+
+    // Free to join again, the match maker didn't keep them. A tournament match
+    // is called again on the next check.
+    fn handle_join_refused(&mut self, players: &[SocketAddr]) {
+        for addr in players {
+            if let Some(player) = self.players.get_mut(addr) {
+                if player.status == PlayerStatus::Queued {
+                    player.status = PlayerStatus::Available;
+                }
+            }
+        }
+
+        if let Some(tournament) = self.tournament.as_mut() {
+            tournament.room_refused(players);
+        }
+    }
+
+    fn handle_room_created(
+        &mut self,
+        room_id: Uuid,
+        players: Vec<(SocketAddr, Seat)>,
+        mode: GameMode,
+        ai: Option<Difficulty>,
+        room: mpsc::Sender<RoomCommand>,
+    ) {
+        let addrs: Vec<SocketAddr> = players.iter().map(|(addr, _)| *addr).collect();
+        if let Some(tournament) = self.tournament.as_mut() {
+            tournament.room_created(room_id, &addrs);
+        }
+
+        let teams = [Side::Left, Side::Right].map(|side| {
+            players
+                .iter()
+                .filter(|(_, seat)| seat.side == side)
+                .map(|(addr, _)| *addr)
+                .collect()
+        });
+        let listing = RoomListing {
+            mode,
+            ai,
+            teams,
+            score: [0, 0],
+            spectators: HashSet::new(),
+        };
+        self.listings.insert(room_id, listing);
+
+        for (addr, _) in players {
+            match self.players.get_mut(&addr) {
+                Some(player) if player.status == PlayerStatus::Queued => {
+                    player.status = PlayerStatus::InMatch;
+                    player.room_id = Some(room_id);
+                }
+
+                // Left or gone while the room was being made, the other team wins
+                _ => {
+                    let _ = room.try_send(RoomCommand::Forfeit(addr));
+                }
+            }
+        }
+
+        self.rooms.insert(room_id, room);
+    }
+
+    // Everyone left in the room goes back to the lobby, the result moves the
+    // ratings and the tournament bracket
+    async fn handle_room_closed(
+        &mut self,
+        room_id: Uuid,
+        players: Vec<SocketAddr>,
+        result: Option<MatchResult>,
+    ) {
+        self.rooms.remove(&room_id);
+        let spectators = self
+            .listings
+            .remove(&room_id)
+            .map(|listing| listing.spectators)
+            .unwrap_or_default();
+
+        for addr in players.into_iter().chain(spectators) {
+            if let Some(player) = self.players.get_mut(&addr) {
+                player.status = PlayerStatus::Available;
+                player.room_id = None;
+            }
+        }
+
+        let Some(result) = result else {
+            return;
+        };
+        self.update_ratings(&result);
+
+        let reported = self
+            .tournament
+            .as_mut()
+            .is_some_and(|tournament| tournament.report(&room_id, &result.winners));
+        if reported {
+            self.send_bracket().await;
+        }
+
+        // The players are free for their next bracket match
+        self.run_tournament().await;
+    }
+
+    // Matches against the AI don't count
+    fn update_ratings(&mut self, result: &MatchResult) {
+        let average = |team: &[SocketAddr]| {
+            let ratings: Vec<f32> = team
+                .iter()
+                .filter_map(|addr| self.players.get(addr))
+                .map(|player| player.rating)
+                .collect();
+            (!ratings.is_empty()).then(|| ratings.iter().sum::<f32>() / ratings.len() as f32)
+        };
+        let (Some(winner), Some(loser)) = (average(&result.winners), average(&result.losers))
+        else {
+            return;
+        };
+
+        let (new_winner, new_loser) = rating::update(winner, loser);
+        let changes = [
+            (&result.winners, new_winner - winner),
+            (&result.losers, new_loser - loser),
+        ];
+        for (team, change) in changes {
+            for addr in team {
+                if let Some(player) = self.players.get_mut(addr) {
+                    player.rating += change;
+                }
+            }
+        }
+    }
+
+    // Open a new tournament for registrations, unless one is still going
+    async fn handle_tournament_create(&mut self, addr: &SocketAddr, json: &Value) {
+        let format = match json["format"].as_str() {
+            None => BracketFormat::default(),
+            Some(format) => match BracketFormat::parse(format) {
+                Some(format) => format,
+                None => {
+                    println!("Unknown bracket format {format} requested by {:?}", addr);
+                    return;
+                }
+            },
+        };
+
+        if !self.players.contains_key(addr) {
+            return;
+        }
+        if self
+            .tournament
+            .as_ref()
+            .is_some_and(|tournament| !tournament.is_finished())
+        {
+            println!(
+                "A tournament is already open, {:?} can't host another",
+                addr
+            );
+            return;
+        }
+
+        println!("{} tournament opened by {:?}", format, addr);
+        self.tournament = Some(Tournament::new(*addr, format));
+        self.send_bracket().await;
+    }
+
+    async fn handle_tournament_register(&mut self, addr: &SocketAddr) {
+        let (Some(player), Some(tournament)) = (self.players.get(addr), self.tournament.as_mut())
+        else {
+            return;
+        };
+
+        let entrant = Entrant {
+            addr: *addr,
+            name: player.name.clone(),
+            rating: player.rating,
+        };
+        if tournament.register(entrant) {
+            self.send_bracket().await;
+        }
+    }
+
+    async fn handle_tournament_unregister(&mut self, addr: &SocketAddr) {
+        let unregistered = self
+            .tournament
+            .as_mut()
+            .is_some_and(|tournament| tournament.unregister(addr));
+        if unregistered {
+            self.send_bracket().await;
+        }
+    }
+
+    // Only the host starts it, or anyone registered once the host is gone
+    async fn handle_tournament_start(&mut self, addr: &SocketAddr) {
+        let Some(tournament) = self.tournament.as_mut() else {
+            return;
+        };
+
+        let host_gone = !self.players.contains_key(&tournament.host);
+        let registered = tournament
+            .entrants
+            .iter()
+            .any(|entrant| entrant.addr == *addr);
+        if tournament.host != *addr && !(host_gone && registered) {
+            println!("Only the host can start the tournament, not {:?}", addr);
+            return;
+        }
+
+        if !tournament.start() {
+            return;
+        }
+        println!(
+            "Tournament started with {} players",
+            tournament.entrants.len()
+        );
+
+        self.send_bracket().await;
+        self.run_tournament().await;
+    }
+
+    // Give a room to every bracket match whose players are both free. Players
+    // busy or gone for too long lose their match, see `no_show_winner` for
+    // when both are.
+    async fn run_tournament(&mut self) {
+        let Some(tournament) = self.tournament.as_mut() else {
+            return;
+        };
+
+        let mut changed = false;
+        for index in tournament.waiting() {
+            let Some(players) = tournament.players(index) else {
+                continue;
+            };
+
+            let available = players.map(|addr| {
+                self.players
+                    .get(&addr)
+                    .is_some_and(|player| player.status == PlayerStatus::Available)
+            });
+
+            if available == [true, true] {
+                let versus = MatchMakerCommand::Versus {
+                    teams: players.map(|addr| vec![addr]),
+                    mode: GameMode::Singles,
+                    first_to: Some(self.config.tournament_first_to),
+                };
+                if self.match_maker.try_send(versus).is_err() {
+                    continue;
+                }
+
+                for addr in players {
+                    if let Some(player) = self.players.get_mut(&addr) {
+                        player.status = PlayerStatus::Queued;
+                    }
+                }
+                tournament.called.remove(&index);
+                tournament.starting.push(index);
+                changed = true;
+                continue;
+            }
+
+            let called_at = *tournament.called.entry(index).or_insert_with(Instant::now);
+            let gone = players.map(|addr| !self.players.contains_key(&addr));
+            if !gone.contains(&true) && called_at.elapsed() < self.config.no_show_after {
+                continue;
+            }
+
+            let winner = no_show_winner(available, gone);
+            println!(
+                "Tournament match {} awarded to {:?}, the other player didn't show up",
+                index, players[winner]
+            );
+            changed |= tournament.walkover(index, winner);
+        }
+
+        if changed {
+            self.send_bracket().await;
+        }
+    }
+
+    // Everyone connected sees the bracket change live
+    async fn send_bracket(&mut self) {
+        let Some(tournament) = &self.tournament else {
+            return;
+        };
+        let message = tournament.to_json();
+
+        let viewers: Vec<SocketAddr> = self
+            .players
+            .values()
+            .filter(|player| player.session.is_some())
+            .map(|player| player.addr)
+            .collect();
+        for addr in viewers {
+            self.send_to(&addr, &message).await;
+        }
+    }
+
+    // Drop messages above what a human client sends, returns whether to process this one
+    async fn check_message_rate(&mut self, addr: &SocketAddr) -> bool {
+        let Some(player) = self.players.get_mut(addr) else {
+            return true;
+        };
+
+        match player.message_rate.check() {
+            Throttle::Allowed => true,
+            Throttle::Refused { first: true } => {
+                self.flag(addr, Violation::MessageRate).await;
+                false
+            }
+            Throttle::Refused { first: false } => false,
+        }
+    }
+
+    // Record a violation and kick the player once they reach MAX_STRIKES
+    async fn flag(&mut self, addr: &SocketAddr, violation: Violation) {
+        let Some(player) = self.players.get_mut(addr) else {
+            return;
+        };
+
+        player.strikes += 1;
+        let (player_id, strikes) = (player.id, player.strikes);
+
+        self.audit_log.record(
+            addr,
+            Some(player_id),
+            &format!("strike {}/{}: {}", strikes, MAX_STRIKES, violation),
+        );
+
+        if strikes >= MAX_STRIKES {
+            self.kick(addr, "too many invalid messages").await;
+        }
+    }
+
+    // Forget the player, ending their match as a forfeit and leaving their party
+    // and the tournament they registered to
+    async fn kick(&mut self, addr: &SocketAddr, reason: &str) {
+        self.leave_party(addr).await;
+
+        let Some(mut player) = self.players.remove(addr) else {
+            return;
+        };
+
+        let _ = self.match_maker.try_send(MatchMakerCommand::Leave(*addr));
+        self.handle_tournament_unregister(addr).await;
+
+        // The room tells the other player and reports back once it is closed
+        match (&player.status, player.room_id) {
+            (PlayerStatus::Spectating, Some(room_id)) => {
+                stop_spectating(&self.rooms, &mut self.listings, room_id, addr);
+            }
+            (_, Some(room_id)) => {
+                if let Some(room) = self.rooms.get(&room_id) {
+                    let _ = room.try_send(RoomCommand::Forfeit(*addr));
+                }
+            }
+            (_, None) => {}
+        }
+
+        self.audit_log
+            .record(addr, Some(player.id), &format!("kicked: {}", reason));
+
+        let packet = player.seal(&json!({ "action": "kicked", "reason": reason }));
+        self.send_packet(addr, &packet).await;
+    }
+
+    // Answer with our receive and send times so the client can work out
+    // the round trip time and the offset between the two clocks
+    async fn handle_ping(&mut self, addr: &SocketAddr, json: &Value, received_at: f64) {
+        let Some(client_time) = json["client_time"].as_f64() else {
+            return;
+        };
+
+        let message = json!({
+            "action": "pong",
+            "client_time": client_time,
+            "server_receive": received_at,
+            "server_send": server_time_ms(),
+        });
+
+        self.send_to(addr, &message).await;
+    }
+}
+
+// Free of the router so it works while one of its players is borrowed
+fn stop_spectating(
+    rooms: &HashMap<Uuid, mpsc::Sender<RoomCommand>>,
+    listings: &mut HashMap<Uuid, RoomListing>,
+    room_id: Uuid,
+    addr: &SocketAddr,
+) {
+    if let Some(room) = rooms.get(&room_id) {
+        let _ = room.try_send(RoomCommand::StopSpectating(*addr));
+    }
+    if let Some(listing) = listings.get_mut(&room_id) {
+        listing.spectators.remove(addr);
+    }
+}
@@ -1,159 +1,186 @@
-use std::{
-    collections::HashMap,
-    net::SocketAddr,
-    sync::{Arc, Mutex},
+use pong_multi_shared::{
+    game::{
+        formation::{GameMode, DEFAULT_DOUBLES_DEPTHS},
+        map::Map,
+        power_up::SpawnTable,
+    },
+    protocol::{WireFormat, MIN_PROTOCOL_VERSION},
 };
+use std::{io, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 use tokio::{
-    net::UdpSocket,
-    sync::{mpsc, Semaphore},
+    sync::{mpsc, watch},
+    task::JoinHandle,
 };
 
-use serde_json::Value;
-use uuid::Uuid;
+use crate::game::pause::PauseRules;
 
-use super::{match_maker::MatchMaker, player::Player, room::Room};
+use super::{
+    audit::{AuditLog, AUDIT_LOG_PATH},
+    chat::WordFilter,
+    clock::server_time_ms,
+    match_maker::MatchMaker,
+    message::ServerEvent,
+    router::Router,
+    transport::{fragmented::FragmentedTransport, udp::UdpTransport, Transport},
+};
 
+// Settings picked when starting the server
 #[derive(Debug, Clone)]
-pub struct Server {
-    pub socket: Arc<UdpSocket>,
-    pub message_queue: mpsc::Sender<(usize, SocketAddr, Vec<u8>)>,
-    pub players: Arc<Mutex<HashMap<SocketAddr, Arc<Mutex<Player>>>>>,
-    pub match_maker: Arc<MatchMaker>,
-    pub rooms: Arc<Mutex<HashMap<Uuid, Arc<Mutex<Room>>>>>,
-    pub player_room_map: Arc<Mutex<HashMap<SocketAddr, Uuid>>>,
-}
+pub struct ServerConfig {
+    // Encoding of everything we send, JSON to inspect the traffic
+    pub wire_format: WireFormat,
 
-impl Server {
-    pub async fn new(addr: &str) -> Arc<Self> {
-        let socket = Arc::new(UdpSocket::bind(addr).await.expect("Failed to bind socket"));
-
-        // Create a queue_message to receive messae from client send in UDP
-        let (tx, rx) = mpsc::channel::<(usize, SocketAddr, Vec<u8>)>(1000);
-
-        // Init the players list current online
-        let players: Arc<Mutex<HashMap<SocketAddr, Arc<Mutex<Player>>>>> =
-            Arc::new(Mutex::new(HashMap::new()));
-
-        // Init the rooms list
-        let rooms: Arc<Mutex<HashMap<Uuid, Arc<Mutex<Room>>>>> =
-            Arc::new(Mutex::new(HashMap::new()));
-
-        // Init player_room_map
-        let player_room_map: Arc<Mutex<HashMap<SocketAddr, Uuid>>> =
-            Arc::new(Mutex::new(HashMap::new()));
-
-        // Init match_maker to create match for player
-        let match_maker = Arc::new(MatchMaker::new(
-            players.clone(),
-            tx.clone(),
-            rooms.clone(),
-            player_room_map.clone(),
-        ));
-
-        // Create the server
-        let server = Arc::new(Server {
-            socket: socket.clone(),
-            message_queue: tx.clone(),
-            players,
-            match_maker: match_maker.clone(),
-            rooms: rooms.clone(),
-            player_room_map: player_room_map.clone(),
-        });
-
-        // Spawn the receiver worker
-        let server_clone = server.clone();
-        tokio::spawn(async move {
-            server_clone.receive_loop().await;
-        });
-
-        // Spawn worker pool for processing task
-        let server_clone = server.clone();
-        tokio::spawn(async move {
-            server_clone.task_worker(rx).await;
-        });
-
-        server
-    }
+    // Clients that can't speak at least this protocol version are asked to update
+    pub min_protocol: u8,
 
-    // Receive message from client and push to message_queue to process later
-    async fn receive_loop(self: Arc<Self>) {
-        let mut buf = [0; 1024];
+    // Distance from the center line of the back and front paddle of a doubles team
+    pub doubles_depths: [f32; 2],
 
-        loop {
-            match self.socket.recv_from(&mut buf).await {
-                Ok((len, addr)) => {
-                    let message_buf = buf[..len].to_vec();
+    // Power-ups that can show up in each mode and how often
+    pub singles_power_ups: SpawnTable,
+    pub doubles_power_ups: SpawnTable,
 
-                    if let Err(_) = self.message_queue.send((len, addr, message_buf)).await {
-                        eprint!("Task queue is full! Dropping packet from {:?}", addr);
-                    }
-                }
-                Err(e) => {
-                    eprint!("Error receiving packet: {:?}", e);
-                }
-            }
-        }
-    }
+    // Players vote on one of these before each match, a single map is played
+    // without a vote
+    pub maps: Vec<Map>,
+
+    // How long players who asked for it wait in the queue before playing the AI
+    pub ai_fallback_after: Duration,
+
+    // Masked out of chat lines and player names
+    pub chat_filter: WordFilter,
+
+    // Points that win a tournament match
+    pub tournament_first_to: u32,
+
+    // How long a tournament match waits for a busy or missing player before
+    // the other one goes through
+    pub no_show_after: Duration,
 
-    // Read message from the message_queue and pass to the process method
-    async fn task_worker(self: Arc<Self>, mut rx: mpsc::Receiver<(usize, SocketAddr, Vec<u8>)>) {
-        let semaphore = Arc::new(Semaphore::new(1000));
+    // How many pauses players get in a match and how long they last
+    pub pauses: PauseRules,
 
-        while let Some((len, addr, buf)) = rx.recv().await {
-            let server_clone = self.clone();
-            let permit = semaphore.clone().acquire_owned().await.unwrap();
+    // File the audit log is appended to, None to only print it
+    pub audit_log: Option<PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            wire_format: WireFormat::default(),
+            min_protocol: MIN_PROTOCOL_VERSION,
+            doubles_depths: DEFAULT_DOUBLES_DEPTHS,
+            singles_power_ups: SpawnTable::singles(),
+            doubles_power_ups: SpawnTable::doubles(),
+            maps: vec![Map::classic()],
+            ai_fallback_after: Duration::from_secs(30),
+            chat_filter: WordFilter::default(),
+            tournament_first_to: 5,
+            no_show_after: Duration::from_secs(120),
+            pauses: PauseRules::default(),
+            audit_log: Some(PathBuf::from(AUDIT_LOG_PATH)),
+        }
+    }
+}
 
-            tokio::spawn(async move {
-                server_clone.process(len, addr, buf).await;
-                drop(permit);
-            });
+impl ServerConfig {
+    pub fn power_ups(&self, mode: GameMode) -> SpawnTable {
+        match mode {
+            GameMode::Singles => self.singles_power_ups,
+            GameMode::Doubles => self.doubles_power_ups,
         }
     }
+}
 
-    // Process user request
-    async fn process(self: Arc<Self>, len: usize, addr: SocketAddr, buf: Vec<u8>) {
-        println!("Bytes received: {:?} from {:?}", len, addr);
+// Handle on the running server. The state lives in the router, room and
+// match maker tasks, which only talk to each other through channels.
+#[derive(Debug)]
+pub struct Server {
+    // Address the transport is bound to, with the actual port when binding to port 0
+    pub addr: SocketAddr,
+    pub events: mpsc::Sender<ServerEvent>,
+    shutdown: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
 
-        let received_str = String::from_utf8_lossy(&buf);
+impl Server {
+    pub async fn new(addr: &str) -> io::Result<Self> {
+        Self::with_transport(UdpTransport::bind(addr).await?)
+    }
 
-        println!("Message received: {:?}", received_str);
+    pub fn with_transport<T: Transport>(transport: T) -> io::Result<Self> {
+        Self::with_config(transport, ServerConfig::default())
+    }
 
-        match serde_json::from_str::<Value>(&received_str) {
-            Ok(json) => {
-                if let Some(action) = json["action"].as_str() {
-                    match action {
-                        "enter" => self.handle_enter(&addr).await,
+    pub fn with_config<T: Transport>(transport: T, config: ServerConfig) -> io::Result<Self> {
+        let addr = transport.local_addr()?;
 
-                        "join" => self.handle_join(&addr).await,
+        // Large messages are split into datagrams that fit the MTU
+        let transport = Arc::new(FragmentedTransport::new(transport));
 
-                        "leave" => self.handle_leave().await,
+        // Every event for the router: packets, outgoing messages and room updates
+        let (tx, rx) = mpsc::channel::<ServerEvent>(4096);
+        let (shutdown, shutdown_rx) = watch::channel(false);
 
-                        "move" => self.handle_move().await,
+        // The match maker creates the rooms and tells the router about them
+        let match_maker = MatchMaker::spawn(tx.clone(), config.clone());
 
-                        _ => println!("Unknow action : {action}"),
-                    }
-                }
-            }
+        // Rooms and the match maker stop by themselves once the router drops their channels
+        let router = Router::new(
+            transport.clone(),
+            match_maker,
+            AuditLog::new(config.audit_log.clone()),
+            config,
+        );
+        let router = tokio::spawn(router.run(rx, shutdown_rx.clone()));
 
-            Err(_) => println!("Invalid JSON received from {:?}", addr),
-        }
+        let receiver = tokio::spawn(receive_loop(transport, tx.clone(), shutdown_rx));
+
+        Ok(Server {
+            addr,
+            events: tx,
+            shutdown,
+            tasks: vec![receiver, router],
+        })
     }
 
-    async fn handle_enter(&self, addr: &SocketAddr) {
-        let mut players = self.players.lock().unwrap();
-        if !players.contains_key(addr) {
-            let player = Player::new(*addr);
-            players.insert(*addr, player.clone());
+    // Stop receiving, close every room and wait for the transport to be released
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
 
-            println!("New player connected: {:?}", addr);
+        for task in self.tasks {
+            let _ = task.await;
         }
     }
+}
 
-    async fn handle_join(&self, addr: &SocketAddr) {
-        self.match_maker.add_to_queue(addr);
+// Receive messages from clients and hand them to the router
+async fn receive_loop<T: Transport>(
+    transport: Arc<T>,
+    events: mpsc::Sender<ServerEvent>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        let received = tokio::select! {
+            received = transport.recv_from() => received,
+            _ = shutdown.changed() => return,
+        };
+
+        match received {
+            Ok((buf, addr)) => {
+                let packet = ServerEvent::Packet {
+                    addr,
+                    buf,
+                    received_at: server_time_ms(),
+                };
+
+                if events.try_send(packet).is_err() {
+                    eprintln!("Event queue is full! Dropping packet from {:?}", addr);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error receiving packet: {:?}", e);
+            }
+        }
     }
-
-    async fn handle_move(&self) {}
-
-    async fn handle_leave(&self) {}
 }
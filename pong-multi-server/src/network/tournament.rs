@@ -0,0 +1,229 @@
+use std::{collections::HashMap, net::SocketAddr};
+
+use serde_json::{json, Value};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::game::bracket::{Bracket, BracketFormat, Slot};
+
+// Big enough for an office, small enough for the bracket to fit in a message
+pub const MAX_ENTRANTS: usize = 32;
+
+#[derive(Debug, Clone)]
+pub struct Entrant {
+    pub addr: SocketAddr,
+    pub name: String,
+    pub rating: f32,
+}
+
+// A knockout tournament hosted by the server, one at a time. Players register
+// until the host starts it, then each bracket match gets a room as soon as
+// both of its players are free.
+#[derive(Debug)]
+pub struct Tournament {
+    pub host: SocketAddr,
+    pub format: BracketFormat,
+
+    // In the order they registered, then by seed once started
+    pub entrants: Vec<Entrant>,
+    pub bracket: Option<Bracket>,
+
+    // Bracket matches handed to the match maker, waiting for their room
+    pub starting: Vec<usize>,
+
+    // Bracket matches being played, by room
+    pub rooms: HashMap<Uuid, usize>,
+
+    // When the players of a ready match were first found busy or gone
+    pub called: HashMap<usize, Instant>,
+}
+
+impl Tournament {
+    pub fn new(host: SocketAddr, format: BracketFormat) -> Self {
+        Self {
+            host,
+            format,
+            entrants: Vec::new(),
+            bracket: None,
+            starting: Vec::new(),
+            rooms: HashMap::new(),
+            called: HashMap::new(),
+        }
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.bracket.is_some()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.bracket.as_ref().is_some_and(Bracket::is_finished)
+    }
+
+    // Only before the start, and once per player
+    pub fn register(&mut self, entrant: Entrant) -> bool {
+        if self.is_started()
+            || self.entrants.len() >= MAX_ENTRANTS
+            || self.entrants.iter().any(|e| e.addr == entrant.addr)
+        {
+            return false;
+        }
+
+        self.entrants.push(entrant);
+        true
+    }
+
+    pub fn unregister(&mut self, addr: &SocketAddr) -> bool {
+        if self.is_started() {
+            return false;
+        }
+
+        let count = self.entrants.len();
+        self.entrants.retain(|entrant| entrant.addr != *addr);
+        self.entrants.len() != count
+    }
+
+    // Seed by rating, the best first, and draw the bracket
+    pub fn start(&mut self) -> bool {
+        if self.is_started() || self.entrants.len() < 2 {
+            return false;
+        }
+
+        self.entrants.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        self.bracket = Some(Bracket::new(self.format, self.entrants.len()));
+
+        true
+    }
+
+    pub fn players(&self, index: usize) -> Option<[SocketAddr; 2]> {
+        match self.bracket.as_ref()?.players(index) {
+            [Slot::Player(a), Slot::Player(b)] => {
+                Some([self.entrants[a].addr, self.entrants[b].addr])
+            }
+            _ => None,
+        }
+    }
+
+    // Ready matches without a room yet
+    pub fn waiting(&self) -> Vec<usize> {
+        let Some(bracket) = &self.bracket else {
+            return Vec::new();
+        };
+
+        bracket
+            .ready()
+            .into_iter()
+            .filter(|index| {
+                !self.starting.contains(index) && !self.rooms.values().any(|room| room == index)
+            })
+            .collect()
+    }
+
+    // The match maker made the room for one of our matches
+    pub fn room_created(&mut self, room_id: Uuid, players: &[SocketAddr]) {
+        if let Some(index) = self.take_starting(players) {
+            self.rooms.insert(room_id, index);
+        }
+    }
+
+    // The match maker turned the match down, it waits to be called again
+    pub fn room_refused(&mut self, players: &[SocketAddr]) {
+        self.take_starting(players);
+    }
+
+    fn take_starting(&mut self, players: &[SocketAddr]) -> Option<usize> {
+        let position = self.starting.iter().position(|index| {
+            self.players(*index)
+                .is_some_and(|pair| pair.iter().all(|addr| players.contains(addr)))
+        })?;
+
+        Some(self.starting.remove(position))
+    }
+
+    // Advance the winner of the match played in the room, returns whether it
+    // was one of ours
+    pub fn report(&mut self, room_id: &Uuid, winners: &[SocketAddr]) -> bool {
+        let Some(index) = self.rooms.remove(room_id) else {
+            return false;
+        };
+        let Some(players) = self.players(index) else {
+            return false;
+        };
+
+        let winner = usize::from(!winners.contains(&players[0]));
+        self.walkover(index, winner)
+    }
+
+    // Decide a match without playing it, `winner` is 0 or 1
+    pub fn walkover(&mut self, index: usize, winner: usize) -> bool {
+        self.called.remove(&index);
+
+        self.bracket
+            .as_mut()
+            .is_some_and(|bracket| bracket.report(index, winner))
+    }
+
+    pub fn champion(&self) -> Option<&Entrant> {
+        let seed = self.bracket.as_ref()?.champion()?;
+        self.entrants.get(seed)
+    }
+
+    // Everything a client needs to draw the bracket. Matches decided by a bye
+    // are left out, nobody played them.
+    pub fn to_json(&self) -> Value {
+        let state = match &self.bracket {
+            None => "registering",
+            Some(bracket) if bracket.is_finished() => "finished",
+            Some(_) => "running",
+        };
+
+        let mut message = json!({
+            "action": "bracket",
+            "format": self.format.as_str(),
+            "state": state,
+            "entrants": self
+                .entrants
+                .iter()
+                .map(|entrant| json!({ "name": entrant.name, "rating": entrant.rating.round() }))
+                .collect::<Vec<_>>(),
+        });
+
+        if let Some(bracket) = &self.bracket {
+            let matches: Vec<Value> = (0..bracket.matches.len())
+                .filter(|index| !bracket.players(*index).contains(&Slot::Bye))
+                .map(|index| {
+                    let played = &bracket.matches[index];
+                    let players = bracket.players(index).map(|slot| match slot {
+                        Slot::Player(seed) => json!(self.entrants[seed].name),
+                        _ => Value::Null,
+                    });
+                    let winner = played
+                        .outcome
+                        .map(|outcome| usize::from(outcome.winner != bracket.players(index)[0]));
+
+                    json!({
+                        "stage": played.stage.as_str(),
+                        "round": played.round,
+                        "players": players,
+                        "winner": winner,
+                        "live": self.rooms.values().any(|room| *room == index),
+                    })
+                })
+                .collect();
+            message["matches"] = json!(matches);
+        }
+
+        if let Some(champion) = self.champion() {
+            message["champion"] = json!(champion.name);
+        }
+
+        message
+    }
+}
+
+// Who goes through when a match was called and one of its players didn't
+// show up in time: the one still free, or still connected. When both are
+// busy or both are gone, the first listed goes through.
+pub fn no_show_winner(available: [bool; 2], gone: [bool; 2]) -> usize {
+    let second = (available[1] && !available[0]) || (gone[0] && !gone[1]);
+    usize::from(second)
+}
@@ -0,0 +1,201 @@
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use pong_multi_shared::netsim::{conditioner::Conditioner, NetworkConditions};
+use tokio::{
+    sync::{self, mpsc, Notify},
+    task::JoinHandle,
+};
+
+use super::Transport;
+
+type Packet = (Vec<u8>, SocketAddr);
+
+// Applies network conditions to another transport, on the way out and on the way in.
+// It starts with a perfect network, change it while running with the `NetworkControl`.
+#[derive(Debug)]
+pub struct ConditionedTransport<T: Transport> {
+    inner: Arc<T>,
+    outgoing: Arc<DelayLine>,
+    incoming_line: Arc<DelayLine>,
+    incoming: sync::Mutex<mpsc::Receiver<Packet>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+// Changes the conditions of a running transport, both directions get the same ones
+#[derive(Debug, Clone)]
+pub struct NetworkControl {
+    outgoing: Arc<DelayLine>,
+    incoming: Arc<DelayLine>,
+}
+
+// Packets waiting to be let through in one direction
+#[derive(Debug)]
+struct DelayLine {
+    conditioner: Mutex<Conditioner<Packet>>,
+    notify: Notify,
+}
+
+impl<T: Transport> ConditionedTransport<T> {
+    pub fn new(inner: T, conditions: NetworkConditions) -> Self {
+        let inner = Arc::new(inner);
+        let outgoing = Arc::new(DelayLine::new(conditions));
+        let incoming_line = Arc::new(DelayLine::new(conditions));
+        let (tx, incoming) = mpsc::channel(4096);
+
+        let tasks = vec![
+            tokio::spawn(send_loop(inner.clone(), outgoing.clone())),
+            tokio::spawn(receive_loop(
+                inner.clone(),
+                incoming_line.clone(),
+                tx.clone(),
+            )),
+            tokio::spawn(release_loop(incoming_line.clone(), tx)),
+        ];
+
+        Self {
+            inner,
+            outgoing,
+            incoming_line,
+            incoming: sync::Mutex::new(incoming),
+            tasks,
+        }
+    }
+
+    pub fn control(&self) -> NetworkControl {
+        NetworkControl {
+            outgoing: self.outgoing.clone(),
+            incoming: self.incoming_line.clone(),
+        }
+    }
+}
+
+impl<T: Transport> Drop for ConditionedTransport<T> {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl<T: Transport> Transport for ConditionedTransport<T> {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    async fn send_to(&self, packet: &[u8], addr: SocketAddr) -> io::Result<()> {
+        match self.outgoing.push((packet.to_vec(), addr)) {
+            Some((packet, addr)) => self.inner.send_to(&packet, addr).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn recv_from(&self) -> io::Result<(Vec<u8>, SocketAddr)> {
+        self.incoming
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+impl NetworkControl {
+    pub fn conditions(&self) -> NetworkConditions {
+        self.outgoing.conditioner.lock().unwrap().conditions
+    }
+
+    pub fn set(&self, conditions: NetworkConditions) {
+        for line in [&self.outgoing, &self.incoming] {
+            line.conditioner.lock().unwrap().conditions = conditions;
+        }
+    }
+}
+
+impl DelayLine {
+    fn new(conditions: NetworkConditions) -> Self {
+        Self {
+            conditioner: Mutex::new(Conditioner::new(conditions)),
+            notify: Notify::new(),
+        }
+    }
+
+    // Queue a packet, or hand it back when it can go through right away
+    fn push(&self, packet: Packet) -> Option<Packet> {
+        let mut conditioner = self.conditioner.lock().unwrap();
+        if conditioner.passes_through() {
+            return Some(packet);
+        }
+
+        let size = packet.0.len();
+        conditioner.push(Instant::now(), packet, size);
+        drop(conditioner);
+
+        self.notify.notify_one();
+        None
+    }
+
+    // Wait for the next packets to be due
+    async fn next_ready(&self) -> Vec<Packet> {
+        loop {
+            let next_due = {
+                let mut conditioner = self.conditioner.lock().unwrap();
+                let ready = conditioner.pop_ready(Instant::now());
+                if !ready.is_empty() {
+                    return ready;
+                }
+                conditioner.next_due()
+            };
+
+            // A new packet may be due before the one we know of
+            match next_due {
+                Some(due) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(due.into()) => {}
+                        _ = self.notify.notified() => {}
+                    }
+                }
+                None => self.notify.notified().await,
+            }
+        }
+    }
+}
+
+async fn send_loop<T: Transport>(inner: Arc<T>, outgoing: Arc<DelayLine>) {
+    loop {
+        for (packet, addr) in outgoing.next_ready().await {
+            if let Err(e) = inner.send_to(&packet, addr).await {
+                eprintln!("Error sending packet to {:?}: {:?}", addr, e);
+            }
+        }
+    }
+}
+
+async fn receive_loop<T: Transport>(
+    inner: Arc<T>,
+    incoming: Arc<DelayLine>,
+    tx: mpsc::Sender<Packet>,
+) {
+    loop {
+        match inner.recv_from().await {
+            Ok(packet) => {
+                if let Some(packet) = incoming.push(packet) {
+                    let _ = tx.try_send(packet);
+                }
+            }
+            Err(e) => eprintln!("Error receiving packet: {:?}", e),
+        }
+    }
+}
+
+async fn release_loop(incoming: Arc<DelayLine>, tx: mpsc::Sender<Packet>) {
+    loop {
+        for packet in incoming.next_ready().await {
+            let _ = tx.try_send(packet);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use pong_multi_shared::fragment::{is_fragment, reassembler::Reassembler, Fragmenter};
+
+use super::Transport;
+
+// Clients with a message being reassembled at once, fragments from anyone else wait their turn
+const MAX_REASSEMBLING_PEERS: usize = 1024;
+
+// Bytes held for incomplete messages from all the clients together
+const MAX_REASSEMBLY_BYTES: usize = 16 * 1024 * 1024;
+
+// How often messages from clients that went quiet are thrown away
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+// Splits packets that don't fit in one datagram and puts them back together on
+// the other end, on top of any transport. The server always runs behind one.
+#[derive(Debug)]
+pub struct FragmentedTransport<T: Transport> {
+    inner: T,
+    fragmenter: Mutex<Fragmenter>,
+    reassembly: Mutex<Reassembly>,
+}
+
+#[derive(Debug)]
+struct Reassembly {
+    peers: HashMap<SocketAddr, Reassembler>,
+    last_sweep: Instant,
+}
+
+impl<T: Transport> FragmentedTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            fragmenter: Mutex::new(Fragmenter::default()),
+            reassembly: Mutex::new(Reassembly {
+                peers: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+}
+
+impl<T: Transport> Transport for FragmentedTransport<T> {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    async fn send_to(&self, packet: &[u8], addr: SocketAddr) -> io::Result<()> {
+        let fragments = self.fragmenter.lock().unwrap().split(packet);
+
+        for fragment in fragments {
+            self.inner.send_to(&fragment, addr).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn recv_from(&self) -> io::Result<(Vec<u8>, SocketAddr)> {
+        loop {
+            let (packet, addr) = self.inner.recv_from().await?;
+            if !is_fragment(&packet) {
+                return Ok((packet, addr));
+            }
+
+            let message = self.reassembly.lock().unwrap().receive(addr, packet);
+            if let Some(message) = message {
+                return Ok((message, addr));
+            }
+        }
+    }
+}
+
+impl Reassembly {
+    fn receive(&mut self, addr: SocketAddr, packet: Vec<u8>) -> Option<Vec<u8>> {
+        let now = Instant::now();
+        if now - self.last_sweep >= SWEEP_INTERVAL {
+            self.sweep(now);
+        }
+
+        if !self.peers.contains_key(&addr) && self.peers.len() >= MAX_REASSEMBLING_PEERS {
+            return None;
+        }
+
+        let pending: usize = self.peers.values().map(Reassembler::pending_bytes).sum();
+        if pending + packet.len() > MAX_REASSEMBLY_BYTES {
+            return None;
+        }
+
+        let reassembler = self.peers.entry(addr).or_default();
+        let message = reassembler.receive(now, packet);
+        if reassembler.is_empty() {
+            self.peers.remove(&addr);
+        }
+
+        message
+    }
+
+    fn sweep(&mut self, now: Instant) {
+        self.peers.retain(|_, reassembler| {
+            reassembler.expire(now);
+            !reassembler.is_empty()
+        });
+        self.last_sweep = now;
+    }
+}
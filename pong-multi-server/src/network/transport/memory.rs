@@ -0,0 +1,119 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio::sync::{self, mpsc};
+
+use super::Transport;
+
+// Packets a queue can hold before new ones are dropped, like a full socket buffer
+const QUEUE_SIZE: usize = 1024;
+
+// Packets passed over channels, for tests and simulations that don't need sockets.
+// Clones share the same network, keep one to connect clients after starting the server.
+#[derive(Debug, Clone)]
+pub struct MemoryTransport {
+    network: Arc<MemoryNetwork>,
+}
+
+#[derive(Debug)]
+struct MemoryNetwork {
+    incoming_tx: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+    incoming: sync::Mutex<mpsc::Receiver<(Vec<u8>, SocketAddr)>>,
+    clients: Mutex<HashMap<SocketAddr, mpsc::Sender<Vec<u8>>>>,
+    next_port: AtomicU16,
+}
+
+// One client of a memory transport, the in-memory counterpart of a socket
+#[derive(Debug)]
+pub struct MemoryClient {
+    pub addr: SocketAddr,
+    server: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+    incoming: mpsc::Receiver<Vec<u8>>,
+}
+
+impl MemoryTransport {
+    // The server side is at port 1, clients get the ports after it
+    const SERVER_PORT: u16 = 1;
+
+    pub fn new() -> Self {
+        let (incoming_tx, incoming) = mpsc::channel(QUEUE_SIZE);
+
+        Self {
+            network: Arc::new(MemoryNetwork {
+                incoming_tx,
+                incoming: sync::Mutex::new(incoming),
+                clients: Mutex::new(HashMap::new()),
+                next_port: AtomicU16::new(Self::SERVER_PORT + 1),
+            }),
+        }
+    }
+
+    pub fn connect(&self) -> MemoryClient {
+        let port = self.network.next_port.fetch_add(1, Ordering::Relaxed);
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let (tx, incoming) = mpsc::channel(QUEUE_SIZE);
+
+        self.network.clients.lock().unwrap().insert(addr, tx);
+
+        MemoryClient {
+            addr,
+            server: self.network.incoming_tx.clone(),
+            incoming,
+        }
+    }
+}
+
+impl Default for MemoryTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(SocketAddr::from((Ipv4Addr::LOCALHOST, Self::SERVER_PORT)))
+    }
+
+    async fn send_to(&self, packet: &[u8], addr: SocketAddr) -> io::Result<()> {
+        let client = self.network.clients.lock().unwrap().get(&addr).cloned();
+        let Some(client) = client else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+
+        if client.try_send(packet.to_vec()).is_err() {
+            // The client went away, forget it
+            if client.is_closed() {
+                self.network.clients.lock().unwrap().remove(&addr);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn recv_from(&self) -> io::Result<(Vec<u8>, SocketAddr)> {
+        let mut incoming = self.network.incoming.lock().await;
+
+        // The network keeps a sender itself, so this never runs dry
+        incoming
+            .recv()
+            .await
+            .ok_or_else(|| io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+impl MemoryClient {
+    pub fn send(&self, packet: &[u8]) {
+        let _ = self.server.try_send((packet.to_vec(), self.addr));
+    }
+
+    pub async fn recv(&mut self) -> Option<Vec<u8>> {
+        self.incoming.recv().await
+    }
+}
@@ -0,0 +1,23 @@
+pub mod conditioned;
+pub mod fragmented;
+pub mod memory;
+pub mod udp;
+pub mod websocket;
+
+use std::{fmt::Debug, future::Future, io, net::SocketAddr};
+
+// Carries packets between the server and the clients. Every client is known by
+// an address, so the router and the rooms work the same whatever the transport.
+pub trait Transport: Debug + Send + Sync + 'static {
+    // Address the clients connect to, with the actual port when bound to port 0
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+
+    fn send_to(
+        &self,
+        packet: &[u8],
+        addr: SocketAddr,
+    ) -> impl Future<Output = io::Result<()>> + Send;
+
+    // Next packet from any client. Has to be cancel safe, the receive loop drops it on shutdown.
+    fn recv_from(&self) -> impl Future<Output = io::Result<(Vec<u8>, SocketAddr)>> + Send;
+}
@@ -0,0 +1,44 @@
+use std::{io, net::SocketAddr};
+
+use pong_multi_shared::fragment::MAX_PACKET_SIZE;
+use tokio::net::UdpSocket;
+
+use super::Transport;
+
+#[derive(Debug)]
+pub struct UdpTransport {
+    socket: UdpSocket,
+}
+
+impl UdpTransport {
+    pub async fn bind(addr: &str) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(addr).await?,
+        })
+    }
+}
+
+impl Transport for UdpTransport {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    async fn send_to(&self, packet: &[u8], addr: SocketAddr) -> io::Result<()> {
+        self.socket.send_to(packet, addr).await.map(|_| ())
+    }
+
+    async fn recv_from(&self) -> io::Result<(Vec<u8>, SocketAddr)> {
+        // One byte more than we accept, to tell a full datagram from a truncated one
+        let mut buf = [0; MAX_PACKET_SIZE + 1];
+
+        loop {
+            let (len, addr) = self.socket.recv_from(&mut buf).await?;
+            if len > MAX_PACKET_SIZE {
+                println!("Dropped an oversized datagram from {:?}", addr);
+                continue;
+            }
+
+            return Ok((buf[..len].to_vec(), addr));
+        }
+    }
+}
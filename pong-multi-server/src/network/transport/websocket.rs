@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{self, mpsc},
+    task::JoinHandle,
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+use super::Transport;
+
+// Packets waiting to be written to a connection before new ones are dropped
+const QUEUE_SIZE: usize = 256;
+
+type Clients = Arc<Mutex<HashMap<SocketAddr, mpsc::Sender<Vec<u8>>>>>;
+
+// Accepts WebSocket connections so browser builds can play.
+// Every binary message is one packet, exactly as it would be sent over UDP.
+#[derive(Debug)]
+pub struct WebSocketTransport {
+    addr: SocketAddr,
+    incoming: sync::Mutex<mpsc::Receiver<(Vec<u8>, SocketAddr)>>,
+    clients: Clients,
+    accept_loop: JoinHandle<()>,
+}
+
+impl WebSocketTransport {
+    pub async fn bind(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+
+        let (incoming_tx, incoming) = mpsc::channel(4096);
+        let clients = Clients::default();
+
+        let accept_loop = tokio::spawn(accept_loop(listener, incoming_tx, clients.clone()));
+
+        Ok(Self {
+            addr,
+            incoming: sync::Mutex::new(incoming),
+            clients,
+            accept_loop,
+        })
+    }
+}
+
+impl Drop for WebSocketTransport {
+    fn drop(&mut self) {
+        self.accept_loop.abort();
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    async fn send_to(&self, packet: &[u8], addr: SocketAddr) -> io::Result<()> {
+        let client = self.clients.lock().unwrap().get(&addr).cloned();
+        let Some(client) = client else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+
+        // A slow connection loses packets instead of holding up the router
+        let _ = client.try_send(packet.to_vec());
+
+        Ok(())
+    }
+
+    async fn recv_from(&self) -> io::Result<(Vec<u8>, SocketAddr)> {
+        self.incoming
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    incoming: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+    clients: Clients,
+) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                tokio::spawn(connection(stream, addr, incoming.clone(), clients.clone()));
+            }
+            Err(e) => eprintln!("Error accepting connection: {:?}", e),
+        }
+    }
+}
+
+// Pump packets both ways until either side closes the connection
+async fn connection(
+    stream: TcpStream,
+    addr: SocketAddr,
+    incoming: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+    clients: Clients,
+) {
+    let websocket = match accept_async(stream).await {
+        Ok(websocket) => websocket,
+        Err(e) => {
+            println!("WebSocket handshake with {:?} failed: {}", addr, e);
+            return;
+        }
+    };
+    let (mut sink, mut stream) = websocket.split();
+
+    let (tx, mut outgoing) = mpsc::channel::<Vec<u8>>(QUEUE_SIZE);
+    clients.lock().unwrap().insert(addr, tx);
+
+    println!("WebSocket connection from {:?}", addr);
+
+    let writer = tokio::spawn(async move {
+        while let Some(packet) = outgoing.recv().await {
+            if sink.send(Message::Binary(packet.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(message) = stream.next().await {
+        let packet = match message {
+            Ok(Message::Binary(data)) => data.to_vec(),
+
+            // Handy for poking at the server by hand
+            Ok(Message::Text(text)) => text.as_bytes().to_vec(),
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => continue,
+        };
+
+        if incoming.try_send((packet, addr)).is_err() && incoming.is_closed() {
+            break;
+        }
+    }
+
+    clients.lock().unwrap().remove(&addr);
+    writer.abort();
+
+    println!("WebSocket connection from {:?} closed", addr);
+}
@@ -0,0 +1,152 @@
+use std::{fmt, time::Instant};
+
+// Strikes before a player gets kicked
+pub const MAX_STRIKES: u32 = 5;
+
+// One input per tick, an ack per snapshot, plus pings and requests
+pub const MAX_MESSAGES_PER_SECOND: f32 = 110.0;
+const MESSAGE_BURST: f32 = 30.0;
+
+// Chat lines a player can send in a row, then one every couple of seconds
+const CHAT_BURST: f32 = 3.0;
+pub const CHAT_LINES_PER_SECOND: f32 = 0.5;
+
+// Inputs a player can bank when their packets arrive bunched together
+pub const MAX_INPUT_BURST: f32 = 10.0;
+
+// How far the tick claimed by an input can be from the room tick
+pub const MAX_TICK_LEAD: u64 = 10;
+pub const MAX_TICK_LAG: u64 = 120;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    MessageRate,
+    PaddleSpeed,
+    InvalidDirection(i64),
+    TickOutOfRange { tick: u64, current: u64 },
+    WrongRoom(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::MessageRate => write!(f, "message rate above {MAX_MESSAGES_PER_SECOND}/s"),
+            Violation::PaddleSpeed => write!(f, "more paddle inputs than simulated ticks"),
+            Violation::InvalidDirection(direction) => {
+                write!(f, "invalid paddle direction {direction}")
+            }
+            Violation::TickOutOfRange { tick, current } => {
+                write!(
+                    f,
+                    "input for tick {tick} while the room is at tick {current}"
+                )
+            }
+            Violation::WrongRoom(room_id) => write!(f, "acting in room {room_id}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Throttle {
+    Allowed,
+
+    // `first` is set on the first refusal after the bucket was last usable,
+    // so a burst only counts as one violation
+    Refused { first: bool },
+}
+
+#[derive(Debug)]
+pub struct TokenBucket {
+    tokens: f32,
+    capacity: f32,
+    exhausted: bool,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: f32) -> Self {
+        Self {
+            tokens: capacity,
+            capacity,
+            exhausted: false,
+        }
+    }
+
+    pub fn refill(&mut self, amount: f32) {
+        self.tokens = (self.tokens + amount).min(self.capacity);
+    }
+
+    pub fn take(&mut self) -> Throttle {
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            self.exhausted = false;
+            return Throttle::Allowed;
+        }
+
+        let first = !self.exhausted;
+        self.exhausted = true;
+        Throttle::Refused { first }
+    }
+}
+
+// Messages per second of one player, refilled from the wall clock
+#[derive(Debug)]
+pub struct MessageRate {
+    bucket: TokenBucket,
+    last_refill: Instant,
+}
+
+impl Default for MessageRate {
+    fn default() -> Self {
+        Self {
+            bucket: TokenBucket::new(MESSAGE_BURST),
+            last_refill: Instant::now(),
+        }
+    }
+}
+
+impl MessageRate {
+    pub fn check(&mut self) -> Throttle {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f32();
+        self.last_refill = now;
+
+        self.bucket.refill(elapsed * MAX_MESSAGES_PER_SECOND);
+        self.bucket.take()
+    }
+}
+
+// Chat lines of one player, spam is dropped without a strike since
+// unlike paddle inputs it doesn't take a modified client
+#[derive(Debug)]
+pub struct ChatRate {
+    bucket: TokenBucket,
+    last_refill: Instant,
+}
+
+impl Default for ChatRate {
+    fn default() -> Self {
+        Self {
+            bucket: TokenBucket::new(CHAT_BURST),
+            last_refill: Instant::now(),
+        }
+    }
+}
+
+impl ChatRate {
+    pub fn check(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f32();
+        self.last_refill = now;
+
+        self.bucket.refill(elapsed * CHAT_LINES_PER_SECOND);
+        self.bucket.take() == Throttle::Allowed
+    }
+}
+
+pub fn check_tick(tick: u64, current: u64) -> Result<(), Violation> {
+    if tick > current + MAX_TICK_LEAD || tick + MAX_TICK_LAG < current {
+        return Err(Violation::TickOutOfRange { tick, current });
+    }
+
+    Ok(())
+}
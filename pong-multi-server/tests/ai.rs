@@ -0,0 +1,129 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{local_addr, room_settings, start_server_with, TestClient};
+use pong_multi_server::network::{
+    match_maker::MatchMaker,
+    message::{MatchMakerCommand, ServerEvent},
+    room::{Room, RoomSettings},
+    server::ServerConfig,
+};
+use pong_multi_shared::game::{
+    ai::Difficulty,
+    formation::{Formation, GameMode, Seat, DEFAULT_DOUBLES_DEPTHS},
+    Side,
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn practice_matches_start_right_away() {
+    let server = start_server_with(ServerConfig::default()).await;
+
+    let mut client = TestClient::connect(server.addr, false).await;
+    client
+        .send(&json!({ "action": "join", "mode": "doubles", "practice": "hard" }))
+        .await;
+
+    let found = client.expect("match_found").await;
+    assert_eq!(
+        (found["side"].as_str(), found["slot"].as_u64()),
+        (Some("left"), Some(0))
+    );
+    assert_eq!(found["ai"], "hard");
+
+    // The first serve goes to our team, the AI next to us goes after it
+    let mut moved = false;
+    for _ in 0..100 {
+        let snapshot = client.expect("snapshot").await;
+        if snapshot["paddles"]["left_2"] != 0.0 {
+            moved = true;
+            break;
+        }
+    }
+    assert!(moved);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn players_who_opt_in_play_the_ai_after_waiting() {
+    let server = start_server_with(ServerConfig {
+        ai_fallback_after: Duration::from_millis(300),
+        ..ServerConfig::default()
+    })
+    .await;
+
+    let mut patient = TestClient::connect(server.addr, false).await;
+    patient
+        .send(&json!({ "action": "join", "mode": "doubles" }))
+        .await;
+
+    let mut waiting = TestClient::connect(server.addr, false).await;
+    waiting
+        .send(&json!({ "action": "join", "ai_fallback": "easy" }))
+        .await;
+
+    assert_eq!(waiting.expect("match_found").await["ai"], "easy");
+    assert!(!patient.receives("match_found").await);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn tickets_without_a_fallback_stay_queued() {
+    let (events, mut rx) = mpsc::channel(64);
+    let mut match_maker = MatchMaker::new(
+        events,
+        ServerConfig {
+            ai_fallback_after: Duration::ZERO,
+            ..ServerConfig::default()
+        },
+    );
+
+    for (port, ai_fallback) in [(1, None), (2, Some(Difficulty::Normal))] {
+        let join = MatchMakerCommand::Join {
+            players: vec![local_addr(port)],
+            mode: GameMode::Doubles,
+            ai_fallback,
+        };
+        match_maker.handle(join).await;
+    }
+    match_maker.expire_tickets().await;
+
+    let Some(ServerEvent::RoomCreated { players, .. }) = rx.recv().await else {
+        panic!("no room created");
+    };
+    assert_eq!(players, [(local_addr(2), Seat::new(Side::Left, 0))]);
+
+    let queue = &match_maker.queues[&GameMode::Doubles];
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue[0].players, [local_addr(1)]);
+}
+
+#[test]
+fn the_ai_takes_every_empty_seat() {
+    let addr = local_addr(1);
+    let settings = RoomSettings {
+        formation: Formation::doubles(DEFAULT_DOUBLES_DEPTHS),
+        ai: Some(Difficulty::Easy),
+        ..room_settings()
+    };
+    let room = Room::new(
+        Uuid::new_v4(),
+        &[(addr, Seat::new(Side::Left, 0))],
+        settings,
+    );
+
+    let seats: Vec<Seat> = room.bots.iter().map(|(seat, _)| *seat).collect();
+    assert_eq!(
+        seats,
+        [
+            Seat::new(Side::Left, 1),
+            Seat::new(Side::Right, 0),
+            Seat::new(Side::Right, 1),
+        ]
+    );
+}
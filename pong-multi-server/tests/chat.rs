@@ -0,0 +1,105 @@
+mod common;
+
+use common::{start_match, start_server_with, TestClient};
+use pong_multi_server::network::{
+    chat::WordFilter,
+    server::{Server, ServerConfig},
+};
+use serde_json::json;
+
+async fn start_server_with_filter(words: &[&str]) -> Server {
+    start_server_with(ServerConfig {
+        chat_filter: WordFilter::new(words),
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+#[test]
+fn the_filter_masks_banned_words() {
+    let filter = WordFilter::new(["darn", "Heck"]);
+
+    assert_eq!(filter.apply("oh darn it"), "oh **** it");
+    assert_eq!(filter.apply("HECKING  close"), "*******  close");
+    assert_eq!(filter.apply("nice shot"), "nice shot");
+    assert_eq!(WordFilter::default().apply("oh darn"), "oh darn");
+}
+
+#[tokio::test]
+async fn chat_goes_to_everyone_in_the_room() {
+    let server = start_server_with_filter(&["darn"]).await;
+    let (_, mut left, mut right) = start_match(server.addr).await;
+
+    left.send(&json!({ "action": "chat", "text": "  darn, nice shot " }))
+        .await;
+
+    for client in [&mut left, &mut right] {
+        let chat = client.expect("chat").await;
+        assert_eq!(chat["text"], "***** nice shot");
+        assert_eq!(chat["side"], "left");
+        assert_eq!(chat["from"], "Player");
+    }
+
+    right
+        .send(&json!({ "action": "chat", "emote": "gg" }))
+        .await;
+    let chat = left.expect("chat").await;
+    assert_eq!(chat["emote"], "gg");
+    assert_eq!(chat["side"], "right");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn lobby_chat_skips_players_in_a_match() {
+    let server = start_server_with_filter(&[]).await;
+    let (_, mut left, mut right) = start_match(server.addr).await;
+
+    let mut first = TestClient::connect_as(server.addr, "SwiftWolf123").await;
+    let mut second = TestClient::connect(server.addr, false).await;
+
+    first
+        .send(&json!({ "action": "chat", "text": "anyone up for doubles?" }))
+        .await;
+
+    let chat = second.expect("chat").await;
+    assert_eq!(chat["from"], "SwiftWolf123");
+    assert_eq!(chat["side"], json!(null));
+
+    // The first line the players in the match see is their own
+    right
+        .send(&json!({ "action": "chat", "emote": "oops" }))
+        .await;
+    assert_eq!(left.expect("chat").await["emote"], "oops");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn chat_spam_and_long_lines_are_dropped() {
+    let server = start_server_with_filter(&[]).await;
+    let (_, mut left, mut right) = start_match(server.addr).await;
+
+    let too_long = "a".repeat(500);
+    left.send(&json!({ "action": "chat", "text": too_long }))
+        .await;
+    for n in 0..10 {
+        left.send(&json!({ "action": "chat", "text": format!("line {n}") }))
+            .await;
+    }
+    right
+        .send(&json!({ "action": "chat", "text": "done" }))
+        .await;
+
+    let mut lines = Vec::new();
+    loop {
+        let text = left.expect("chat").await["text"].clone();
+        lines.push(text.clone());
+        if text == "done" {
+            break;
+        }
+    }
+    assert_eq!(lines, ["line 0", "line 1", "line 2", "done"]);
+
+    server.shutdown().await;
+}
@@ -0,0 +1,277 @@
+// Shared by several test crates, each only uses part of it
+#![allow(dead_code)]
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use pong_multi_server::{
+    game::pause::PauseRules,
+    network::{
+        room::RoomSettings,
+        server::{Server, ServerConfig},
+        transport::{
+            memory::{MemoryClient, MemoryTransport},
+            udp::UdpTransport,
+        },
+    },
+};
+use pong_multi_shared::{
+    fragment::{reassembler::Reassembler, Fragmenter},
+    game::{formation::Formation, map::Map, power_up::SpawnTable},
+    protocol::{Decoder, Encoder, WireFormat, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+    security::{
+        key_from_hex,
+        packet::{is_secure, Session},
+        to_hex, Handshake, Role,
+    },
+};
+use serde_json::{json, Value};
+use tokio::{net::UdpSocket, time::timeout};
+
+// Long enough for a loaded CI machine, short enough for a failing test to end quickly
+pub const TIMEOUT: Duration = Duration::from_secs(2);
+
+pub async fn start_server() -> Server {
+    start_server_with(ServerConfig::default()).await
+}
+
+// Kicks are still printed, but tests leave no audit log behind
+pub async fn start_server_with(config: ServerConfig) -> Server {
+    let config = ServerConfig {
+        audit_log: None,
+        ..config
+    };
+    let transport = UdpTransport::bind("127.0.0.1:0").await.unwrap();
+    Server::with_config(transport, config).expect("Failed to start the server")
+}
+
+// For tests that build rooms or queues by hand, nothing listens on it
+pub fn local_addr(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+// Singles with nothing else going on, tests change what they exercise
+pub fn room_settings() -> RoomSettings {
+    RoomSettings {
+        formation: Formation::singles(),
+        power_ups: SpawnTable::off(),
+        maps: vec![Map::classic()],
+        ai: None,
+        first_to: None,
+        pauses: PauseRules::default(),
+    }
+}
+
+pub enum Link {
+    Udp(UdpSocket),
+    Memory(MemoryClient),
+}
+
+// A player speaking the protocol over its own socket or memory link
+pub struct TestClient {
+    pub link: Link,
+    pub session: Option<Session>,
+    pub seq: u32,
+    pub fragmenter: Fragmenter,
+    pub reassembler: Reassembler,
+    pub encoder: Encoder,
+    pub decoder: Decoder,
+
+    // Payload of the last message received, as it came over the wire
+    pub last_payload: Vec<u8>,
+}
+
+impl TestClient {
+    pub async fn new(server: SocketAddr) -> Self {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.connect(server).await.unwrap();
+
+        Self::with_link(Link::Udp(socket))
+    }
+
+    pub fn over_memory(transport: &MemoryTransport) -> Self {
+        Self::with_link(Link::Memory(transport.connect()))
+    }
+
+    fn with_link(link: Link) -> Self {
+        Self {
+            link,
+            session: None,
+            seq: 0,
+            fragmenter: Fragmenter::default(),
+            reassembler: Reassembler::default(),
+            encoder: Encoder::new(WireFormat::Binary),
+            decoder: Decoder::default(),
+            last_payload: Vec::new(),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        match &self.link {
+            Link::Udp(socket) => socket.local_addr().unwrap(),
+            Link::Memory(client) => client.addr,
+        }
+    }
+
+    pub async fn connect(server: SocketAddr, encrypted: bool) -> Self {
+        Self::new(server).await.handshake(encrypted).await
+    }
+
+    // Enter and set up the session, every message after this is authenticated
+    pub async fn handshake(mut self, encrypted: bool) -> Self {
+        self.send(&enter_message()).await;
+        self.expect("entered").await;
+
+        self.exchange_keys(encrypted).await
+    }
+
+    // Connect with the name other players see
+    pub async fn connect_as(server: SocketAddr, name: &str) -> Self {
+        let mut client = Self::new(server).await;
+
+        let mut enter = enter_message();
+        enter["name"] = json!(name);
+        client.send(&enter).await;
+        client.expect("entered").await;
+
+        client.exchange_keys(false).await
+    }
+
+    async fn exchange_keys(mut self, encrypted: bool) -> Self {
+        let handshake = Handshake::new();
+        self.send(&json!({
+            "action": "handshake",
+            "public_key": to_hex(&handshake.public_key),
+            "encrypt": encrypted,
+        }))
+        .await;
+        let reply = self.expect("handshake").await;
+
+        let server_key = reply["public_key"].as_str().and_then(key_from_hex).unwrap();
+        self.session = Some(handshake.complete(Role::Client, server_key, encrypted));
+
+        self
+    }
+
+    // Connect and join the queue
+    pub async fn join(server: SocketAddr) -> Self {
+        let mut client = Self::connect(server, false).await;
+        client.send(&json!({ "action": "join" })).await;
+        client
+    }
+
+    pub async fn join_mode(server: SocketAddr, mode: &str) -> Self {
+        let mut client = Self::connect(server, false).await;
+        client
+            .send(&json!({ "action": "join", "mode": mode }))
+            .await;
+        client
+    }
+
+    pub async fn send(&mut self, message: &Value) {
+        let packet = self.seal(message);
+
+        for fragment in self.fragmenter.split(&packet) {
+            match &self.link {
+                Link::Udp(socket) => {
+                    socket.send(&fragment).await.unwrap();
+                }
+                Link::Memory(client) => client.send(&fragment),
+            }
+        }
+    }
+
+    pub fn seal(&mut self, message: &Value) -> Vec<u8> {
+        let payload = self.encoder.encode(message);
+        match self.session.as_mut() {
+            Some(session) => session.seal(&payload),
+            None => payload,
+        }
+    }
+
+    pub async fn send_move(&mut self, room_id: &str, direction: i64) {
+        self.seq += 1;
+        let message = json!({
+            "action": "move",
+            "room_id": room_id,
+            "seq": self.seq,
+            "direction": direction,
+        });
+        self.send(&message).await;
+    }
+
+    // Next message, None if nothing came before the timeout
+    pub async fn receive(&mut self) -> Option<Value> {
+        let packet = loop {
+            let datagram = match &mut self.link {
+                Link::Udp(socket) => {
+                    let mut buf = [0; 2048];
+                    let len = timeout(TIMEOUT, socket.recv(&mut buf)).await.ok()?.unwrap();
+                    buf[..len].to_vec()
+                }
+                Link::Memory(client) => timeout(TIMEOUT, client.recv()).await.ok()??,
+            };
+
+            if let Some(packet) = self.reassembler.receive(Instant::now(), datagram) {
+                break packet;
+            }
+        };
+
+        let payload = match self.session.as_mut() {
+            Some(session) if is_secure(&packet) => session.open(&packet).unwrap(),
+            _ => packet,
+        };
+
+        let message = self.decoder.decode(&payload).unwrap();
+        self.last_payload = payload;
+        Some(message)
+    }
+
+    // Skip messages until the given action, panics if it never comes
+    pub async fn expect(&mut self, action: &str) -> Value {
+        while let Some(message) = self.receive().await {
+            if message["action"] == action {
+                return message;
+            }
+        }
+
+        panic!("No {action} received");
+    }
+
+    // Whether the given action comes before the timeout, skipping snapshots
+    pub async fn receives(&mut self, action: &str) -> bool {
+        while let Some(message) = self.receive().await {
+            if message["action"] == action {
+                return true;
+            }
+            if message["action"] != "snapshot" {
+                return false;
+            }
+        }
+
+        false
+    }
+}
+
+pub fn enter_message() -> Value {
+    json!({
+        "action": "enter",
+        "protocol": PROTOCOL_VERSION,
+        "min_protocol": MIN_PROTOCOL_VERSION,
+        "build": "test",
+    })
+}
+
+// Two players in the same room, returns the room id with them
+pub async fn start_match(server: SocketAddr) -> (String, TestClient, TestClient) {
+    let mut left = TestClient::join(server).await;
+    let mut right = TestClient::join(server).await;
+
+    let found = left.expect("match_found").await;
+    right.expect("match_found").await;
+
+    let room_id = found["room_id"].as_str().unwrap().to_string();
+    (room_id, left, right)
+}
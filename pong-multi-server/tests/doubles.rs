@@ -0,0 +1,130 @@
+mod common;
+
+use std::{collections::HashSet, net::SocketAddr};
+
+use common::{local_addr, start_server, TestClient};
+use pong_multi_server::network::{
+    match_maker::MatchMaker,
+    message::{MatchMakerCommand, ServerEvent},
+    server::ServerConfig,
+};
+use pong_multi_shared::game::{
+    formation::{GameMode, Seat, DEFAULT_DOUBLES_DEPTHS},
+    Side,
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+
+async fn start_doubles(server: SocketAddr) -> Vec<TestClient> {
+    let mut clients = Vec::new();
+    for _ in 0..4 {
+        clients.push(TestClient::join_mode(server, "doubles").await);
+    }
+    clients
+}
+
+#[tokio::test]
+async fn four_players_fill_a_doubles_room() {
+    let server = start_server().await;
+
+    // A singles player waiting on their own isn't pulled into doubles
+    let mut single = TestClient::join(server.addr).await;
+    let mut clients = start_doubles(server.addr).await;
+
+    let mut rooms = HashSet::new();
+    let mut seats = HashSet::new();
+    for client in &mut clients {
+        let found = client.expect("match_found").await;
+
+        assert_eq!(found["mode"], "doubles");
+        assert_eq!(found["depths"], json!(DEFAULT_DOUBLES_DEPTHS));
+        rooms.insert(found["room_id"].as_str().unwrap().to_string());
+        seats.insert((
+            found["side"].as_str().unwrap().to_string(),
+            found["slot"].as_u64().unwrap(),
+        ));
+    }
+
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(seats.len(), 4);
+    assert!(!single.receives("match_found").await);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn doubles_snapshots_carry_every_paddle() {
+    let server = start_server().await;
+    let mut clients = start_doubles(server.addr).await;
+
+    let snapshot = clients[0].expect("snapshot").await;
+    for key in ["left", "left_2", "right", "right_2"] {
+        assert!(snapshot["paddles"][key].is_number(), "{snapshot}");
+    }
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn a_leaving_player_forfeits_for_their_team() {
+    let server = start_server().await;
+    let mut clients = start_doubles(server.addr).await;
+
+    let mut sides = Vec::new();
+    for client in &mut clients {
+        let found = client.expect("match_found").await;
+        sides.push(found["side"].as_str().unwrap().to_string());
+    }
+
+    clients[0].send(&json!({ "action": "leave" })).await;
+    let winner = if sides[0] == "left" { "right" } else { "left" };
+
+    for client in &mut clients[1..] {
+        let over = client.expect("match_over").await;
+        assert_eq!(over["winner"], winner);
+        assert_eq!(over["reason"], "forfeit");
+    }
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn parties_are_kept_on_the_same_team() {
+    let (events, mut rx) = mpsc::channel(64);
+    let mut match_maker = MatchMaker::new(events, ServerConfig::default());
+
+    let join = |players| MatchMakerCommand::Join {
+        players,
+        mode: GameMode::Doubles,
+        ai_fallback: None,
+    };
+
+    match_maker.handle(join(vec![local_addr(1)])).await;
+    match_maker
+        .handle(join(vec![local_addr(2), local_addr(3)]))
+        .await;
+
+    // Already queued, ignored
+    match_maker.handle(join(vec![local_addr(1)])).await;
+
+    match_maker.handle(join(vec![local_addr(4)])).await;
+
+    let players = loop {
+        match rx.recv().await {
+            Some(ServerEvent::RoomCreated { players, .. }) => break players,
+            Some(_) => continue,
+            None => panic!("no room created"),
+        }
+    };
+
+    assert_eq!(
+        players,
+        [
+            (local_addr(1), Seat::new(Side::Left, 0)),
+            (local_addr(4), Seat::new(Side::Left, 1)),
+            (local_addr(2), Seat::new(Side::Right, 0)),
+            (local_addr(3), Seat::new(Side::Right, 1)),
+        ]
+    );
+    assert!(match_maker.queues[&GameMode::Doubles].is_empty());
+}
@@ -0,0 +1,47 @@
+use pong_multi_server::network::{
+    message::ServerEvent, server::Server, transport::memory::MemoryTransport,
+};
+use pong_multi_shared::fragment::MAX_PACKET_SIZE;
+use serde_json::json;
+
+mod common;
+
+use common::{start_server, TestClient};
+
+#[tokio::test]
+async fn large_messages_reach_the_server() {
+    let server = start_server().await;
+    let mut client = TestClient::connect(server.addr, true).await;
+
+    let padding = "x".repeat(MAX_PACKET_SIZE * 4);
+    client
+        .send(&json!({ "action": "ping", "client_time": 1.0, "padding": padding }))
+        .await;
+
+    let pong = client.expect("pong").await;
+    assert_eq!(pong["client_time"], 1.0);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn large_messages_reach_the_client() {
+    let memory = MemoryTransport::new();
+    let server = Server::with_transport(memory.clone()).unwrap();
+    let mut client = TestClient::over_memory(&memory).handshake(false).await;
+
+    let history: Vec<u32> = (0..2000).collect();
+    server
+        .events
+        .send(ServerEvent::Send {
+            addr: client.addr(),
+            message: json!({ "action": "history", "matches": history }),
+        })
+        .await
+        .unwrap();
+
+    let received = client.expect("history").await;
+    assert_eq!(received["matches"], json!(history));
+
+    server.shutdown().await;
+}
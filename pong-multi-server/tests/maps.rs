@@ -0,0 +1,115 @@
+mod common;
+
+use common::{local_addr, room_settings, start_match, start_server_with, TestClient};
+use pong_multi_server::{
+    game::vote::MapVote,
+    network::{
+        room::{InputOutcome, Room, RoomSettings},
+        server::{Server, ServerConfig},
+    },
+};
+use pong_multi_shared::game::{formation::Seat, map::Map, Side};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn start_server_with_maps(names: &[&str]) -> Server {
+    start_server_with(ServerConfig {
+        maps: names
+            .iter()
+            .map(|name| Map::by_name(name).unwrap())
+            .collect(),
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+async fn vote(client: &mut TestClient, room_id: &str, map: &str) {
+    client
+        .send(&json!({ "action": "vote", "room_id": room_id, "map": map }))
+        .await;
+}
+
+#[tokio::test]
+async fn players_vote_on_the_map_before_the_match() {
+    let server = start_server_with_maps(&["classic", "pillars", "windmills"]).await;
+    let (room_id, mut left, mut right) = start_match(server.addr).await;
+
+    let ballot = left.expect("map_vote").await;
+    assert_eq!(ballot["maps"], json!(["classic", "pillars", "windmills"]));
+    right.expect("map_vote").await;
+
+    vote(&mut left, &room_id, "windmills").await;
+    vote(&mut right, &room_id, "windmills").await;
+
+    for client in [&mut left, &mut right] {
+        let picked = client.expect("map_picked").await;
+        assert_eq!(
+            Map::from_json(&picked["map"]),
+            Ok(Map::by_name("windmills").unwrap())
+        );
+    }
+
+    // The match starts once the map is picked
+    assert!(left.receives("snapshot").await);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn a_single_map_is_played_without_a_vote() {
+    let server = start_server_with_maps(&["wormholes"]).await;
+    let (_, mut left, _) = start_match(server.addr).await;
+
+    let picked = left.receive().await.unwrap();
+    assert_eq!(picked["action"], "map_picked");
+    assert_eq!(picked["map"]["name"], "wormholes");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn the_classic_field_sends_no_map() {
+    let server = start_server_with_maps(&["classic"]).await;
+    let (_, mut left, _) = start_match(server.addr).await;
+
+    assert_eq!(left.receive().await.unwrap()["action"], "snapshot");
+
+    server.shutdown().await;
+}
+
+#[test]
+fn ties_go_to_the_map_listed_first() {
+    let maps = Map::built_in();
+    let mut vote = MapVote::new(maps.clone());
+    let (left, right) = (local_addr(1), local_addr(2));
+
+    assert_eq!(vote.winner(), &maps[0]);
+
+    assert!(vote.cast(left, "wormholes"));
+    assert!(vote.cast(right, "pillars"));
+    assert!(!vote.cast(right, "nowhere"));
+    assert_eq!(vote.winner().name, "pillars");
+    assert!(vote.is_over(2));
+
+    // Players can change their mind
+    assert!(vote.cast(right, "wormholes"));
+    assert_eq!(vote.winner().name, "wormholes");
+}
+
+#[test]
+fn paddles_stay_put_during_the_vote() {
+    let addr = local_addr(1);
+    let seat = Seat::new(Side::Left, 0);
+    let settings = RoomSettings {
+        maps: Map::built_in(),
+        ..room_settings()
+    };
+    let mut room = Room::new(Uuid::new_v4(), &[(addr, seat)], settings);
+
+    assert_eq!(room.apply_input(&addr, 1, 1, None), InputOutcome::Dropped);
+
+    let picked = room.cast_vote(&addr, "pillars");
+    assert_eq!(picked[0].1["map"]["name"], "pillars");
+    assert_eq!(room.state.map.name, "pillars");
+    assert_eq!(room.apply_input(&addr, 2, 1, None), InputOutcome::Applied);
+}
@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+use pong_multi_server::network::{
+    server::Server,
+    transport::{conditioned::ConditionedTransport, memory::MemoryTransport},
+};
+use pong_multi_shared::netsim::NetworkConditions;
+use serde_json::json;
+
+mod common;
+
+use common::TestClient;
+
+#[tokio::test]
+async fn latency_applies_both_ways_and_changes_at_runtime() {
+    let memory = MemoryTransport::new();
+    let transport = ConditionedTransport::new(memory.clone(), NetworkConditions::default());
+    let network = transport.control();
+    let server = Server::with_transport(transport).unwrap();
+
+    let mut client = TestClient::over_memory(&memory).handshake(false).await;
+
+    network.set("latency=100".parse().unwrap());
+
+    let sent = Instant::now();
+    client
+        .send(&json!({ "action": "ping", "client_time": 0.0 }))
+        .await;
+    client.expect("pong").await;
+    assert!(sent.elapsed() >= Duration::from_millis(200));
+
+    network.set(NetworkConditions::default());
+
+    let sent = Instant::now();
+    client
+        .send(&json!({ "action": "ping", "client_time": 0.0 }))
+        .await;
+    client.expect("pong").await;
+    assert!(sent.elapsed() < Duration::from_millis(100));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn total_loss_drops_everything() {
+    let memory = MemoryTransport::new();
+    let transport = ConditionedTransport::new(memory.clone(), "loss=1".parse().unwrap());
+    let server = Server::with_transport(transport).unwrap();
+
+    let mut client = TestClient::over_memory(&memory);
+    client.send(&json!({ "action": "enter" })).await;
+
+    assert!(client.receive().await.is_none());
+
+    server.shutdown().await;
+}
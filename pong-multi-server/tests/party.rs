@@ -0,0 +1,196 @@
+mod common;
+
+use std::net::SocketAddr;
+
+use common::{local_addr, start_server, TestClient};
+use pong_multi_server::network::{
+    match_maker::MatchMaker,
+    message::{MatchMakerCommand, ServerEvent},
+    party::{normalize_code, Lineup, Party, MAX_PARTY_SIZE},
+    server::ServerConfig,
+};
+use pong_multi_shared::game::formation::GameMode;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+// A leader and a friend who joined with the code
+async fn start_party(server: SocketAddr) -> (TestClient, TestClient) {
+    let mut leader = TestClient::connect(server, false).await;
+    leader.send(&json!({ "action": "party_create" })).await;
+    let party = leader.expect("party").await;
+    assert_eq!(party["leading"], true);
+    let code = party["code"].as_str().unwrap().to_lowercase();
+
+    let mut friend = TestClient::connect(server, false).await;
+    friend
+        .send(&json!({ "action": "party_join", "code": code }))
+        .await;
+
+    for client in [&mut leader, &mut friend] {
+        let party = client.expect("party").await;
+        assert_eq!(party["members"].as_array().unwrap().len(), 2);
+    }
+
+    (leader, friend)
+}
+
+#[test]
+fn parties_line_up_by_mode() {
+    let mut party = Party::new("ABC234".to_string(), local_addr(1));
+    party.add(local_addr(2));
+    assert_eq!(
+        party.lineup(GameMode::Doubles),
+        Some(Lineup::Team(vec![local_addr(1), local_addr(2)]))
+    );
+    assert_eq!(
+        party.lineup(GameMode::Singles),
+        Some(Lineup::Versus([vec![local_addr(1)], vec![local_addr(2)]]))
+    );
+
+    party.add(local_addr(3));
+    assert_eq!(party.lineup(GameMode::Singles), None);
+    assert_eq!(party.lineup(GameMode::Doubles), None);
+
+    party.add(local_addr(4));
+    assert!(!party.add(local_addr(5)));
+    assert_eq!(party.members.len(), MAX_PARTY_SIZE);
+
+    party.remove(&local_addr(1));
+    assert_eq!(party.leader(), local_addr(2));
+    assert_eq!(normalize_code(" abc 234"), "ABC234");
+}
+
+#[tokio::test]
+async fn lineups_the_match_maker_turns_down_are_sent_back() {
+    let (events, mut rx) = mpsc::channel(64);
+    let mut match_maker = MatchMaker::new(events, ServerConfig::default());
+
+    let join = MatchMakerCommand::Join {
+        players: vec![local_addr(1)],
+        mode: GameMode::Doubles,
+        ai_fallback: None,
+    };
+    match_maker.handle(join).await;
+
+    // Teams that don't fit the mode, with a player who is already queued
+    let versus = MatchMakerCommand::Versus {
+        teams: [vec![local_addr(1), local_addr(2)], vec![local_addr(3)]],
+        mode: GameMode::Singles,
+        first_to: None,
+    };
+    match_maker.handle(versus).await;
+
+    let Some(ServerEvent::JoinRefused { players }) = rx.recv().await else {
+        panic!("no refusal");
+    };
+    assert_eq!(players, [local_addr(2), local_addr(3)]);
+    assert_eq!(match_maker.queues[&GameMode::Doubles].len(), 1);
+}
+
+#[tokio::test]
+async fn a_party_queues_as_a_team_in_doubles() {
+    let server = start_server().await;
+    let (mut leader, mut friend) = start_party(server.addr).await;
+
+    let mut others = Vec::new();
+    for _ in 0..2 {
+        others.push(TestClient::join_mode(server.addr, "doubles").await);
+    }
+    leader
+        .send(&json!({ "action": "join", "mode": "doubles" }))
+        .await;
+
+    let leader_found = leader.expect("match_found").await;
+    let friend_found = friend.expect("match_found").await;
+    assert_eq!(leader_found["room_id"], friend_found["room_id"]);
+    assert_eq!(leader_found["side"], friend_found["side"]);
+
+    for other in &mut others {
+        let found = other.expect("match_found").await;
+        assert_eq!(found["room_id"], leader_found["room_id"]);
+        assert_ne!(found["side"], leader_found["side"]);
+    }
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn a_party_of_two_plays_each_other_in_singles() {
+    let server = start_server().await;
+    let mut stranger = TestClient::join(server.addr).await;
+    let (mut leader, mut friend) = start_party(server.addr).await;
+
+    leader.send(&json!({ "action": "join" })).await;
+
+    let leader_found = leader.expect("match_found").await;
+    let friend_found = friend.expect("match_found").await;
+    assert_eq!(leader_found["room_id"], friend_found["room_id"]);
+    assert_eq!(leader_found["side"], "left");
+    assert_eq!(friend_found["side"], "right");
+    assert!(!stranger.receives("match_found").await);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn only_the_leader_queues_the_party() {
+    let server = start_server().await;
+    let (mut leader, mut friend) = start_party(server.addr).await;
+
+    friend.send(&json!({ "action": "join" })).await;
+    assert!(!leader.receives("match_found").await);
+
+    // Once the leader is gone the friend leads
+    leader.send(&json!({ "action": "party_leave" })).await;
+    leader.expect("party_left").await;
+    let party = friend.expect("party").await;
+    assert_eq!(party["leading"], true);
+    assert_eq!(party["members"].as_array().unwrap().len(), 1);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn players_are_invited_by_name() {
+    let server = start_server().await;
+    let mut leader = TestClient::connect_as(server.addr, "BraveTiger404").await;
+    let mut invited = TestClient::connect_as(server.addr, "SwiftWolf123").await;
+
+    leader.send(&json!({ "action": "party_create" })).await;
+    let code = leader.expect("party").await["code"].clone();
+
+    leader
+        .send(&json!({ "action": "party_invite", "name": "swiftwolf123" }))
+        .await;
+    let invite = invited.expect("party_invite").await;
+    assert_eq!(invite["from"], "BraveTiger404");
+    assert_eq!(invite["code"], code);
+
+    invited
+        .send(&json!({ "action": "party_join", "code": code }))
+        .await;
+    let party = invited.expect("party").await;
+    assert_eq!(party["members"], json!(["BraveTiger404", "SwiftWolf123"]));
+    assert_eq!(party["leading"], false);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn parties_stay_together_after_a_match() {
+    let server = start_server().await;
+    let (mut leader, mut friend) = start_party(server.addr).await;
+
+    leader.send(&json!({ "action": "join" })).await;
+    leader.expect("match_found").await;
+    friend.expect("match_found").await;
+
+    friend.send(&json!({ "action": "leave" })).await;
+    leader.expect("match_over").await;
+
+    leader.send(&json!({ "action": "join" })).await;
+    assert!(leader.receives("match_found").await);
+    assert!(friend.receives("match_found").await);
+
+    server.shutdown().await;
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{local_addr, room_settings, start_match, start_server, start_server_with};
+use pong_multi_server::{
+    game::pause::{Pause, PausePhase, PauseRefused, PauseRules},
+    network::{
+        room::{InputOutcome, Room},
+        server::ServerConfig,
+    },
+};
+use pong_multi_shared::game::{formation::Seat, Side, TICK_RATE};
+use serde_json::json;
+use uuid::Uuid;
+
+#[test]
+fn players_only_get_so_many_pauses() {
+    let mut pause = Pause::new(PauseRules {
+        per_player: 1,
+        ..PauseRules::default()
+    });
+
+    assert_eq!(pause.request(local_addr(1), false), Ok(()));
+    assert_eq!(
+        pause.request(local_addr(2), false),
+        Err(PauseRefused::AlreadyPaused)
+    );
+    assert!(pause.resume(local_addr(1), 2));
+
+    while !pause.is_running() {
+        pause.tick();
+    }
+    assert_eq!(pause.phase, PausePhase::Playing);
+    assert_eq!(
+        pause.request(local_addr(1), false),
+        Err(PauseRefused::NoPausesLeft)
+    );
+    assert_eq!(pause.pauses_left(&local_addr(2)), 1);
+}
+
+#[test]
+fn pending_pauses_start_after_the_point() {
+    let mut pause = Pause::new(PauseRules::default());
+
+    pause.request(local_addr(1), true).unwrap();
+    assert!(pause.is_running());
+    assert!(pause.point_over());
+    assert!(!pause.is_running());
+
+    // Taking back a pause that didn't start gives it back
+    let mut pause = Pause::new(PauseRules::default());
+    pause.request(local_addr(1), true).unwrap();
+    assert!(pause.resume(local_addr(1), 2));
+    assert_eq!(pause.phase, PausePhase::Playing);
+    assert_eq!(pause.pauses_left(&local_addr(1)), 2);
+}
+
+#[test]
+fn the_others_can_vote_to_resume() {
+    let mut pause = Pause::new(PauseRules::default());
+    pause.request(local_addr(1), false).unwrap();
+
+    // Two of the three others in doubles
+    assert!(pause.resume(local_addr(2), 4));
+    assert!(!pause.resume(local_addr(2), 4));
+    assert!(matches!(pause.phase, PausePhase::Paused { .. }));
+
+    assert!(pause.resume(local_addr(3), 4));
+    assert!(matches!(pause.phase, PausePhase::Resuming { .. }));
+    assert_eq!(pause.seconds_left(), 3);
+}
+
+#[test]
+fn pauses_end_by_themselves() {
+    let mut pause = Pause::new(PauseRules {
+        max_length: Duration::from_secs(1),
+        countdown: Duration::from_secs(1),
+        ..PauseRules::default()
+    });
+    pause.request(local_addr(1), false).unwrap();
+
+    let changes = (0..TICK_RATE * 2).filter(|_| pause.tick()).count();
+    assert_eq!(changes, 2);
+    assert_eq!(pause.phase, PausePhase::Playing);
+}
+
+#[test]
+fn paddles_stay_put_while_paused() {
+    let (left, right) = (local_addr(1), local_addr(2));
+    let players = [
+        (left, Seat::new(Side::Left, 0)),
+        (right, Seat::new(Side::Right, 0)),
+    ];
+    let mut room = Room::new(Uuid::new_v4(), &players, room_settings());
+
+    let paused = room.request_pause(&left, false);
+    assert_eq!(paused.len(), 2);
+    assert_eq!(room.apply_input(&right, 1, 1, None), InputOutcome::Dropped);
+
+    // Spectators can't pause or vote
+    assert!(room.resume(&local_addr(3)).is_empty());
+}
+
+#[tokio::test]
+async fn the_player_who_paused_resumes() {
+    let server = start_server().await;
+    let (room_id, mut left, _right) = start_match(server.addr).await;
+
+    left.send(&json!({ "action": "pause", "room_id": &room_id }))
+        .await;
+    assert_eq!(left.expect("pause_state").await["state"], "paused");
+
+    left.send(&json!({ "action": "resume", "room_id": &room_id }))
+        .await;
+    let resuming = left.expect("pause_state").await;
+    assert_eq!(resuming["state"], "resuming");
+    assert_eq!(resuming["seconds"], 3);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn paused_matches_go_on_once_resumed() {
+    let server = start_server_with(ServerConfig {
+        pauses: PauseRules {
+            per_player: 1,
+            countdown: Duration::from_millis(200),
+            ..PauseRules::default()
+        },
+        ..ServerConfig::default()
+    })
+    .await;
+    let (room_id, mut left, mut right) = start_match(server.addr).await;
+
+    let pause = json!({ "action": "pause", "room_id": &room_id });
+    let resume = json!({ "action": "resume", "room_id": &room_id });
+
+    left.send(&pause).await;
+    let paused = left.expect("pause_state").await;
+    assert_eq!(paused["state"], "paused");
+    assert_eq!(paused["side"], "left");
+    assert_eq!(paused["yours"], true);
+    assert_eq!(paused["pauses_left"], 0);
+
+    let paused = right.expect("pause_state").await;
+    assert_eq!(paused["yours"], false);
+    assert_eq!(paused["needed"], 1);
+
+    // In singles the vote of the other player is enough
+    right.send(&resume).await;
+    assert_eq!(right.expect("pause_state").await["state"], "resuming");
+    assert_eq!(right.expect("pause_state").await["state"], "playing");
+    right.expect("snapshot").await;
+
+    left.send(&pause).await;
+    let refused = left.expect("pause_refused").await;
+    assert_eq!(refused["reason"], "no_pauses_left");
+
+    server.shutdown().await;
+}
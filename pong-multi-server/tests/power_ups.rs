@@ -0,0 +1,85 @@
+mod common;
+
+use common::{local_addr, room_settings, start_match, start_server_with, TestClient, TIMEOUT};
+use pong_multi_server::network::{
+    room::{InputOutcome, Room},
+    server::ServerConfig,
+};
+use pong_multi_shared::game::{
+    formation::Seat,
+    power_up::{PowerUpKind, SpawnTable, LASER_STUN_TICKS},
+    Side,
+};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+// Wait for a snapshot matching the condition, None if none came in time
+async fn find_snapshot(
+    client: &mut TestClient,
+    condition: impl Fn(&serde_json::Value) -> bool,
+) -> Option<serde_json::Value> {
+    let deadline = Instant::now() + TIMEOUT;
+
+    while Instant::now() < deadline {
+        let Some(message) = client.receive().await else {
+            continue;
+        };
+        if message["action"] == "snapshot" && condition(&message) {
+            return Some(message);
+        }
+    }
+
+    None
+}
+
+#[tokio::test]
+async fn power_ups_from_the_spawn_table_show_up_in_snapshots() {
+    let server = start_server_with(ServerConfig {
+        singles_power_ups: "every=1,key=1".parse().unwrap(),
+        ..ServerConfig::default()
+    })
+    .await;
+    let (_, mut left, _) = start_match(server.addr).await;
+
+    let snapshot = find_snapshot(&mut left, |snapshot| snapshot["power_up"].is_object())
+        .await
+        .expect("no power-up spawned");
+
+    assert_eq!(snapshot["power_up"]["kind"], PowerUpKind::Key.id());
+    assert_eq!(snapshot["lock"]["open"], 0);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn matches_without_power_ups_send_none() {
+    let server = start_server_with(ServerConfig {
+        singles_power_ups: SpawnTable::off(),
+        ..ServerConfig::default()
+    })
+    .await;
+    let (_, mut left, _) = start_match(server.addr).await;
+
+    let snapshot = find_snapshot(&mut left, |_| true).await.unwrap();
+    assert!(snapshot.get("lock").is_none());
+    assert!(snapshot.get("power_up").is_none());
+
+    server.shutdown().await;
+}
+
+#[test]
+fn stunned_paddles_ignore_inputs() {
+    let addr = local_addr(1);
+    let seat = Seat::new(Side::Left, 0);
+    let mut room = Room::new(Uuid::new_v4(), &[(addr, seat)], room_settings());
+
+    room.state.stunned[Side::Left.index()] = LASER_STUN_TICKS;
+
+    assert_eq!(room.apply_input(&addr, 1, 1, None), InputOutcome::Applied);
+    assert_eq!(room.state.paddle(seat).y, 0.0);
+    assert_eq!(room.last_inputs[&addr], 1);
+
+    room.state.stunned = [0, 0];
+    assert_eq!(room.apply_input(&addr, 2, 1, None), InputOutcome::Applied);
+    assert!(room.state.paddle(seat).y > 0.0);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::{start_match, start_server, TestClient};
+use pong_multi_server::network::listing::{page, ListingKind, RoomFilter, MAX_ROOMS_PER_PAGE};
+use pong_multi_shared::game::formation::GameMode;
+use serde_json::json;
+
+#[test]
+fn filters_narrow_the_list() {
+    let filter = RoomFilter::from_json(&json!({ "kind": "live", "name": " AN " })).unwrap();
+    assert_eq!(filter.kind, Some(ListingKind::Live));
+    assert_eq!(filter.name.as_deref(), Some("an"));
+
+    assert!(filter.wants(ListingKind::Live, &[GameMode::Singles], &["Bob", "Anna"]));
+    assert!(!filter.wants(ListingKind::Live, &[GameMode::Singles], &["Bob"]));
+    assert!(!filter.wants(ListingKind::Open, &GameMode::ALL, &["Anna"]));
+
+    let doubles = RoomFilter::from_json(&json!({ "mode": "doubles" })).unwrap();
+    assert!(!doubles.wants(ListingKind::Live, &[GameMode::Singles], &[]));
+    assert!(RoomFilter::from_json(&json!({ "kind": "private" })).is_err());
+}
+
+#[test]
+fn pages_split_the_list() {
+    let (entries, pages) = page((0..10).collect(), 1, 4);
+    assert_eq!(entries, vec![4, 5, 6, 7]);
+    assert_eq!(pages, 3);
+
+    assert!(page((0..10).collect::<Vec<_>>(), 5, 4).0.is_empty());
+    assert_eq!(page(Vec::<u32>::new(), 0, 4).1, 1);
+    assert_eq!(
+        page((0..50).collect::<Vec<_>>(), 0, 100).0.len(),
+        MAX_ROOMS_PER_PAGE
+    );
+}
+
+#[tokio::test]
+async fn listed_parties_show_as_open() {
+    let server = start_server().await;
+
+    let mut leader = TestClient::connect_as(server.addr, "Anna").await;
+    leader
+        .send(&json!({ "action": "party_create", "listed": true }))
+        .await;
+    let party = leader.expect("party").await;
+    assert_eq!(party["listed"], true);
+
+    let mut hidden = TestClient::connect_as(server.addr, "Andy").await;
+    hidden.send(&json!({ "action": "party_create" })).await;
+    hidden.expect("party").await;
+
+    let mut browser = TestClient::connect(server.addr, false).await;
+    browser
+        .send(&json!({ "action": "list_rooms", "kind": "open" }))
+        .await;
+
+    let rooms = browser.expect("rooms").await;
+    assert_eq!(rooms["total"], 1);
+    assert_eq!(rooms["rooms"][0]["code"], party["code"]);
+    assert_eq!(rooms["rooms"][0]["players"], json!(["Anna"]));
+    assert_eq!(rooms["rooms"][0]["slots"], 3);
+    assert_eq!(rooms["rooms"][0]["modes"], json!(["singles", "doubles"]));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn live_matches_can_be_watched() {
+    let server = start_server().await;
+    let (room_id, mut left, _right) = start_match(server.addr).await;
+
+    let mut spectator = TestClient::connect(server.addr, false).await;
+    spectator
+        .send(&json!({ "action": "list_rooms", "mode": "singles" }))
+        .await;
+
+    let rooms = spectator.expect("rooms").await;
+    assert_eq!(rooms["pages"], 1);
+    let room = &rooms["rooms"][0];
+    assert_eq!(room["kind"], "live");
+    assert_eq!(room["room_id"], room_id.as_str());
+    assert_eq!(room["score"], json!({ "left": 0, "right": 0 }));
+    assert_eq!(room["spectators"], 0);
+
+    spectator
+        .send(&json!({ "action": "spectate", "room_id": &room_id }))
+        .await;
+    let spectating = spectator.expect("spectating").await;
+    assert_eq!(spectating["room_id"], room_id.as_str());
+    spectator.expect("snapshot").await;
+
+    spectator.send(&json!({ "action": "list_rooms" })).await;
+    assert_eq!(spectator.expect("rooms").await["rooms"][0]["spectators"], 1);
+
+    // Spectators see the end of the match like the players
+    left.send(&json!({ "action": "leave" })).await;
+    let over = spectator.expect("match_over").await;
+    assert_eq!(over["winner"], "right");
+
+    server.shutdown().await;
+}
@@ -0,0 +1,252 @@
+mod common;
+
+use common::{enter_message, start_match, start_server, TestClient};
+use pong_multi_server::network::{
+    server::{Server, ServerConfig},
+    transport::udp::UdpTransport,
+};
+use pong_multi_shared::{
+    protocol::WireFormat,
+    security::{key_from_hex, to_hex, Handshake, Role},
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn two_players_join_and_get_paired() {
+    let server = start_server().await;
+
+    let mut left = TestClient::join(server.addr).await;
+    let mut right = TestClient::join(server.addr).await;
+
+    let left_found = left.expect("match_found").await;
+    let right_found = right.expect("match_found").await;
+
+    assert_eq!(left_found["room_id"], right_found["room_id"]);
+    assert_eq!(left_found["side"], "left");
+    assert_eq!(right_found["side"], "right");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn leave_awards_forfeit() {
+    let server = start_server().await;
+    let (_, mut left, mut right) = start_match(server.addr).await;
+
+    left.send(&json!({ "action": "leave" })).await;
+
+    let over = right.expect("match_over").await;
+    assert_eq!(over["winner"], "right");
+    assert_eq!(over["reason"], "forfeit");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn players_can_queue_again_after_a_match() {
+    let server = start_server().await;
+    let (_, mut left, mut right) = start_match(server.addr).await;
+
+    left.send(&json!({ "action": "leave" })).await;
+    right.expect("match_over").await;
+
+    left.send(&json!({ "action": "join" })).await;
+    right.send(&json!({ "action": "join" })).await;
+
+    assert!(left.receives("match_found").await);
+    assert!(right.receives("match_found").await);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn leaving_the_queue_prevents_pairing() {
+    let server = start_server().await;
+
+    let mut gone = TestClient::join(server.addr).await;
+    gone.send(&json!({ "action": "leave" })).await;
+
+    let mut first = TestClient::join(server.addr).await;
+    let mut second = TestClient::join(server.addr).await;
+
+    let first_found = first.expect("match_found").await;
+    let second_found = second.expect("match_found").await;
+    assert_eq!(first_found["room_id"], second_found["room_id"]);
+    assert!(gone.receive().await.is_none());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn snapshots_acknowledge_inputs() {
+    let server = start_server().await;
+    let (room_id, mut left, _right) = start_match(server.addr).await;
+
+    left.send_move(&room_id, 1).await;
+    left.send_move(&room_id, 1).await;
+
+    loop {
+        let snapshot = left.expect("snapshot").await;
+        if snapshot["ack"] == 2 {
+            assert!(snapshot["paddles"]["left"].as_f64().unwrap() > 0.0);
+            break;
+        }
+    }
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn invalid_moves_get_the_player_kicked() {
+    let server = start_server().await;
+    let (room_id, mut left, mut right) = start_match(server.addr).await;
+
+    for _ in 0..5 {
+        left.send_move(&room_id, 5).await;
+    }
+
+    let kicked = left.expect("kicked").await;
+    assert_eq!(kicked["reason"], "too many invalid messages");
+
+    let over = right.expect("match_over").await;
+    assert_eq!(over["winner"], "right");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn unauthenticated_messages_are_ignored() {
+    let server = start_server().await;
+    let mut client = TestClient::connect(server.addr, false).await;
+
+    // The session is set up, but this goes out in the clear
+    let session = client.session.take();
+    client.send(&json!({ "action": "join" })).await;
+    client.session = session;
+
+    let mut other = TestClient::join(server.addr).await;
+    assert!(other.receive().await.is_none());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn plain_enter_cannot_change_a_session() {
+    let server = start_server().await;
+    let mut client = TestClient::connect_as(server.addr, "Anna").await;
+
+    // Someone spoofing the address, without the session
+    let session = client.session.take();
+    client
+        .send(&json!({ "action": "enter", "protocol": 1, "name": "Mallory" }))
+        .await;
+    assert!(!client.receives("entered").await);
+    client.session = session;
+
+    client
+        .send(&json!({ "action": "chat", "text": "still me" }))
+        .await;
+    assert_eq!(client.expect("chat").await["from"], "Anna");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn plain_handshakes_cannot_take_over_a_new_session() {
+    let server = start_server().await;
+    let mut client = TestClient::new(server.addr).await;
+    client.send(&enter_message()).await;
+    client.expect("entered").await;
+
+    let handshake = Handshake::new();
+    let offer = json!({ "action": "handshake", "public_key": to_hex(&handshake.public_key) });
+    client.send(&offer).await;
+    client.expect("handshake").await;
+
+    // Someone spoofing the address before the client used the session
+    let spoofed =
+        json!({ "action": "handshake", "public_key": to_hex(&Handshake::new().public_key) });
+    client.send(&spoofed).await;
+    assert!(!client.receives("handshake").await);
+
+    // The client sending its key again, as if our answer got lost
+    client.send(&offer).await;
+    let reply = client.expect("handshake").await;
+    let server_key = reply["public_key"].as_str().and_then(key_from_hex).unwrap();
+    client.session = Some(handshake.complete(Role::Client, server_key, false));
+
+    client
+        .send(&json!({ "action": "ping", "client_time": 42.0 }))
+        .await;
+    assert_eq!(client.expect("pong").await["client_time"], 42.0);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn encrypted_sessions_answer_pings() {
+    let server = start_server().await;
+    let mut client = TestClient::connect(server.addr, true).await;
+
+    client
+        .send(&json!({ "action": "ping", "client_time": 42.0 }))
+        .await;
+
+    let pong = client.expect("pong").await;
+    assert_eq!(pong["client_time"], 42.0);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn shutdown_releases_the_socket() {
+    let server = start_server().await;
+    let addr = server.addr;
+
+    server.shutdown().await;
+
+    let restarted = Server::new(&addr.to_string())
+        .await
+        .expect("The port is still in use");
+    restarted.shutdown().await;
+}
+
+#[tokio::test]
+async fn acknowledged_snapshots_arrive_as_deltas() {
+    let server = start_server().await;
+    let (_, mut left, _right) = start_match(server.addr).await;
+
+    let first = left.expect("snapshot").await;
+    let full_size = left.last_payload.len();
+    left.send(&json!({ "action": "ack", "tick": first["tick"] }))
+        .await;
+
+    loop {
+        let snapshot = left.expect("snapshot").await;
+        assert!(snapshot["paddles"]["left"].is_number());
+
+        if left.last_payload.len() < full_size {
+            break;
+        }
+    }
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn json_wire_format_sends_plain_text() {
+    let config = ServerConfig {
+        wire_format: WireFormat::Json,
+        ..ServerConfig::default()
+    };
+    let server =
+        Server::with_config(UdpTransport::bind("127.0.0.1:0").await.unwrap(), config).unwrap();
+
+    let mut client = TestClient::new(server.addr).await;
+    client.send(&json!({ "action": "enter" })).await;
+    client.expect("entered").await;
+
+    assert_eq!(client.last_payload.first(), Some(&b'{'));
+
+    server.shutdown().await;
+}
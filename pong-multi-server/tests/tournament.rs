@@ -0,0 +1,219 @@
+mod common;
+
+use std::{net::SocketAddr, time::Duration};
+
+use common::{local_addr, start_server_with, TestClient};
+use pong_multi_server::{
+    game::{
+        bracket::{Bracket, BracketFormat, Slot, Stage},
+        rating,
+    },
+    network::{
+        server::{Server, ServerConfig},
+        tournament::{no_show_winner, Entrant, Tournament},
+    },
+};
+use serde_json::{json, Value};
+
+async fn start_server_with_no_show(no_show_after: Duration) -> Server {
+    start_server_with(ServerConfig {
+        no_show_after,
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+// Skip bracket updates until the tournament is in the given state
+async fn expect_bracket(client: &mut TestClient, state: &str) -> Value {
+    loop {
+        let bracket = client.expect("bracket").await;
+        if bracket["state"] == state {
+            return bracket;
+        }
+    }
+}
+
+// A host and a second player, both registered
+async fn register_two(server: SocketAddr) -> (TestClient, TestClient) {
+    let mut host = TestClient::connect_as(server, "Ann").await;
+    host.send(&json!({ "action": "tournament_create" })).await;
+    host.expect("bracket").await;
+    host.send(&json!({ "action": "tournament_register" })).await;
+
+    let mut other = TestClient::connect_as(server, "Bob").await;
+    other
+        .send(&json!({ "action": "tournament_register" }))
+        .await;
+
+    let bracket = host.expect("bracket").await;
+    assert_eq!(bracket["entrants"].as_array().unwrap().len(), 1);
+    let bracket = host.expect("bracket").await;
+    assert_eq!(bracket["entrants"].as_array().unwrap().len(), 2);
+    assert_eq!(bracket["state"], "registering");
+
+    (host, other)
+}
+
+#[test]
+fn byes_go_to_the_top_seeds() {
+    let mut bracket = Bracket::new(BracketFormat::SingleElimination, 3);
+
+    assert_eq!(bracket.matches.len(), 3);
+    assert_eq!(bracket.players(0), [Slot::Player(0), Slot::Bye]);
+    assert_eq!(bracket.ready(), vec![1]);
+
+    assert!(bracket.report(1, 1));
+    assert_eq!(bracket.players(2), [Slot::Player(0), Slot::Player(2)]);
+    assert!(!bracket.report(1, 0));
+
+    assert!(bracket.report(2, 0));
+    assert!(bracket.is_finished());
+    assert_eq!(bracket.champion(), Some(0));
+}
+
+#[test]
+fn losing_once_drops_to_the_losers_bracket() {
+    let mut bracket = Bracket::new(BracketFormat::DoubleElimination, 4);
+
+    assert_eq!(bracket.matches.len(), 6);
+    assert_eq!(bracket.matches[5].stage, Stage::GrandFinal);
+    assert_eq!(bracket.ready(), vec![0, 1]);
+
+    // Seeds 0 and 1 win their first match, 1 then wins the winners final
+    assert!(bracket.report(0, 0));
+    assert!(bracket.report(1, 0));
+    assert_eq!(bracket.players(3), [Slot::Player(3), Slot::Player(2)]);
+    assert!(bracket.report(2, 1));
+
+    // Seed 0 comes back through the losers bracket and takes the title
+    assert!(bracket.report(3, 0));
+    assert_eq!(bracket.players(4), [Slot::Player(3), Slot::Player(0)]);
+    assert!(bracket.report(4, 1));
+    assert_eq!(bracket.players(5), [Slot::Player(1), Slot::Player(0)]);
+    assert!(!bracket.is_finished());
+
+    assert!(bracket.report(5, 1));
+    assert_eq!(bracket.champion(), Some(0));
+}
+
+#[test]
+fn entrants_are_seeded_by_rating() {
+    let mut tournament = Tournament::new(local_addr(1), BracketFormat::SingleElimination);
+
+    for (port, rating) in [(1, 990.0), (2, 1100.0), (3, 1000.0)] {
+        assert!(tournament.register(Entrant {
+            addr: local_addr(port),
+            name: format!("p{port}"),
+            rating,
+        }));
+    }
+    assert!(!tournament.register(tournament.entrants[0].clone()));
+
+    assert!(tournament.start());
+    assert_eq!(tournament.entrants[0].addr, local_addr(2));
+    assert_eq!(tournament.waiting(), vec![1]);
+    assert_eq!(tournament.players(1), Some([local_addr(3), local_addr(1)]));
+
+    let (winner, loser) = rating::update(1000.0, 1000.0);
+    assert_eq!(winner, 1016.0);
+    assert_eq!(loser, 984.0);
+}
+
+#[test]
+fn refused_matches_are_called_again() {
+    let mut tournament = Tournament::new(local_addr(1), BracketFormat::SingleElimination);
+    for port in [1, 2] {
+        tournament.register(Entrant {
+            addr: local_addr(port),
+            name: format!("p{port}"),
+            rating: 1000.0,
+        });
+    }
+    assert!(tournament.start());
+
+    tournament.starting.push(0);
+    assert!(tournament.waiting().is_empty());
+
+    tournament.room_refused(&[local_addr(1), local_addr(2)]);
+    assert_eq!(tournament.waiting(), vec![0]);
+}
+
+#[test]
+fn the_first_listed_goes_through_when_nobody_shows_up() {
+    assert_eq!(no_show_winner([false, true], [false, false]), 1);
+    assert_eq!(no_show_winner([true, false], [false, false]), 0);
+    assert_eq!(no_show_winner([false, false], [true, false]), 1);
+    assert_eq!(no_show_winner([false, false], [false, false]), 0);
+    assert_eq!(no_show_winner([false, false], [true, true]), 0);
+}
+
+#[tokio::test]
+async fn only_the_host_starts_the_tournament() {
+    let server = common::start_server().await;
+    let (mut host, mut other) = register_two(server.addr).await;
+
+    other.send(&json!({ "action": "tournament_start" })).await;
+    host.send(&json!({ "action": "tournament_start" })).await;
+
+    let bracket = expect_bracket(&mut other, "running").await;
+    assert_eq!(bracket["matches"].as_array().unwrap().len(), 1);
+    assert_eq!(bracket["matches"][0]["players"], json!(["Ann", "Bob"]));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn winners_advance_from_their_rooms() {
+    let server = common::start_server().await;
+    let (mut host, mut other) = register_two(server.addr).await;
+
+    host.send(&json!({ "action": "tournament_start" })).await;
+
+    let found = host.expect("match_found").await;
+    assert_eq!(
+        other.expect("match_found").await["room_id"],
+        found["room_id"]
+    );
+
+    other.send(&json!({ "action": "leave" })).await;
+
+    let bracket = expect_bracket(&mut host, "finished").await;
+    assert_eq!(bracket["champion"], "Ann");
+    assert_eq!(bracket["matches"][0]["winner"], 0);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn players_who_dont_show_up_lose() {
+    let server = start_server_with_no_show(Duration::from_millis(200)).await;
+    let (mut host, mut other) = register_two(server.addr).await;
+
+    // Busy in the queue when the match is called
+    other.send(&json!({ "action": "join" })).await;
+    host.send(&json!({ "action": "tournament_start" })).await;
+
+    let bracket = expect_bracket(&mut host, "finished").await;
+    assert_eq!(bracket["champion"], "Ann");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn busy_players_leave_the_match_to_the_first_listed() {
+    let server = start_server_with_no_show(Duration::from_millis(200)).await;
+    let (mut host, mut other) = register_two(server.addr).await;
+
+    // Both waiting for a doubles match that never fills
+    for client in [&mut host, &mut other] {
+        client
+            .send(&json!({ "action": "join", "mode": "doubles" }))
+            .await;
+    }
+    host.send(&json!({ "action": "tournament_start" })).await;
+
+    let bracket = expect_bracket(&mut host, "finished").await;
+    assert_eq!(bracket["champion"], bracket["matches"][0]["players"][0]);
+
+    server.shutdown().await;
+}
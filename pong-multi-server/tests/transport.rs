@@ -0,0 +1,59 @@
+use futures_util::{SinkExt, StreamExt};
+use pong_multi_server::network::{
+    server::Server,
+    transport::{memory::MemoryTransport, websocket::WebSocketTransport},
+};
+use pong_multi_shared::protocol::decode;
+use serde_json::json;
+use tokio::time::timeout;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+mod common;
+
+use common::{TestClient, TIMEOUT};
+
+#[tokio::test]
+async fn players_get_paired_over_memory_transport() {
+    let transport = MemoryTransport::new();
+    let server = Server::with_transport(transport.clone()).unwrap();
+
+    let mut left = TestClient::over_memory(&transport).handshake(true).await;
+    let mut right = TestClient::over_memory(&transport).handshake(true).await;
+
+    left.send(&json!({ "action": "join" })).await;
+    right.send(&json!({ "action": "join" })).await;
+
+    let left_found = left.expect("match_found").await;
+    let right_found = right.expect("match_found").await;
+    assert_eq!(left_found["room_id"], right_found["room_id"]);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn websocket_clients_get_answers() {
+    let server =
+        Server::with_transport(WebSocketTransport::bind("127.0.0.1:0").await.unwrap()).unwrap();
+
+    let (mut websocket, _) = connect_async(format!("ws://{}", server.addr))
+        .await
+        .unwrap();
+
+    let ping = json!({ "action": "ping", "client_time": 7.0 }).to_string();
+    websocket
+        .send(Message::Binary(ping.into_bytes().into()))
+        .await
+        .unwrap();
+
+    let reply = timeout(TIMEOUT, websocket.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let pong = decode(&reply.into_data()).unwrap();
+
+    assert_eq!(pong["action"], "pong");
+    assert_eq!(pong["client_time"], 7.0);
+
+    server.shutdown().await;
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::{enter_message, start_server, TestClient};
+use pong_multi_server::network::{
+    server::{Server, ServerConfig},
+    transport::udp::UdpTransport,
+};
+use pong_multi_shared::protocol::{BINARY_FLAG, PROTOCOL_VERSION};
+use serde_json::json;
+
+#[tokio::test]
+async fn current_clients_get_the_current_protocol() {
+    let server = start_server().await;
+    let mut client = TestClient::new(server.addr).await;
+
+    client.send(&enter_message()).await;
+    let entered = client.expect("entered").await;
+
+    assert_eq!(entered["protocol"], PROTOCOL_VERSION);
+    assert_eq!(client.last_payload[0], BINARY_FLAG | PROTOCOL_VERSION);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn clients_without_a_version_are_downgraded() {
+    let server = start_server().await;
+    let mut client = TestClient::new(server.addr).await;
+
+    client.send(&json!({ "action": "enter" })).await;
+    let entered = client.expect("entered").await;
+
+    assert_eq!(entered["protocol"], 1);
+    assert_eq!(client.last_payload[0], BINARY_FLAG | 1);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn outdated_clients_are_asked_to_update() {
+    let config = ServerConfig {
+        min_protocol: PROTOCOL_VERSION,
+        ..ServerConfig::default()
+    };
+    let server =
+        Server::with_config(UdpTransport::bind("127.0.0.1:0").await.unwrap(), config).unwrap();
+    let mut client = TestClient::new(server.addr).await;
+
+    client.send(&json!({ "action": "enter" })).await;
+    let rejected = client.expect("rejected").await;
+
+    assert!(rejected["reason"].as_str().unwrap().contains("update"));
+    assert_eq!(rejected["min_protocol"], PROTOCOL_VERSION);
+    assert_eq!(client.last_payload.first(), Some(&b'{'));
+
+    // Not registered, so there is no session to set up
+    client
+        .send(&json!({ "action": "handshake", "public_key": "ab".repeat(32) }))
+        .await;
+    assert!(client.receive().await.is_none());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn newer_clients_are_told_the_server_is_older() {
+    let server = start_server().await;
+    let mut client = TestClient::new(server.addr).await;
+
+    client
+        .send(&json!({
+            "action": "enter",
+            "protocol": PROTOCOL_VERSION + 2,
+            "min_protocol": PROTOCOL_VERSION + 1,
+        }))
+        .await;
+    let rejected = client.expect("rejected").await;
+
+    assert!(rejected["reason"].as_str().unwrap().contains("older"));
+
+    server.shutdown().await;
+}
@@ -0,0 +1,87 @@
+use serde_json::{json, Value};
+
+// Longest chat line accepted, in characters
+pub const MAX_CHAT_LENGTH: usize = 120;
+
+// Canned lines sent with a single key, no typing in the middle of a rally
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Emote {
+    GoodGame,
+    NiceShot,
+    WellPlayed,
+    Oops,
+}
+
+impl Emote {
+    pub const ALL: [Emote; 4] = [
+        Emote::GoodGame,
+        Emote::NiceShot,
+        Emote::WellPlayed,
+        Emote::Oops,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Emote::GoodGame => "gg",
+            Emote::NiceShot => "nice_shot",
+            Emote::WellPlayed => "well_played",
+            Emote::Oops => "oops",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|emote| emote.as_str() == value)
+    }
+
+    // What the players see
+    pub fn text(&self) -> &'static str {
+        match self {
+            Emote::GoodGame => "GG",
+            Emote::NiceShot => "Nice shot",
+            Emote::WellPlayed => "Well played",
+            Emote::Oops => "Oops",
+        }
+    }
+}
+
+// A chat line carries either some text or an emote
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatContent {
+    Text(String),
+    Emote(Emote),
+}
+
+impl ChatContent {
+    // None for an unknown emote, or text that is empty or too long once trimmed
+    pub fn from_json(json: &Value) -> Option<Self> {
+        if let Some(emote) = json["emote"].as_str() {
+            return Emote::parse(emote).map(ChatContent::Emote);
+        }
+
+        let text: String = json["text"]
+            .as_str()?
+            .trim()
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+        if text.is_empty() || text.chars().count() > MAX_CHAT_LENGTH {
+            return None;
+        }
+
+        Some(ChatContent::Text(text))
+    }
+
+    pub fn add_to(&self, message: &mut Value) {
+        match self {
+            ChatContent::Text(text) => message["text"] = json!(text),
+            ChatContent::Emote(emote) => message["emote"] = json!(emote.as_str()),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            ChatContent::Text(text) => text,
+            ChatContent::Emote(emote) => emote.text(),
+        }
+    }
+}
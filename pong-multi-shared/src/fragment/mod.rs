@@ -0,0 +1,97 @@
+pub mod reassembler;
+
+// First byte of a fragment, next to the secured packet kinds and '{' for plain JSON
+pub const PACKET_FRAGMENT: u8 = 0x03;
+
+// Largest datagram we send, safely under the usual 1500 bytes MTU with IP and UDP headers
+pub const MAX_PACKET_SIZE: usize = 1200;
+
+// Kind byte, message id (u32), fragment index (u16) and fragment count (u16)
+pub const FRAGMENT_HEADER_SIZE: usize = 9;
+pub const MAX_FRAGMENT_PAYLOAD: usize = MAX_PACKET_SIZE - FRAGMENT_HEADER_SIZE;
+
+// Largest message that can be split, anything bigger is refused on both ends
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+pub const MAX_FRAGMENTS: usize = MAX_MESSAGE_SIZE.div_ceil(MAX_FRAGMENT_PAYLOAD);
+
+pub fn is_fragment(packet: &[u8]) -> bool {
+    packet.first() == Some(&PACKET_FRAGMENT)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentHeader {
+    pub message_id: u32,
+    pub index: u16,
+    pub count: u16,
+}
+
+impl FragmentHeader {
+    pub fn parse(packet: &[u8]) -> Option<Self> {
+        if !is_fragment(packet) || packet.len() <= FRAGMENT_HEADER_SIZE {
+            return None;
+        }
+
+        let header = Self {
+            message_id: u32::from_be_bytes(packet[1..5].try_into().ok()?),
+            index: u16::from_be_bytes(packet[5..7].try_into().ok()?),
+            count: u16::from_be_bytes(packet[7..9].try_into().ok()?),
+        };
+
+        let valid = header.count >= 2
+            && header.index < header.count
+            && header.count as usize <= MAX_FRAGMENTS;
+        valid.then_some(header)
+    }
+
+    fn write(&self, packet: &mut Vec<u8>) {
+        packet.push(PACKET_FRAGMENT);
+        packet.extend_from_slice(&self.message_id.to_be_bytes());
+        packet.extend_from_slice(&self.index.to_be_bytes());
+        packet.extend_from_slice(&self.count.to_be_bytes());
+    }
+}
+
+// Splits packets too big for one datagram. Small packets go through untouched.
+#[derive(Debug, Default)]
+pub struct Fragmenter {
+    next_message_id: u32,
+}
+
+impl Fragmenter {
+    // The datagrams to send for a packet, empty if it is too big to send at all
+    pub fn split(&mut self, packet: &[u8]) -> Vec<Vec<u8>> {
+        if packet.len() <= MAX_PACKET_SIZE {
+            return vec![packet.to_vec()];
+        }
+
+        if packet.len() > MAX_MESSAGE_SIZE {
+            eprintln!(
+                "Dropping a {} bytes message, the limit is {}",
+                packet.len(),
+                MAX_MESSAGE_SIZE
+            );
+            return Vec::new();
+        }
+
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+
+        let chunks = packet.chunks(MAX_FRAGMENT_PAYLOAD);
+        let count = chunks.len() as u16;
+
+        chunks
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut fragment = Vec::with_capacity(FRAGMENT_HEADER_SIZE + chunk.len());
+                FragmentHeader {
+                    message_id,
+                    index: index as u16,
+                    count,
+                }
+                .write(&mut fragment);
+                fragment.extend_from_slice(chunk);
+                fragment
+            })
+            .collect()
+    }
+}
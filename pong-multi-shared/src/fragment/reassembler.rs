@@ -0,0 +1,126 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use super::{is_fragment, FragmentHeader, FRAGMENT_HEADER_SIZE};
+
+// A message missing fragments for this long won't be completed, the rest was lost
+pub const REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Bytes held for incomplete messages from one peer, the oldest go first past this
+pub const MAX_PENDING_BYTES: usize = 256 * 1024;
+
+struct Partial {
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+    bytes: usize,
+    started: Instant,
+}
+
+// Puts fragmented messages from one peer back together.
+// Fragments may come in any order, duplicates are ignored.
+#[derive(Default)]
+pub struct Reassembler {
+    partials: HashMap<u32, Partial>,
+    pending_bytes: usize,
+}
+
+impl std::fmt::Debug for Reassembler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reassembler")
+            .field("partials", &self.partials.len())
+            .field("pending_bytes", &self.pending_bytes)
+            .finish()
+    }
+}
+
+impl Reassembler {
+    pub fn is_empty(&self) -> bool {
+        self.partials.is_empty()
+    }
+
+    pub fn pending_bytes(&self) -> usize {
+        self.pending_bytes
+    }
+
+    // The whole message once its last fragment arrives. Packets that aren't
+    // fragments come straight back, invalid fragments are dropped.
+    pub fn receive(&mut self, now: Instant, packet: Vec<u8>) -> Option<Vec<u8>> {
+        if !is_fragment(&packet) {
+            return Some(packet);
+        }
+
+        self.expire(now);
+
+        let header = FragmentHeader::parse(&packet)?;
+        let chunk = &packet[FRAGMENT_HEADER_SIZE..];
+
+        self.make_room(chunk.len());
+
+        let partial = self
+            .partials
+            .entry(header.message_id)
+            .or_insert_with(|| Partial {
+                fragments: vec![None; header.count as usize],
+                received: 0,
+                bytes: 0,
+                started: now,
+            });
+
+        // A different count means an old message id came back around, or a forgery
+        if partial.fragments.len() != header.count as usize {
+            return None;
+        }
+
+        let slot = partial.fragments.get_mut(header.index as usize)?;
+        if slot.is_some() {
+            return None;
+        }
+
+        *slot = Some(chunk.to_vec());
+        partial.received += 1;
+        partial.bytes += chunk.len();
+        self.pending_bytes += chunk.len();
+
+        if partial.received < partial.fragments.len() {
+            return None;
+        }
+
+        let partial = self.partials.remove(&header.message_id)?;
+        self.pending_bytes -= partial.bytes;
+
+        Some(partial.fragments.into_iter().flatten().flatten().collect())
+    }
+
+    // Forget messages that won't be completed anymore
+    pub fn expire(&mut self, now: Instant) {
+        let pending_bytes = &mut self.pending_bytes;
+
+        self.partials.retain(|_, partial| {
+            let alive = now.saturating_duration_since(partial.started) < REASSEMBLY_TIMEOUT;
+            if !alive {
+                *pending_bytes -= partial.bytes;
+            }
+            alive
+        });
+    }
+
+    // Drop the oldest incomplete messages until `size` more bytes fit
+    fn make_room(&mut self, size: usize) {
+        while self.pending_bytes + size > MAX_PENDING_BYTES {
+            let Some(oldest) = self
+                .partials
+                .iter()
+                .min_by_key(|(_, partial)| partial.started)
+                .map(|(id, _)| *id)
+            else {
+                return;
+            };
+
+            if let Some(partial) = self.partials.remove(&oldest) {
+                self.pending_bytes -= partial.bytes;
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+use rand::Rng;
+
+use super::{
+    ball::{Ball, BALL_SIZE},
+    formation::Seat,
+    paddle::{PADDLE_SPEED, PADDLE_WIDTH},
+    state::GameState,
+    FIELD_HEIGHT, TICK_DT,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+// What makes an AI beatable
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AiSettings {
+    // How many ticks late the AI sees the ball
+    pub reaction_ticks: usize,
+
+    // The AI aims this far off where the ball will land at most, picked
+    // again every time the ball comes back
+    pub prediction_error: f32,
+
+    // Fraction of the paddle speed the AI moves at
+    pub max_speed: f32,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.as_str() == value)
+    }
+
+    pub fn settings(&self) -> AiSettings {
+        match self {
+            Difficulty::Easy => AiSettings {
+                reaction_ticks: 18,
+                prediction_error: 130.0,
+                max_speed: 0.6,
+            },
+            Difficulty::Normal => AiSettings {
+                reaction_ticks: 10,
+                prediction_error: 90.0,
+                max_speed: 0.8,
+            },
+            Difficulty::Hard => AiSettings {
+                reaction_ticks: 4,
+                prediction_error: 40.0,
+                max_speed: 1.0,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+            .ok_or_else(|| format!("Unknown difficulty {value}, expected easy, normal or hard"))
+    }
+}
+
+// Steers one paddle with the same inputs a player would send, one per tick
+#[derive(Debug, Clone)]
+pub struct AiController {
+    pub settings: AiSettings,
+
+    // The last positions of the ball, the AI reacts to the oldest one
+    seen: VecDeque<Ball>,
+
+    // Aim offset for the current rally
+    error: f32,
+    approaching: bool,
+
+    // Builds up by max_speed every tick, the paddle moves when it reaches 1
+    speed_budget: f32,
+}
+
+impl AiController {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self::with_settings(difficulty.settings())
+    }
+
+    pub fn with_settings(settings: AiSettings) -> Self {
+        Self {
+            settings,
+            seen: VecDeque::new(),
+            error: 0.0,
+            approaching: false,
+            speed_budget: 0.0,
+        }
+    }
+
+    // Direction to move the paddle of the seat this tick
+    pub fn direction(&mut self, state: &GameState, seat: Seat, rng: &mut impl Rng) -> i8 {
+        self.seen.push_back(state.ball);
+        while self.seen.len() > self.settings.reaction_ticks + 1 {
+            self.seen.pop_front();
+        }
+        let ball = self.seen[0];
+        let paddle = state.paddle(seat);
+
+        let approaching = ball.moving_towards(seat.side);
+        if approaching && !self.approaching {
+            let error = self.settings.prediction_error;
+            self.error = if error > 0.0 {
+                rng.random_range(-error..=error)
+            } else {
+                0.0
+            };
+        }
+        self.approaching = approaching;
+
+        // Wait in the middle while the ball goes the other way
+        let target = if approaching {
+            landing_y(&ball, paddle.x - seat.side.sign() * PADDLE_WIDTH / 2.0) + self.error
+        } else {
+            0.0
+        };
+
+        self.speed_budget = (self.speed_budget + self.settings.max_speed).min(1.0);
+
+        let distance = target - paddle.y;
+        if distance.abs() < PADDLE_SPEED * TICK_DT || self.speed_budget < 1.0 {
+            return 0;
+        }
+        self.speed_budget -= 1.0;
+
+        if distance > 0.0 {
+            1
+        } else {
+            -1
+        }
+    }
+}
+
+// Height at which the ball crosses `x`, bouncing off the top and bottom walls
+pub fn landing_y(ball: &Ball, x: f32) -> f32 {
+    if ball.vx == 0.0 {
+        return ball.y;
+    }
+
+    let time = ((x - ball.x) / ball.vx).max(0.0);
+    let y = ball.y + ball.vy * time;
+
+    // Unfold the bounces, the ball zigzags between the two limits
+    let limit = FIELD_HEIGHT / 2.0 - BALL_SIZE / 2.0;
+    let folded = (y + limit).rem_euclid(4.0 * limit);
+    if folded > 2.0 * limit {
+        3.0 * limit - folded
+    } else {
+        folded - limit
+    }
+}
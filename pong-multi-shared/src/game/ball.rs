@@ -0,0 +1,118 @@
+use super::{
+    paddle::{Paddle, PADDLE_HEIGHT, PADDLE_WIDTH},
+    Side, FIELD_HEIGHT,
+};
+
+pub const BALL_SIZE: f32 = 32.0;
+
+// Units per second
+pub const BALL_SPEED: f32 = 420.0;
+pub const BALL_MAX_SPEED: f32 = 900.0;
+
+// Speed gained on every paddle hit
+const BALL_ACCELERATION: f32 = 1.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ball {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+}
+
+impl Ball {
+    // Put the ball back in the middle, heading towards the given side
+    pub fn serve(towards: Side) -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            vx: towards.sign() * BALL_SPEED * 0.8,
+            vy: BALL_SPEED * 0.6,
+        }
+    }
+
+    // Move the ball and bounce it off the top and bottom walls
+    pub fn advance(&mut self, dt: f32) {
+        self.x += self.vx * dt;
+        self.y += self.vy * dt;
+
+        let limit = FIELD_HEIGHT / 2.0 - BALL_SIZE / 2.0;
+        if self.y > limit {
+            self.y = limit;
+            self.vy = -self.vy.abs();
+        } else if self.y < -limit {
+            self.y = -limit;
+            self.vy = self.vy.abs();
+        }
+    }
+
+    pub fn overlaps(&self, paddle: &Paddle) -> bool {
+        (self.x - paddle.x).abs() <= (BALL_SIZE + PADDLE_WIDTH) / 2.0
+            && (self.y - paddle.y).abs() <= (BALL_SIZE + PADDLE_HEIGHT) / 2.0
+    }
+
+    // The ball went past the front face of the paddle without touching it
+    pub fn behind(&self, paddle: &Paddle) -> bool {
+        let front = paddle.x.abs() - (BALL_SIZE + PADDLE_WIDTH) / 2.0;
+
+        self.x.abs() > front && self.x.signum() == paddle.x.signum() && !self.overlaps(paddle)
+    }
+
+    pub fn moving_towards(&self, side: Side) -> bool {
+        self.vx * side.sign() > 0.0
+    }
+
+    // Send the ball back, steering it by where it hit the paddle
+    pub fn bounce_off(&mut self, paddle: &Paddle) {
+        let speed = ((self.vx * self.vx + self.vy * self.vy).sqrt() * BALL_ACCELERATION)
+            .min(BALL_MAX_SPEED);
+        let offset = ((self.y - paddle.y) / (PADDLE_HEIGHT / 2.0)).clamp(-1.0, 1.0);
+        let angle = offset * std::f32::consts::FRAC_PI_4;
+
+        let direction = if paddle.x > 0.0 { -1.0 } else { 1.0 };
+        self.vx = direction * speed * angle.cos();
+        self.vy = speed * angle.sin();
+
+        // Push the ball out of the paddle so it can't hit twice
+        self.x = paddle.x + direction * (BALL_SIZE + PADDLE_WIDTH) / 2.0;
+    }
+
+    // Bounce off a box turned by `angle` around its center, pushing the ball out
+    // along the side it went in the least. Returns whether it touched the box.
+    pub fn bounce_off_box(
+        &mut self,
+        center: (f32, f32),
+        half_size: (f32, f32),
+        angle: f32,
+    ) -> bool {
+        let (sin, cos) = angle.sin_cos();
+        let rotate = |x: f32, y: f32| (x * cos + y * sin, -x * sin + y * cos);
+        let unrotate = |x: f32, y: f32| (x * cos - y * sin, x * sin + y * cos);
+
+        let (mut x, mut y) = rotate(self.x - center.0, self.y - center.1);
+        let (mut vx, mut vy) = rotate(self.vx, self.vy);
+
+        let reach = (half_size.0 + BALL_SIZE / 2.0, half_size.1 + BALL_SIZE / 2.0);
+        let depth = (reach.0 - x.abs(), reach.1 - y.abs());
+        if depth.0 <= 0.0 || depth.1 <= 0.0 {
+            return false;
+        }
+
+        if depth.0 < depth.1 {
+            let direction = if x >= 0.0 { 1.0 } else { -1.0 };
+            vx = direction * vx.abs();
+            x = direction * reach.0;
+        } else {
+            let direction = if y >= 0.0 { 1.0 } else { -1.0 };
+            vy = direction * vy.abs();
+            y = direction * reach.1;
+        }
+
+        let (x, y) = unrotate(x, y);
+        (self.vx, self.vy) = unrotate(vx, vy);
+        self.x = center.0 + x;
+        self.y = center.1 + y;
+
+        true
+    }
+}
@@ -0,0 +1,144 @@
+use std::{fmt, str::FromStr};
+
+use super::{
+    ball::BALL_SIZE,
+    paddle::{PADDLE_WIDTH, PADDLE_X},
+    Side,
+};
+
+// Back paddle on the goal line, front paddle a third of the way to the center
+pub const DEFAULT_DOUBLES_DEPTHS: [f32; 2] = [PADDLE_X, 400.0];
+
+// Closer to the center than this and the front paddles of both teams would touch
+const MIN_DEPTH: f32 = PADDLE_WIDTH + BALL_SIZE;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    #[default]
+    Singles,
+    Doubles,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [GameMode::Singles, GameMode::Doubles];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameMode::Singles => "singles",
+            GameMode::Doubles => "doubles",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "singles" => Some(GameMode::Singles),
+            "doubles" => Some(GameMode::Doubles),
+            _ => None,
+        }
+    }
+
+    pub fn team_size(&self) -> usize {
+        match self {
+            GameMode::Singles => 1,
+            GameMode::Doubles => 2,
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+            .ok_or_else(|| format!("Unknown mode {value}, expected singles or doubles"))
+    }
+}
+
+// Where a player stands: their team and their paddle within it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Seat {
+    pub side: Side,
+    pub slot: usize,
+}
+
+impl Seat {
+    pub fn new(side: Side, slot: usize) -> Self {
+        Self { side, slot }
+    }
+
+    // Name of the paddle in messages, "left" and "right" for the first slot
+    // so singles look the same as before doubles existed
+    pub fn key(&self) -> String {
+        match self.slot {
+            0 => self.side.as_str().to_string(),
+            slot => format!("{}_{}", self.side.as_str(), slot + 1),
+        }
+    }
+
+    pub fn parse_key(key: &str) -> Option<Self> {
+        match key.split_once('_') {
+            None => Some(Self::new(Side::parse(key)?, 0)),
+            Some((side, number)) => {
+                let slot = number.parse::<usize>().ok()?.checked_sub(1)?;
+                (slot > 0).then_some(Self::new(Side::parse(side)?, slot))
+            }
+        }
+    }
+}
+
+// Distance from the center line of every paddle in a team, one per slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formation {
+    pub mode: GameMode,
+    pub depths: Vec<f32>,
+}
+
+impl Default for Formation {
+    fn default() -> Self {
+        Self::singles()
+    }
+}
+
+impl Formation {
+    pub fn singles() -> Self {
+        Self {
+            mode: GameMode::Singles,
+            depths: vec![PADDLE_X],
+        }
+    }
+
+    pub fn doubles(depths: [f32; 2]) -> Self {
+        Self {
+            mode: GameMode::Doubles,
+            depths: depths
+                .iter()
+                .map(|depth| depth.clamp(MIN_DEPTH, PADDLE_X))
+                .collect(),
+        }
+    }
+
+    pub fn team_size(&self) -> usize {
+        self.depths.len()
+    }
+
+    // Every seat, the left team first
+    pub fn seats(&self) -> impl Iterator<Item = Seat> + '_ {
+        [Side::Left, Side::Right]
+            .into_iter()
+            .flat_map(|side| (0..self.team_size()).map(move |slot| Seat::new(side, slot)))
+    }
+
+    pub fn index(&self, seat: Seat) -> usize {
+        seat.side.index() * self.team_size() + seat.slot
+    }
+
+    pub fn x(&self, seat: Seat) -> f32 {
+        seat.side.sign() * self.depths[seat.slot]
+    }
+}
@@ -0,0 +1,303 @@
+use serde_json::{json, Value};
+
+use super::{
+    ball::{Ball, BALL_SIZE},
+    FIELD_HEIGHT, FIELD_WIDTH, TICK_DT,
+};
+
+// Sprites a map can use as its background
+pub const BACKGROUNDS: [&str; 3] = ["background_blue", "background_brown", "background_green"];
+
+// Thickness of the rotating blocks, their length comes from the map
+pub const ROTATING_BLOCK_WIDTH: f32 = 24.0;
+
+// The ball falls into a hole once its center is this close
+pub const PORTAL_RADIUS: f32 = 24.0;
+
+// Goals smaller than a paddle couldn't be defended anyway
+pub const MIN_GOAL_SIZE: f32 = 128.0;
+
+// Keeps maps from other servers from flooding the field
+const MAX_OBSTACLES: usize = 32;
+
+// Shipped with the game, every client knows them
+const BUILT_IN: [&str; 4] = [
+    include_str!("../../maps/classic.json"),
+    include_str!("../../maps/pillars.json"),
+    include_str!("../../maps/windmills.json"),
+    include_str!("../../maps/wormholes.json"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Block {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+// Spins around its center, `speed` in radians per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RotatingBlock {
+    pub x: f32,
+    pub y: f32,
+    pub length: f32,
+    pub speed: f32,
+}
+
+impl RotatingBlock {
+    // Taken from the tick so the server and every client agree on it
+    pub fn angle_at(&self, tick: f64) -> f32 {
+        (self.speed as f64 * tick * TICK_DT as f64) as f32
+    }
+}
+
+// A ball falling in the hole at `from` comes out of the one at `to`, the other
+// way doesn't work
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Portal {
+    pub from: Point,
+    pub to: Point,
+}
+
+// Everything on the field besides the paddles and the balls. Maps are JSON
+// files, the server sends the one picked to the players.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Map {
+    pub name: String,
+    pub background: String,
+
+    // Height of the opening in each back wall, the ball bounces off the rest
+    pub goal_size: f32,
+    pub blocks: Vec<Block>,
+    pub rotating_blocks: Vec<RotatingBlock>,
+    pub portals: Vec<Portal>,
+
+    // Where the ball is served from, in turns
+    pub spawns: Vec<Point>,
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl Map {
+    // The empty field, same as before maps existed
+    pub fn classic() -> Self {
+        Self {
+            name: "classic".to_string(),
+            background: BACKGROUNDS[0].to_string(),
+            goal_size: FIELD_HEIGHT,
+            blocks: Vec::new(),
+            rotating_blocks: Vec::new(),
+            portals: Vec::new(),
+            spawns: vec![Point { x: 0.0, y: 0.0 }],
+        }
+    }
+
+    pub fn built_in() -> Vec<Map> {
+        BUILT_IN
+            .iter()
+            .map(|text| {
+                let json = serde_json::from_str(text).expect("Built-in map isn't valid JSON");
+                Map::from_json(&json).expect("Built-in map is invalid")
+            })
+            .collect()
+    }
+
+    pub fn by_name(name: &str) -> Option<Map> {
+        Self::built_in().into_iter().find(|map| map.name == name)
+    }
+
+    pub fn from_json(json: &Value) -> Result<Self, String> {
+        let name = json["name"]
+            .as_str()
+            .filter(|name| !name.is_empty())
+            .ok_or("Missing name")?;
+
+        let background = json["background"].as_str().unwrap_or(BACKGROUNDS[0]);
+        if !BACKGROUNDS.contains(&background) {
+            return Err(format!("Unknown background {background}"));
+        }
+
+        let goal_size = match &json["goal_size"] {
+            Value::Null => FIELD_HEIGHT,
+            size => number(size, "goal_size")?,
+        };
+        if !(MIN_GOAL_SIZE..=FIELD_HEIGHT).contains(&goal_size) {
+            return Err(format!(
+                "goal_size must be between {MIN_GOAL_SIZE} and {FIELD_HEIGHT}"
+            ));
+        }
+
+        let blocks = list(json, "blocks", |block| {
+            let block = Block {
+                x: number(&block["x"], "block x")?,
+                y: number(&block["y"], "block y")?,
+                width: number(&block["width"], "block width")?,
+                height: number(&block["height"], "block height")?,
+            };
+            if block.width <= 0.0 || block.height <= 0.0 {
+                return Err("Blocks need a size".to_string());
+            }
+            inside(block.x, block.y, "block")?;
+            Ok(block)
+        })?;
+
+        let rotating_blocks = list(json, "rotating_blocks", |block| {
+            let block = RotatingBlock {
+                x: number(&block["x"], "rotating block x")?,
+                y: number(&block["y"], "rotating block y")?,
+                length: number(&block["length"], "rotating block length")?,
+                speed: number(&block["speed"], "rotating block speed")?,
+            };
+            if block.length <= 0.0 {
+                return Err("Rotating blocks need a length".to_string());
+            }
+            inside(block.x, block.y, "rotating block")?;
+            Ok(block)
+        })?;
+
+        let portals = list(json, "portals", |portal| {
+            Ok(Portal {
+                from: point(&portal["from"], "portal")?,
+                to: point(&portal["to"], "portal")?,
+            })
+        })?;
+
+        let spawns = list(json, "spawns", |spawn| point(spawn, "spawn"))?;
+        let spawns = if spawns.is_empty() {
+            Self::classic().spawns
+        } else {
+            spawns
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            background: background.to_string(),
+            goal_size,
+            blocks,
+            rotating_blocks,
+            portals,
+            spawns,
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let point = |point: &Point| json!({ "x": point.x, "y": point.y });
+
+        json!({
+            "name": self.name,
+            "background": self.background,
+            "goal_size": self.goal_size,
+            "blocks": self.blocks.iter().map(|block| json!({
+                "x": block.x,
+                "y": block.y,
+                "width": block.width,
+                "height": block.height,
+            })).collect::<Vec<_>>(),
+            "rotating_blocks": self.rotating_blocks.iter().map(|block| json!({
+                "x": block.x,
+                "y": block.y,
+                "length": block.length,
+                "speed": block.speed,
+            })).collect::<Vec<_>>(),
+            "portals": self.portals.iter().map(|portal| json!({
+                "from": point(&portal.from),
+                "to": point(&portal.to),
+            })).collect::<Vec<_>>(),
+            "spawns": self.spawns.iter().map(point).collect::<Vec<_>>(),
+        })
+    }
+
+    // Serve points are used in turns, one per point played
+    pub fn spawn(&self, points_played: u32) -> Point {
+        self.spawns[points_played as usize % self.spawns.len()]
+    }
+
+    // Bounce the ball off the obstacles and the back walls, and move it through
+    // the portals
+    pub fn collide(&self, ball: &mut Ball, tick: u64) {
+        for block in &self.blocks {
+            ball.bounce_off_box(
+                (block.x, block.y),
+                (block.width / 2.0, block.height / 2.0),
+                0.0,
+            );
+        }
+
+        for block in &self.rotating_blocks {
+            ball.bounce_off_box(
+                (block.x, block.y),
+                (block.length / 2.0, ROTATING_BLOCK_WIDTH / 2.0),
+                block.angle_at(tick as f64),
+            );
+        }
+
+        let portal = self
+            .portals
+            .iter()
+            .find(|portal| (ball.x - portal.from.x).hypot(ball.y - portal.from.y) <= PORTAL_RADIUS);
+        if let Some(portal) = portal {
+            ball.x = portal.to.x;
+            ball.y = portal.to.y;
+        }
+
+        let edge = FIELD_WIDTH / 2.0 - BALL_SIZE / 2.0;
+        if ball.x.abs() > edge && ball.y.abs() > self.goal_size / 2.0 && ball.vx * ball.x > 0.0 {
+            ball.vx = -ball.vx;
+            ball.x = ball.x.signum() * edge;
+        }
+    }
+}
+
+fn number(value: &Value, what: &str) -> Result<f32, String> {
+    value
+        .as_f64()
+        .map(|value| value as f32)
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| format!("Invalid {what}"))
+}
+
+fn point(value: &Value, what: &str) -> Result<Point, String> {
+    let point = Point {
+        x: number(&value["x"], what)?,
+        y: number(&value["y"], what)?,
+    };
+    inside(point.x, point.y, what)?;
+    Ok(point)
+}
+
+fn inside(x: f32, y: f32, what: &str) -> Result<(), String> {
+    if x.abs() > FIELD_WIDTH / 2.0 || y.abs() > FIELD_HEIGHT / 2.0 {
+        return Err(format!("A {what} is outside the field"));
+    }
+    Ok(())
+}
+
+// Missing lists are empty
+fn list<T>(
+    json: &Value,
+    key: &str,
+    parse: impl Fn(&Value) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    let items = match &json[key] {
+        Value::Null => return Ok(Vec::new()),
+        Value::Array(items) => items,
+        _ => return Err(format!("{key} must be a list")),
+    };
+
+    if items.len() > MAX_OBSTACLES {
+        return Err(format!("Too many {key}, at most {MAX_OBSTACLES}"));
+    }
+
+    items.iter().map(parse).collect()
+}
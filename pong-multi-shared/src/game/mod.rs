@@ -0,0 +1,60 @@
+pub mod ai;
+pub mod ball;
+pub mod formation;
+pub mod map;
+pub mod paddle;
+pub mod power_up;
+pub mod state;
+
+// Fixed simulation rate, used by the server room loop and the client prediction
+pub const TICK_RATE: u32 = 60;
+pub const TICK_DT: f32 = 1.0 / TICK_RATE as f32;
+
+// The playfield is centered on the origin
+pub const FIELD_WIDTH: f32 = 1280.0;
+pub const FIELD_HEIGHT: f32 = 720.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "left" => Some(Side::Left),
+            "right" => Some(Side::Right),
+            _ => None,
+        }
+    }
+
+    pub fn opponent(&self) -> Self {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            Side::Left => 0,
+            Side::Right => 1,
+        }
+    }
+
+    // -1.0 for the left half of the field, 1.0 for the right half
+    pub fn sign(&self) -> f32 {
+        match self {
+            Side::Left => -1.0,
+            Side::Right => 1.0,
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use super::{Side, FIELD_HEIGHT, FIELD_WIDTH, TICK_DT};
+
+pub const PADDLE_WIDTH: f32 = 32.0;
+pub const PADDLE_HEIGHT: f32 = 128.0;
+
+// Units per second
+pub const PADDLE_SPEED: f32 = 600.0;
+
+// Distance between the center of the field and the center of a paddle
+pub const PADDLE_X: f32 = FIELD_WIDTH / 2.0 - PADDLE_WIDTH;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Paddle {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Paddle {
+    pub fn new(side: Side) -> Self {
+        Self {
+            x: side.sign() * PADDLE_X,
+            y: 0.0,
+        }
+    }
+
+    pub fn apply_input(&mut self, direction: i8) {
+        self.y = step_paddle(self.y, direction);
+    }
+}
+
+// Move a paddle by one tick of input. Both the server and the client prediction
+// go through here so replayed inputs land on exactly the same position.
+pub fn step_paddle(y: f32, direction: i8) -> f32 {
+    let limit = FIELD_HEIGHT / 2.0 - PADDLE_HEIGHT / 2.0;
+
+    (y + direction.signum() as f32 * PADDLE_SPEED * TICK_DT).clamp(-limit, limit)
+}
@@ -0,0 +1,226 @@
+use std::{fmt, str::FromStr};
+
+use super::{
+    ball::{Ball, BALL_SIZE},
+    FIELD_HEIGHT, TICK_RATE,
+};
+
+// Size of a power-up waiting on the field, the ball picks it up on contact
+pub const POWER_UP_SIZE: f32 = 48.0;
+
+// How long the effects last
+pub const LASER_STUN_TICKS: u64 = TICK_RATE as u64 * 3 / 2;
+pub const STAR_TICKS: u64 = TICK_RATE as u64 * 8;
+pub const KEY_TICKS: u64 = TICK_RATE as u64 * 5;
+
+// The locked blocks stand on the center line against the top and bottom walls.
+// Opened with a key, the ball can take the shortcut along the walls.
+pub const LOCKED_BLOCK_WIDTH: f32 = 32.0;
+pub const LOCKED_BLOCK_HEIGHT: f32 = 160.0;
+pub const LOCKED_BLOCK_Y: f32 = FIELD_HEIGHT / 2.0 - LOCKED_BLOCK_HEIGHT / 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerUpKind {
+    // Stuns the paddles of the other team
+    Laser,
+    // A second ball until it scores or runs out
+    Star,
+    // Opens the locked blocks
+    Key,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 3] = [PowerUpKind::Laser, PowerUpKind::Star, PowerUpKind::Key];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PowerUpKind::Laser => "laser",
+            PowerUpKind::Star => "star",
+            PowerUpKind::Key => "key",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    // Number sent in snapshots, they only carry numbers
+    pub fn id(&self) -> u64 {
+        match self {
+            PowerUpKind::Laser => 1,
+            PowerUpKind::Star => 2,
+            PowerUpKind::Key => 3,
+        }
+    }
+
+    pub fn from_id(id: u64) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.id() == id)
+    }
+
+    fn index(&self) -> usize {
+        self.id() as usize - 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl PowerUp {
+    pub fn touches(&self, ball: &Ball) -> bool {
+        (ball.x - self.x).abs() <= (BALL_SIZE + POWER_UP_SIZE) / 2.0
+            && (ball.y - self.y).abs() <= (BALL_SIZE + POWER_UP_SIZE) / 2.0
+    }
+}
+
+// The ball given by a star, it scores like the main one but isn't served again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExtraBall {
+    pub ball: Ball,
+    pub ticks_left: u64,
+}
+
+// The two locked blocks, open for a while after a key is picked up
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Lock {
+    pub open_ticks: u64,
+}
+
+impl Lock {
+    pub fn is_locked(&self) -> bool {
+        self.open_ticks == 0
+    }
+
+    pub fn bounce(&self, ball: &mut Ball) {
+        if !self.is_locked() {
+            return;
+        }
+
+        for block_y in [LOCKED_BLOCK_Y, -LOCKED_BLOCK_Y] {
+            ball.bounce_off_box(
+                (0.0, block_y),
+                (LOCKED_BLOCK_WIDTH / 2.0, LOCKED_BLOCK_HEIGHT / 2.0),
+                0.0,
+            );
+        }
+    }
+}
+
+// What can spawn in a mode and how often. Weights are relative, a kind with
+// weight 0 never shows up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnTable {
+    pub interval_ticks: u64,
+    pub weights: [u32; 3],
+}
+
+impl SpawnTable {
+    pub const fn off() -> Self {
+        Self {
+            interval_ticks: 0,
+            weights: [0; 3],
+        }
+    }
+
+    pub fn singles() -> Self {
+        Self {
+            interval_ticks: TICK_RATE as u64 * 10,
+            weights: [1, 1, 0],
+        }
+    }
+
+    pub fn doubles() -> Self {
+        Self {
+            interval_ticks: TICK_RATE as u64 * 7,
+            weights: [2, 2, 1],
+        }
+    }
+
+    pub fn weight(&self, kind: PowerUpKind) -> u32 {
+        self.weights[kind.index()]
+    }
+
+    pub fn is_off(&self) -> bool {
+        self.interval_ticks == 0 || self.weights.iter().all(|weight| *weight == 0)
+    }
+
+    // Keys only make sense with something to open, the locked blocks come with them
+    pub fn has_locks(&self) -> bool {
+        !self.is_off() && self.weight(PowerUpKind::Key) > 0
+    }
+
+    // Pick a kind from a roll in 0..total weight
+    pub fn pick(&self, roll: u32) -> Option<PowerUpKind> {
+        let mut roll = roll;
+
+        for kind in PowerUpKind::ALL {
+            let weight = self.weight(kind);
+            if roll < weight {
+                return Some(kind);
+            }
+            roll -= weight;
+        }
+
+        None
+    }
+
+    pub fn total_weight(&self) -> u32 {
+        self.weights.iter().sum()
+    }
+}
+
+// "off", or the seconds between spawns followed by the weights,
+// like "every=10,laser=1,star=1,key=0". Kinds left out don't spawn.
+impl FromStr for SpawnTable {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "off" {
+            return Ok(Self::off());
+        }
+
+        let mut table = Self::off();
+        for entry in value.split(',') {
+            let (key, number) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, got {entry}"))?;
+            let number: u32 = number
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid number in {entry}"))?;
+
+            match key.trim() {
+                "every" => table.interval_ticks = number as u64 * TICK_RATE as u64,
+                kind => {
+                    let kind = PowerUpKind::parse(kind)
+                        .ok_or_else(|| format!("Unknown power-up {kind}"))?;
+                    table.weights[kind.index()] = number;
+                }
+            }
+        }
+
+        if table.interval_ticks == 0 {
+            return Err("Missing every=<seconds>".to_string());
+        }
+
+        Ok(table)
+    }
+}
+
+impl fmt::Display for SpawnTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_off() {
+            return write!(f, "off");
+        }
+
+        write!(f, "every={}", self.interval_ticks / TICK_RATE as u64)?;
+        for kind in PowerUpKind::ALL {
+            write!(f, ",{}={}", kind.as_str(), self.weight(kind))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use super::{
+    ball::{Ball, BALL_SIZE},
+    formation::{Formation, Seat},
+    map::Map,
+    paddle::Paddle,
+    power_up::{ExtraBall, Lock, PowerUp, PowerUpKind, KEY_TICKS, LASER_STUN_TICKS, STAR_TICKS},
+    Side, FIELD_WIDTH, TICK_DT,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameState {
+    pub tick: u64,
+    pub ball: Ball,
+    pub formation: Formation,
+    pub map: Map,
+
+    // One per seat, in the order of Formation::seats
+    pub paddles: Vec<Paddle>,
+    pub score: [u32; 2],
+
+    // Whoever touched a ball last, power-ups go to them
+    pub last_hitter: Option<Seat>,
+
+    // Waiting on the field to be picked up, one at a time
+    pub power_up: Option<PowerUp>,
+    pub extra_ball: Option<ExtraBall>,
+
+    // Ticks left before the paddles of each team can move again
+    pub stunned: [u64; 2],
+
+    // Only there when keys can spawn
+    pub lock: Option<Lock>,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameState {
+    pub fn new() -> Self {
+        Self::with_formation(Formation::singles())
+    }
+
+    pub fn with_formation(formation: Formation) -> Self {
+        let paddles = formation
+            .seats()
+            .map(|seat| Paddle {
+                x: formation.x(seat),
+                y: 0.0,
+            })
+            .collect();
+
+        Self {
+            tick: 0,
+            ball: Ball::serve(Side::Left),
+            formation,
+            map: Map::classic(),
+            paddles,
+            score: [0, 0],
+            last_hitter: None,
+            power_up: None,
+            extra_ball: None,
+            stunned: [0, 0],
+            lock: None,
+        }
+    }
+
+    // Play on another map, the ball is served again from its first spawn point
+    pub fn set_map(&mut self, map: Map) {
+        self.map = map;
+        self.serve(Side::Left);
+    }
+
+    fn serve(&mut self, towards: Side) {
+        let spawn = self.map.spawn(self.score.iter().sum());
+
+        self.ball = Ball {
+            x: spawn.x,
+            y: spawn.y,
+            ..Ball::serve(towards)
+        };
+        self.last_hitter = None;
+    }
+
+    pub fn is_stunned(&self, side: Side) -> bool {
+        self.stunned[side.index()] > 0
+    }
+
+    pub fn paddle(&self, seat: Seat) -> &Paddle {
+        &self.paddles[self.formation.index(seat)]
+    }
+
+    pub fn paddle_mut(&mut self, seat: Seat) -> &mut Paddle {
+        let index = self.formation.index(seat);
+        &mut self.paddles[index]
+    }
+
+    // Advance the ball by one tick, returns the side that scored if any
+    pub fn step(&mut self) -> Option<Side> {
+        self.advance();
+        self.collect_power_up();
+        self.score_point()
+    }
+
+    // Move the balls and bounce them off the paddles and the locked blocks
+    pub fn advance(&mut self) {
+        self.tick += 1;
+        self.run_timers();
+
+        self.ball.advance(TICK_DT);
+        if let Some(seat) = self.bounce(BallId::Main) {
+            self.last_hitter = Some(seat);
+        }
+
+        if let Some(extra) = &mut self.extra_ball {
+            extra.ball.advance(TICK_DT);
+            if let Some(seat) = self.bounce(BallId::Extra) {
+                self.last_hitter = Some(seat);
+            }
+        }
+    }
+
+    fn run_timers(&mut self) {
+        for stunned in &mut self.stunned {
+            *stunned = stunned.saturating_sub(1);
+        }
+
+        if let Some(lock) = &mut self.lock {
+            lock.open_ticks = lock.open_ticks.saturating_sub(1);
+        }
+
+        if let Some(extra) = &mut self.extra_ball {
+            extra.ticks_left = extra.ticks_left.saturating_sub(1);
+            if extra.ticks_left == 0 {
+                self.extra_ball = None;
+            }
+        }
+    }
+
+    // Returns the seat of the paddle the ball bounced off, if any
+    fn bounce(&mut self, id: BallId) -> Option<Seat> {
+        let ball = match id {
+            BallId::Main => &mut self.ball,
+            BallId::Extra => &mut self.extra_ball.as_mut()?.ball,
+        };
+
+        if let Some(lock) = &self.lock {
+            lock.bounce(ball);
+        }
+        self.map.collide(ball, self.tick);
+
+        let mut hitter = None;
+        for (seat, paddle) in self.formation.seats().zip(&self.paddles) {
+            if ball.moving_towards(seat.side) && ball.overlaps(paddle) {
+                ball.bounce_off(paddle);
+                hitter = Some(seat);
+            }
+        }
+
+        hitter
+    }
+
+    // A ball touched the power-up on the field, the last hitter gets its effect.
+    // Nobody hit the ball yet after a serve, so it just goes through.
+    pub fn collect_power_up(&mut self) -> Option<(Seat, PowerUpKind)> {
+        let power_up = self.power_up?;
+        let seat = self.last_hitter?;
+
+        let touched = power_up.touches(&self.ball)
+            || self
+                .extra_ball
+                .is_some_and(|extra| power_up.touches(&extra.ball));
+        if !touched {
+            return None;
+        }
+
+        self.power_up = None;
+        match power_up.kind {
+            PowerUpKind::Laser => {
+                self.stunned[seat.side.opponent().index()] = LASER_STUN_TICKS;
+            }
+            PowerUpKind::Star => {
+                let mut ball = self.ball;
+                ball.vy = -ball.vy;
+                self.extra_ball = Some(ExtraBall {
+                    ball,
+                    ticks_left: STAR_TICKS,
+                });
+            }
+            PowerUpKind::Key => {
+                if let Some(lock) = &mut self.lock {
+                    lock.open_ticks = KEY_TICKS;
+                }
+            }
+        }
+
+        Some((seat, power_up.kind))
+    }
+
+    // Count a point once a ball left the field. The main ball is served again,
+    // the extra one is gone.
+    pub fn score_point(&mut self) -> Option<Side> {
+        let extra_scorer = self.extra_ball.and_then(|extra| scorer(&extra.ball));
+        if let Some(scorer) = extra_scorer {
+            self.score[scorer.index()] += 1;
+            self.extra_ball = None;
+        }
+
+        let scorer = scorer(&self.ball);
+        if let Some(scorer) = scorer {
+            self.score[scorer.index()] += 1;
+            self.serve(scorer.opponent());
+        }
+
+        scorer.or(extra_scorer)
+    }
+}
+
+enum BallId {
+    Main,
+    Extra,
+}
+
+fn scorer(ball: &Ball) -> Option<Side> {
+    let edge = FIELD_WIDTH / 2.0 + BALL_SIZE / 2.0;
+
+    if ball.x > edge {
+        Some(Side::Left)
+    } else if ball.x < -edge {
+        Some(Side::Right)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,6 @@
+pub mod chat;
+pub mod fragment;
+pub mod game;
+pub mod netsim;
+pub mod protocol;
+pub mod security;
@@ -0,0 +1,163 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::NetworkConditions;
+
+// Packets queued on a slow link for longer than this are dropped, like a full router buffer
+const MAX_BACKLOG: Duration = Duration::from_secs(1);
+
+// Holds packets back to apply network conditions to one direction of a connection.
+// Packets go in with `push` and come out of `pop_ready` once they are due.
+pub struct Conditioner<P> {
+    pub conditions: NetworkConditions,
+    queue: BinaryHeap<Reverse<Queued<P>>>,
+    rng: StdRng,
+
+    // Numbered in arrival order, so packets due at the same time keep it
+    next_order: u64,
+
+    // When the link is done sending what is already queued, for the bandwidth cap
+    link_free_at: Option<Instant>,
+
+    // Due time of the last packet that kept its place, later ones can't overtake it
+    last_due: Option<Instant>,
+}
+
+impl<P> fmt::Debug for Conditioner<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Conditioner")
+            .field("conditions", &self.conditions)
+            .field("queued", &self.queue.len())
+            .finish()
+    }
+}
+
+struct Queued<P> {
+    due: Instant,
+    order: u64,
+    packet: P,
+}
+
+impl<P> PartialEq for Queued<P> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.due, self.order) == (other.due, other.order)
+    }
+}
+
+impl<P> Eq for Queued<P> {}
+
+impl<P> PartialOrd for Queued<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P> Ord for Queued<P> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.due, self.order).cmp(&(other.due, other.order))
+    }
+}
+
+impl<P: Clone> Conditioner<P> {
+    pub fn new(conditions: NetworkConditions) -> Self {
+        Self {
+            conditions,
+            queue: BinaryHeap::new(),
+            rng: StdRng::from_os_rng(),
+            next_order: 0,
+            link_free_at: None,
+            last_due: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    // With a perfect network and nothing queued, packets can skip the queue
+    pub fn passes_through(&self) -> bool {
+        self.conditions.is_perfect() && self.queue.is_empty()
+    }
+
+    // Queue a packet of `size` bytes sent at `now`, it may be dropped or duplicated
+    pub fn push(&mut self, now: Instant, packet: P, size: usize) {
+        let conditions = self.conditions;
+
+        if self.rng.random_bool(conditions.loss) {
+            return;
+        }
+
+        // The packet waits for the ones before it to be on the wire
+        let mut sent = now;
+        if conditions.bandwidth > 0.0 {
+            let start = self.link_free_at.map_or(now, |free| free.max(now));
+            if start - now > MAX_BACKLOG {
+                return;
+            }
+
+            // Kilobits per second is bits per millisecond
+            let transmit = size as f64 * 8.0 / conditions.bandwidth;
+            sent = start + Duration::from_secs_f64(transmit / 1000.0);
+            self.link_free_at = Some(sent);
+        }
+
+        let jitter = if conditions.jitter > 0.0 {
+            self.rng
+                .random_range(-conditions.jitter..=conditions.jitter)
+        } else {
+            0.0
+        };
+        let delay = (conditions.latency + jitter).max(0.0);
+        let mut due = sent + Duration::from_secs_f64(delay / 1000.0);
+
+        if self.rng.random_bool(conditions.reorder) {
+            // Held back long enough for the next packets to overtake it
+            let extra = self
+                .rng
+                .random_range(1.0..=conditions.jitter.max(10.0) * 2.0);
+            due += Duration::from_secs_f64(extra / 1000.0);
+        } else {
+            // Jitter alone doesn't reorder packets on a real link
+            due = self.last_due.map_or(due, |last| last.max(due));
+            self.last_due = Some(due);
+        }
+
+        if self.rng.random_bool(conditions.duplicate) {
+            let copy = packet.clone();
+            self.enqueue(due + Duration::from_millis(1), copy);
+        }
+
+        self.enqueue(due, packet);
+    }
+
+    fn enqueue(&mut self, due: Instant, packet: P) {
+        let order = self.next_order;
+        self.next_order += 1;
+
+        self.queue.push(Reverse(Queued { due, order, packet }));
+    }
+
+    // When the next packet is due, if any
+    pub fn next_due(&self) -> Option<Instant> {
+        self.queue.peek().map(|Reverse(queued)| queued.due)
+    }
+
+    // Every packet due by `now`, in the order they arrive
+    pub fn pop_ready(&mut self, now: Instant) -> Vec<P> {
+        let mut ready = Vec::new();
+
+        while self.next_due().is_some_and(|due| due <= now) {
+            if let Some(Reverse(queued)) = self.queue.pop() {
+                ready.push(queued.packet);
+            }
+        }
+
+        ready
+    }
+}
@@ -0,0 +1,122 @@
+pub mod conditioner;
+
+use std::{fmt, str::FromStr};
+
+// Network impairments to apply on top of the real network, for reproducing
+// bad connections on a dev box. The default is a perfect network.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkConditions {
+    // One way delay added to every packet, in milliseconds
+    pub latency: f64,
+
+    // Random variation of the delay, up to this much either way
+    pub jitter: f64,
+
+    // Chances for each packet between 0 and 1
+    pub loss: f64,
+    pub duplicate: f64,
+    pub reorder: f64,
+
+    // Link speed in kilobits per second, 0 for unlimited
+    pub bandwidth: f64,
+}
+
+impl NetworkConditions {
+    pub const PRESETS: [&'static str; 5] = ["off", "lan", "wifi", "mobile", "terrible"];
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let conditions = match name {
+            "off" => Self::default(),
+            "lan" => Self {
+                latency: 2.0,
+                jitter: 1.0,
+                ..Self::default()
+            },
+            "wifi" => Self {
+                latency: 20.0,
+                jitter: 10.0,
+                loss: 0.01,
+                ..Self::default()
+            },
+            "mobile" => Self {
+                latency: 80.0,
+                jitter: 30.0,
+                loss: 0.03,
+                duplicate: 0.01,
+                reorder: 0.02,
+                bandwidth: 1000.0,
+            },
+            "terrible" => Self {
+                latency: 200.0,
+                jitter: 80.0,
+                loss: 0.1,
+                duplicate: 0.05,
+                reorder: 0.1,
+                bandwidth: 128.0,
+            },
+            _ => return None,
+        };
+
+        Some(conditions)
+    }
+
+    pub fn is_perfect(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// Either a preset name or `key=value` pairs, e.g. "latency=100 jitter=20 loss=0.05".
+// Pairs after a preset change it, "mobile loss=0" is the mobile preset without loss.
+impl FromStr for NetworkConditions {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut conditions = Self::default();
+
+        for part in s.split_whitespace() {
+            let Some((key, value)) = part.split_once('=') else {
+                conditions = Self::preset(part).ok_or(format!("Unknown preset {part}"))?;
+                continue;
+            };
+
+            let value: f64 = value
+                .parse()
+                .ok()
+                .filter(|value: &f64| *value >= 0.0)
+                .ok_or(format!("Invalid value for {key}: {value}"))?;
+            let chance = || {
+                if value <= 1.0 {
+                    Ok(value)
+                } else {
+                    Err(format!("{key} is a chance between 0 and 1"))
+                }
+            };
+
+            match key {
+                "latency" => conditions.latency = value,
+                "jitter" => conditions.jitter = value,
+                "loss" => conditions.loss = chance()?,
+                "duplicate" => conditions.duplicate = chance()?,
+                "reorder" => conditions.reorder = chance()?,
+                "bandwidth" => conditions.bandwidth = value,
+                _ => return Err(format!("Unknown setting {key}")),
+            }
+        }
+
+        Ok(conditions)
+    }
+}
+
+impl fmt::Display for NetworkConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_perfect() {
+            return write!(f, "off");
+        }
+
+        write!(
+            f,
+            "latency={} jitter={} loss={} duplicate={} reorder={} bandwidth={}",
+            self.latency, self.jitter, self.loss, self.duplicate, self.reorder, self.bandwidth
+        )
+    }
+}
@@ -0,0 +1,90 @@
+use super::WireError;
+
+// LEB128, small numbers take a single byte
+pub fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+// Zigzag first so small negative numbers stay small too
+pub fn put_signed(buf: &mut Vec<u8>, value: i64) {
+    put_varint(buf, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+pub fn put_f64(buf: &mut Vec<u8>, value: f64) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+pub fn put_text(buf: &mut Vec<u8>, text: &str) {
+    put_varint(buf, text.len() as u64);
+    buf.extend_from_slice(text.as_bytes());
+}
+
+// Reads a payload front to back, every read fails once the bytes run out
+#[derive(Debug)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], WireError> {
+        if self.data.len() < len {
+            return Err(WireError::Truncated);
+        }
+
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.data)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, WireError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn varint(&mut self) -> Result<u64, WireError> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(WireError::Malformed("varint"))
+    }
+
+    pub fn signed(&mut self) -> Result<i64, WireError> {
+        let value = self.varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, WireError> {
+        let bytes = self.bytes(8)?;
+        Ok(f64::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn text(&mut self) -> Result<&'a str, WireError> {
+        let len = self.varint()?;
+        let len = usize::try_from(len).map_err(|_| WireError::Truncated)?;
+
+        std::str::from_utf8(self.bytes(len)?).map_err(|_| WireError::Malformed("text"))
+    }
+}
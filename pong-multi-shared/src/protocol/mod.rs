@@ -0,0 +1,286 @@
+pub mod buffer;
+pub mod schema;
+pub mod snapshot;
+
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use serde_json::Value;
+
+use buffer::Reader;
+use snapshot::{QuantizedSnapshot, SnapshotHistory};
+
+// Bumped whenever the binary encoding changes.
+//   1: binary messages and delta snapshots
+//   2: enter carries the protocol and build, entered the version picked
+//   3: doubles, join and match_found carry the mode and snapshots say
+//      which paddles are there
+//   4: power-ups, snapshots carry the extra ball, the power-up on the field,
+//      the stunned teams and the locked blocks
+//   5: maps, voted on with the vote message before the match
+//   6: AI opponents, join asks for practice or a fallback after a wait and
+//      match_found says which difficulty the AI plays at
+//   7: chat, text or emotes, from a player to their room or the lobby
+//   8: parties, created and joined with a code, queued by their leader
+//   9: tournaments, opened, registered to and started by message, the
+//      bracket itself goes as JSON
+//  10: room browser, list_rooms asks for a page of it and spectate watches
+//      one of the matches, parties can be listed
+//  11: pause and resume during a match, pause_state tells everyone in the room
+pub const PROTOCOL_VERSION: u8 = 11;
+
+// Oldest version we can still read and write
+pub const MIN_PROTOCOL_VERSION: u8 = 1;
+
+// Set on the version byte that starts every binary payload, so it can't be
+// mistaken for JSON text, a secured packet or a fragment
+pub const BINARY_FLAG: u8 = 0x80;
+
+// Message ids next to the ones in schema::MESSAGES
+pub const SNAPSHOT_ID: u8 = 0x20;
+pub const EMBEDDED_JSON_ID: u8 = 0xff;
+
+pub fn is_binary(payload: &[u8]) -> bool {
+    payload.first().is_some_and(|byte| byte & BINARY_FLAG != 0)
+}
+
+// What we send. Both ends read either, JSON is there to look at the traffic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    #[default]
+    Binary,
+    Json,
+}
+
+impl FromStr for WireFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "binary" => Ok(WireFormat::Binary),
+            "json" => Ok(WireFormat::Json),
+            other => Err(format!(
+                "Unknown wire format {other}, expected binary or json"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireFormat::Binary => write!(f, "binary"),
+            WireFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    Truncated,
+    Malformed(&'static str),
+    InvalidJson,
+    UnsupportedVersion(u8),
+    UnknownMessage(u8),
+    // A delta against a snapshot we don't have (anymore)
+    MissingBaseline(u64),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::Truncated => write!(f, "truncated message"),
+            WireError::Malformed(what) => write!(f, "malformed {what}"),
+            WireError::InvalidJson => write!(f, "invalid JSON"),
+            WireError::UnsupportedVersion(version) => {
+                write!(f, "unsupported protocol version {version}")
+            }
+            WireError::UnknownMessage(id) => write!(f, "unknown message id {id}"),
+            WireError::MissingBaseline(tick) => {
+                write!(f, "no snapshot at tick {tick} to apply the delta to")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionMismatch {
+    ClientTooOld,
+    ServerTooOld,
+}
+
+impl fmt::Display for VersionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionMismatch::ClientTooOld => {
+                write!(
+                    f,
+                    "Your game is out of date, please update it to play online"
+                )
+            }
+            VersionMismatch::ServerTooOld => {
+                write!(
+                    f,
+                    "The server runs an older version of the game, try again later"
+                )
+            }
+        }
+    }
+}
+
+// The newest version both ends speak
+pub fn negotiate(
+    client: RangeInclusive<u8>,
+    server: RangeInclusive<u8>,
+) -> Result<u8, VersionMismatch> {
+    let version = (*client.end()).min(*server.end());
+
+    if version < *server.start() {
+        Err(VersionMismatch::ClientTooOld)
+    } else if version < *client.start() {
+        Err(VersionMismatch::ServerTooOld)
+    } else {
+        Ok(version)
+    }
+}
+
+// Encoding for one peer. Snapshots are sent as deltas against the latest one
+// the peer acknowledged, or in full until there is one.
+#[derive(Debug)]
+pub struct Encoder {
+    pub format: WireFormat,
+
+    // Lowered to what the peer speaks once the versions are negotiated
+    pub version: u8,
+
+    sent: SnapshotHistory,
+    acked: Option<u64>,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new(WireFormat::default())
+    }
+}
+
+impl Encoder {
+    pub fn new(format: WireFormat) -> Self {
+        Self {
+            format,
+            version: PROTOCOL_VERSION,
+            sent: SnapshotHistory::default(),
+            acked: None,
+        }
+    }
+
+    // Only snapshots we still have count, older acks can arrive out of order
+    pub fn acknowledge(&mut self, tick: u64) {
+        if self.sent.get(tick).is_some() && self.acked.is_none_or(|acked| tick > acked) {
+            self.acked = Some(tick);
+        }
+    }
+
+    pub fn encode(&mut self, message: &Value) -> Vec<u8> {
+        // Nothing is negotiated before enter, every version reads it as text
+        if self.format == WireFormat::Json || message["action"] == "enter" {
+            return message.to_string().into_bytes();
+        }
+
+        let mut buf = vec![BINARY_FLAG | self.version];
+
+        match message["action"].as_str() {
+            Some("snapshot") => {
+                if let Some(snapshot) = QuantizedSnapshot::from_json(message, self.version) {
+                    let baseline = self.acked.and_then(|tick| self.sent.get(tick));
+
+                    buf.push(SNAPSHOT_ID);
+                    snapshot.write(baseline, self.version, &mut buf);
+                    self.sent.push(snapshot);
+                    return buf;
+                }
+            }
+
+            // Ticks restart from zero in every room, played or watched
+            Some("match_found" | "spectating") => {
+                self.sent.clear();
+                self.acked = None;
+            }
+
+            _ => {}
+        }
+
+        let written = message["action"]
+            .as_str()
+            .and_then(schema::by_action)
+            .is_some_and(|schema| schema.write(message, self.version, &mut buf));
+
+        // Anything without a compact encoding goes as it is
+        if !written {
+            buf.truncate(1);
+            buf.push(EMBEDDED_JSON_ID);
+            buf.extend_from_slice(message.to_string().as_bytes());
+        }
+
+        buf
+    }
+}
+
+// Decoding for one peer, keeps the snapshots received to apply deltas to
+#[derive(Debug, Default)]
+pub struct Decoder {
+    received: SnapshotHistory,
+}
+
+impl Decoder {
+    pub fn decode(&mut self, payload: &[u8]) -> Result<Value, WireError> {
+        if !is_binary(payload) {
+            return serde_json::from_slice(payload).map_err(|_| WireError::InvalidJson);
+        }
+
+        let mut reader = Reader::new(payload);
+
+        let version = reader.u8()? & !BINARY_FLAG;
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+            return Err(WireError::UnsupportedVersion(version));
+        }
+
+        let message = match reader.u8()? {
+            SNAPSHOT_ID => {
+                let snapshot = QuantizedSnapshot::read(&mut reader, version, &self.received)?;
+                self.received.push(snapshot);
+                snapshot.to_json()
+            }
+
+            EMBEDDED_JSON_ID => {
+                serde_json::from_slice(reader.rest()).map_err(|_| WireError::InvalidJson)?
+            }
+
+            id => schema::by_id(id)
+                .ok_or(WireError::UnknownMessage(id))?
+                .read(&mut reader)?,
+        };
+
+        if !reader.is_empty() {
+            return Err(WireError::Malformed("trailing bytes"));
+        }
+
+        if matches!(
+            message["action"].as_str(),
+            Some("match_found" | "spectating")
+        ) {
+            self.received.clear();
+        }
+
+        Ok(message)
+    }
+}
+
+// For messages that aren't snapshots, which don't need any state
+pub fn encode(message: &Value, format: WireFormat) -> Vec<u8> {
+    Encoder::new(format).encode(message)
+}
+
+pub fn decode(payload: &[u8]) -> Result<Value, WireError> {
+    Decoder::default().decode(payload)
+}
@@ -0,0 +1,466 @@
+use serde_json::{Map, Value};
+
+use super::{
+    buffer::{put_f64, put_signed, put_text, put_varint, Reader},
+    WireError,
+};
+use crate::{
+    game::Side,
+    security::{key_from_hex, to_hex},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Bool,
+    Unsigned,
+    Signed,
+    Float,
+    Text,
+    // 32 bytes written as hex in JSON
+    Key,
+    // Hyphenated lowercase UUID in JSON
+    Uuid,
+    Side,
+}
+
+// Fields of a message in encoding order, with the protocol version they were
+// added in. Nested fields use dotted paths.
+#[derive(Debug)]
+pub struct MessageSchema {
+    pub id: u8,
+    pub action: &'static str,
+    pub fields: &'static [(&'static str, FieldType, u8)],
+}
+
+// Messages with a compact encoding, anything else goes as embedded JSON.
+// Ids are part of the protocol, only add new ones at the end. The same goes
+// for fields, older peers skip the ones they don't know through the presence bits.
+pub const MESSAGES: &[MessageSchema] = &[
+    MessageSchema {
+        id: 1,
+        action: "enter",
+        fields: &[],
+    },
+    MessageSchema {
+        id: 2,
+        action: "entered",
+        fields: &[
+            ("player_id", FieldType::Uuid, 1),
+            ("protocol", FieldType::Unsigned, 2),
+        ],
+    },
+    MessageSchema {
+        id: 3,
+        action: "handshake",
+        fields: &[
+            ("public_key", FieldType::Key, 1),
+            ("encrypt", FieldType::Bool, 1),
+        ],
+    },
+    MessageSchema {
+        id: 4,
+        action: "join",
+        fields: &[
+            ("mode", FieldType::Text, 3),
+            ("practice", FieldType::Text, 6),
+            ("ai_fallback", FieldType::Text, 6),
+        ],
+    },
+    MessageSchema {
+        id: 5,
+        action: "leave",
+        fields: &[],
+    },
+    MessageSchema {
+        id: 6,
+        action: "move",
+        fields: &[
+            ("room_id", FieldType::Uuid, 1),
+            ("seq", FieldType::Unsigned, 1),
+            ("direction", FieldType::Signed, 1),
+            ("tick", FieldType::Unsigned, 1),
+        ],
+    },
+    MessageSchema {
+        id: 7,
+        action: "ack",
+        fields: &[("tick", FieldType::Unsigned, 1)],
+    },
+    MessageSchema {
+        id: 8,
+        action: "ping",
+        fields: &[("client_time", FieldType::Float, 1)],
+    },
+    MessageSchema {
+        id: 9,
+        action: "pong",
+        fields: &[
+            ("client_time", FieldType::Float, 1),
+            ("server_receive", FieldType::Float, 1),
+            ("server_send", FieldType::Float, 1),
+        ],
+    },
+    MessageSchema {
+        id: 10,
+        action: "match_found",
+        fields: &[
+            ("room_id", FieldType::Uuid, 1),
+            ("side", FieldType::Side, 1),
+            ("mode", FieldType::Text, 3),
+            ("slot", FieldType::Unsigned, 3),
+            ("ai", FieldType::Text, 6),
+        ],
+    },
+    MessageSchema {
+        id: 11,
+        action: "match_over",
+        fields: &[
+            ("winner", FieldType::Side, 1),
+            ("reason", FieldType::Text, 1),
+            ("score.left", FieldType::Unsigned, 1),
+            ("score.right", FieldType::Unsigned, 1),
+        ],
+    },
+    MessageSchema {
+        id: 12,
+        action: "kicked",
+        fields: &[("reason", FieldType::Text, 1)],
+    },
+    MessageSchema {
+        id: 13,
+        action: "vote",
+        fields: &[("room_id", FieldType::Uuid, 5), ("map", FieldType::Text, 5)],
+    },
+    MessageSchema {
+        id: 14,
+        action: "chat",
+        fields: &[
+            ("text", FieldType::Text, 7),
+            ("emote", FieldType::Text, 7),
+            ("from", FieldType::Text, 7),
+            ("side", FieldType::Side, 7),
+        ],
+    },
+    MessageSchema {
+        id: 15,
+        action: "party_create",
+        fields: &[("listed", FieldType::Bool, 10)],
+    },
+    MessageSchema {
+        id: 16,
+        action: "party_invite",
+        fields: &[
+            ("name", FieldType::Text, 8),
+            ("from", FieldType::Text, 8),
+            ("code", FieldType::Text, 8),
+        ],
+    },
+    MessageSchema {
+        id: 17,
+        action: "party_join",
+        fields: &[("code", FieldType::Text, 8)],
+    },
+    MessageSchema {
+        id: 18,
+        action: "party_leave",
+        fields: &[],
+    },
+    MessageSchema {
+        id: 19,
+        action: "party_left",
+        fields: &[],
+    },
+    MessageSchema {
+        id: 20,
+        action: "tournament_create",
+        fields: &[("format", FieldType::Text, 9)],
+    },
+    MessageSchema {
+        id: 21,
+        action: "tournament_register",
+        fields: &[],
+    },
+    MessageSchema {
+        id: 22,
+        action: "tournament_unregister",
+        fields: &[],
+    },
+    MessageSchema {
+        id: 23,
+        action: "tournament_start",
+        fields: &[],
+    },
+    MessageSchema {
+        id: 24,
+        action: "list_rooms",
+        fields: &[
+            ("page", FieldType::Unsigned, 10),
+            ("per_page", FieldType::Unsigned, 10),
+            ("kind", FieldType::Text, 10),
+            ("mode", FieldType::Text, 10),
+            ("name", FieldType::Text, 10),
+        ],
+    },
+    MessageSchema {
+        id: 25,
+        action: "spectate",
+        fields: &[("room_id", FieldType::Uuid, 10)],
+    },
+    MessageSchema {
+        id: 26,
+        action: "spectating",
+        fields: &[
+            ("room_id", FieldType::Uuid, 10),
+            ("mode", FieldType::Text, 10),
+            ("ai", FieldType::Text, 10),
+        ],
+    },
+    MessageSchema {
+        id: 27,
+        action: "pause",
+        fields: &[
+            ("room_id", FieldType::Uuid, 11),
+            ("after_point", FieldType::Bool, 11),
+        ],
+    },
+    MessageSchema {
+        id: 28,
+        action: "resume",
+        fields: &[("room_id", FieldType::Uuid, 11)],
+    },
+    MessageSchema {
+        id: 29,
+        action: "pause_state",
+        fields: &[
+            ("state", FieldType::Text, 11),
+            ("side", FieldType::Text, 11),
+            ("yours", FieldType::Bool, 11),
+            ("seconds", FieldType::Unsigned, 11),
+            ("votes", FieldType::Unsigned, 11),
+            ("needed", FieldType::Unsigned, 11),
+            ("pauses_left", FieldType::Unsigned, 11),
+        ],
+    },
+    MessageSchema {
+        id: 30,
+        action: "pause_refused",
+        fields: &[("reason", FieldType::Text, 11)],
+    },
+];
+
+pub fn by_action(action: &str) -> Option<&'static MessageSchema> {
+    MESSAGES.iter().find(|schema| schema.action == action)
+}
+
+pub fn by_id(id: u8) -> Option<&'static MessageSchema> {
+    MESSAGES.iter().find(|schema| schema.id == id)
+}
+
+// Dotted path of every value in the message that isn't an object itself
+pub fn leaf_paths(message: &Value) -> Vec<String> {
+    fn walk(value: &Value, prefix: &str, paths: &mut Vec<String>) {
+        match value {
+            Value::Object(object) if !object.is_empty() => {
+                for (key, value) in object {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    walk(value, &path, paths);
+                }
+            }
+            _ => paths.push(prefix.to_string()),
+        }
+    }
+
+    let mut paths = Vec::new();
+    walk(message, "", &mut paths);
+    paths
+}
+
+pub fn get_path<'a>(message: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(message, |value, key| value.as_object()?.get(key))
+}
+
+pub fn set_path(message: &mut Value, path: &str, field: Value) {
+    let mut keys = path.split('.').peekable();
+    let mut value = message;
+
+    while let Some(key) = keys.next() {
+        let Some(object) = value.as_object_mut() else {
+            return;
+        };
+
+        if keys.peek().is_none() {
+            object.insert(key.to_string(), field);
+            return;
+        }
+
+        value = object
+            .entry(key)
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+}
+
+impl MessageSchema {
+    // Presence bits followed by the fields that are there. Returns false when the
+    // message has something the schema can't carry in that protocol version, so
+    // nothing gets lost.
+    pub fn write(&self, message: &Value, version: u8, buf: &mut Vec<u8>) -> bool {
+        let fields = || self.fields.iter().filter(|(_, _, since)| *since <= version);
+
+        let known = leaf_paths(message)
+            .iter()
+            .all(|path| path == "action" || fields().any(|(field, _, _)| field == path));
+        if !known {
+            return false;
+        }
+
+        let mut present = 0u64;
+        let mut body = Vec::new();
+
+        // Bits are numbered over every field, like read does
+        for (index, (path, field_type, since)) in self.fields.iter().enumerate() {
+            if *since > version {
+                continue;
+            }
+            let Some(value) = get_path(message, path) else {
+                continue;
+            };
+            if !write_field(*field_type, value, &mut body) {
+                return false;
+            }
+            present |= 1 << index;
+        }
+
+        buf.push(self.id);
+        put_varint(buf, present);
+        buf.extend_from_slice(&body);
+        true
+    }
+
+    pub fn read(&self, reader: &mut Reader) -> Result<Value, WireError> {
+        let present = reader.varint()?;
+        if present >> self.fields.len() != 0 {
+            return Err(WireError::Malformed("presence bits"));
+        }
+
+        let mut message = serde_json::json!({ "action": self.action });
+
+        for (index, (path, field_type, _)) in self.fields.iter().enumerate() {
+            if present & (1 << index) != 0 {
+                let value = read_field(*field_type, reader)?;
+                set_path(&mut message, path, value);
+            }
+        }
+
+        Ok(message)
+    }
+}
+
+fn write_field(field_type: FieldType, value: &Value, buf: &mut Vec<u8>) -> bool {
+    match field_type {
+        FieldType::Bool => {
+            let Some(value) = value.as_bool() else {
+                return false;
+            };
+            buf.push(value as u8);
+        }
+        FieldType::Unsigned => {
+            let Some(value) = value.as_u64() else {
+                return false;
+            };
+            put_varint(buf, value);
+        }
+        FieldType::Signed => {
+            let Some(value) = value.as_i64() else {
+                return false;
+            };
+            put_signed(buf, value);
+        }
+        FieldType::Float => {
+            let Some(value) = value.as_f64() else {
+                return false;
+            };
+            put_f64(buf, value);
+        }
+        FieldType::Text => {
+            let Some(value) = value.as_str() else {
+                return false;
+            };
+            put_text(buf, value);
+        }
+        FieldType::Key => {
+            let Some(key) = value.as_str().and_then(key_from_hex) else {
+                return false;
+            };
+            buf.extend_from_slice(&key);
+        }
+        FieldType::Uuid => {
+            let Some(uuid) = value.as_str().and_then(uuid_from_str) else {
+                return false;
+            };
+            buf.extend_from_slice(&uuid);
+        }
+        FieldType::Side => {
+            let Some(side) = value.as_str().and_then(Side::parse) else {
+                return false;
+            };
+            buf.push(side.index() as u8);
+        }
+    }
+
+    true
+}
+
+fn read_field(field_type: FieldType, reader: &mut Reader) -> Result<Value, WireError> {
+    let value = match field_type {
+        FieldType::Bool => match reader.u8()? {
+            0 => Value::Bool(false),
+            1 => Value::Bool(true),
+            _ => return Err(WireError::Malformed("bool")),
+        },
+        FieldType::Unsigned => Value::from(reader.varint()?),
+        FieldType::Signed => Value::from(reader.signed()?),
+        FieldType::Float => Value::from(reader.f64()?),
+        FieldType::Text => Value::from(reader.text()?),
+        FieldType::Key => Value::from(to_hex(reader.bytes(32)?)),
+        FieldType::Uuid => Value::from(uuid_to_string(reader.bytes(16)?)),
+        FieldType::Side => match reader.u8()? {
+            0 => Value::from(Side::Left.as_str()),
+            1 => Value::from(Side::Right.as_str()),
+            _ => return Err(WireError::Malformed("side")),
+        },
+    };
+
+    Ok(value)
+}
+
+// Only the canonical form is accepted, so it comes back out exactly the same
+fn uuid_from_str(text: &str) -> Option<[u8; 16]> {
+    let hex: String = text.chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut uuid = [0; 16];
+    for (index, byte) in uuid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+    }
+
+    (uuid_to_string(&uuid) == text).then_some(uuid)
+}
+
+fn uuid_to_string(bytes: &[u8]) -> String {
+    let hex = to_hex(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
@@ -0,0 +1,244 @@
+use std::collections::VecDeque;
+
+use serde_json::{json, Value};
+
+use super::{
+    buffer::{put_signed, put_varint, Reader},
+    schema::{get_path, leaf_paths, set_path},
+    WireError,
+};
+
+// Snapshots kept on both ends to encode and decode deltas against,
+// a bit more than 3 seconds at the room snapshot rate
+pub const SNAPSHOT_HISTORY: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quantity {
+    Integer,
+    // Fixed point, the value is stored multiplied by this
+    Fixed(f64),
+}
+
+// Snapshot fields besides the tick, in encoding order, with the protocol
+// version they were added in. Positions and velocities are kept to 1/8 of
+// a pixel, the server time to 1/100 ms.
+pub const SNAPSHOT_FIELDS: [(&str, Quantity, u8); 22] = [
+    ("time", Quantity::Fixed(100.0), 1),
+    ("ack", Quantity::Integer, 1),
+    ("ball.x", Quantity::Fixed(8.0), 1),
+    ("ball.y", Quantity::Fixed(8.0), 1),
+    ("ball.vx", Quantity::Fixed(8.0), 1),
+    ("ball.vy", Quantity::Fixed(8.0), 1),
+    ("paddles.left", Quantity::Fixed(8.0), 1),
+    ("paddles.right", Quantity::Fixed(8.0), 1),
+    ("score.left", Quantity::Integer, 1),
+    ("score.right", Quantity::Integer, 1),
+    ("paddles.left_2", Quantity::Fixed(8.0), 3),
+    ("paddles.right_2", Quantity::Fixed(8.0), 3),
+    ("extra_ball.x", Quantity::Fixed(8.0), 4),
+    ("extra_ball.y", Quantity::Fixed(8.0), 4),
+    ("extra_ball.vx", Quantity::Fixed(8.0), 4),
+    ("extra_ball.vy", Quantity::Fixed(8.0), 4),
+    ("power_up.kind", Quantity::Integer, 4),
+    ("power_up.x", Quantity::Fixed(8.0), 4),
+    ("power_up.y", Quantity::Fixed(8.0), 4),
+    ("stunned.left", Quantity::Integer, 4),
+    ("stunned.right", Quantity::Integer, 4),
+    ("lock.open", Quantity::Integer, 4),
+];
+
+// From this version on, snapshots say which fields they have. Before it,
+// every field of the version is always there.
+pub const OPTIONAL_FIELDS_VERSION: u8 = 3;
+
+// Past this a float can't hold every integer anymore
+const MAX_QUANTIZED: f64 = (1u64 << 53) as f64;
+
+// A snapshot the way it goes over the wire. Both ends keep these rather than
+// the JSON so the deltas are computed from exactly the same numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuantizedSnapshot {
+    pub tick: u64,
+
+    // A bit per field, missing ones are left at zero
+    pub present: u64,
+    pub fields: [i64; SNAPSHOT_FIELDS.len()],
+}
+
+// Bits of the fields a version knows about
+fn fields_of(version: u8) -> u64 {
+    SNAPSHOT_FIELDS
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, _, since))| *since <= version)
+        .fold(0, |bits, (index, _)| bits | 1 << index)
+}
+
+impl QuantizedSnapshot {
+    // None for anything that isn't a snapshot the version can carry exactly
+    pub fn from_json(message: &Value, version: u8) -> Option<Self> {
+        let known = fields_of(version);
+        let known_path = |path: &str| {
+            SNAPSHOT_FIELDS
+                .iter()
+                .enumerate()
+                .any(|(index, (field, _, _))| known & (1 << index) != 0 && *field == path)
+        };
+        if !leaf_paths(message)
+            .iter()
+            .all(|path| path == "action" || path == "tick" || known_path(path))
+        {
+            return None;
+        }
+
+        let mut snapshot = Self {
+            tick: message["tick"].as_u64()?,
+            ..Self::default()
+        };
+
+        for (index, (value, (path, quantity, _))) in
+            snapshot.fields.iter_mut().zip(SNAPSHOT_FIELDS).enumerate()
+        {
+            let Some(field) = get_path(message, path) else {
+                continue;
+            };
+            snapshot.present |= 1 << index;
+
+            *value = match quantity {
+                Quantity::Integer => field.as_i64()?,
+                Quantity::Fixed(scale) => {
+                    let scaled = (field.as_f64()? * scale).round();
+                    if !scaled.is_finite() || scaled.abs() > MAX_QUANTIZED {
+                        return None;
+                    }
+                    scaled as i64
+                }
+            };
+        }
+
+        if version < OPTIONAL_FIELDS_VERSION && snapshot.present != known {
+            return None;
+        }
+
+        Some(snapshot)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut message = json!({ "action": "snapshot", "tick": self.tick });
+
+        for (index, (value, (path, quantity, _))) in
+            self.fields.iter().zip(SNAPSHOT_FIELDS).enumerate()
+        {
+            if self.present & (1 << index) == 0 {
+                continue;
+            }
+
+            let field = match quantity {
+                Quantity::Integer => Value::from(*value),
+                Quantity::Fixed(scale) => Value::from(*value as f64 / scale),
+            };
+            set_path(&mut message, path, field);
+        }
+
+        message
+    }
+
+    // The tick, how far back the baseline is (0 without one), the fields that
+    // appeared or went away since the baseline, a bit per changed field and the
+    // changes themselves
+    pub fn write(&self, baseline: Option<&QuantizedSnapshot>, version: u8, buf: &mut Vec<u8>) {
+        let baseline = baseline.filter(|baseline| baseline.tick < self.tick);
+
+        put_varint(buf, self.tick);
+        put_varint(
+            buf,
+            baseline.map_or(0, |baseline| self.tick - baseline.tick),
+        );
+
+        let baseline = baseline.copied().unwrap_or_default();
+        if version >= OPTIONAL_FIELDS_VERSION {
+            put_varint(buf, self.present ^ baseline.present);
+        }
+
+        let mut changed = 0u64;
+        let mut deltas = Vec::new();
+
+        for (index, (value, base)) in self.fields.iter().zip(baseline.fields).enumerate() {
+            if self.present & (1 << index) != 0 && *value != base {
+                changed |= 1 << index;
+                put_signed(&mut deltas, value.wrapping_sub(base));
+            }
+        }
+
+        put_varint(buf, changed);
+        buf.extend_from_slice(&deltas);
+    }
+
+    pub fn read(
+        reader: &mut Reader,
+        version: u8,
+        history: &SnapshotHistory,
+    ) -> Result<Self, WireError> {
+        let tick = reader.varint()?;
+        let baseline = match reader.varint()? {
+            0 => QuantizedSnapshot::default(),
+            distance => {
+                let base_tick = tick
+                    .checked_sub(distance)
+                    .ok_or(WireError::Malformed("snapshot baseline"))?;
+                *history
+                    .get(base_tick)
+                    .ok_or(WireError::MissingBaseline(base_tick))?
+            }
+        };
+
+        let present = if version >= OPTIONAL_FIELDS_VERSION {
+            reader.varint()? ^ baseline.present
+        } else {
+            fields_of(version)
+        };
+
+        let changed = reader.varint()?;
+        if present & !fields_of(version) != 0 || changed & !present != 0 {
+            return Err(WireError::Malformed("snapshot fields"));
+        }
+
+        let mut snapshot = Self {
+            tick,
+            present,
+            fields: baseline.fields,
+        };
+        for (index, value) in snapshot.fields.iter_mut().enumerate() {
+            if present & (1 << index) == 0 {
+                *value = 0;
+            } else if changed & (1 << index) != 0 {
+                *value = value.wrapping_add(reader.signed()?);
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
+
+// The last snapshots sent or received, oldest first
+#[derive(Debug, Default)]
+pub struct SnapshotHistory {
+    snapshots: VecDeque<QuantizedSnapshot>,
+}
+
+impl SnapshotHistory {
+    pub fn get(&self, tick: u64) -> Option<&QuantizedSnapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.tick == tick)
+    }
+
+    pub fn push(&mut self, snapshot: QuantizedSnapshot) {
+        self.snapshots.push_back(snapshot);
+        if self.snapshots.len() > SNAPSHOT_HISTORY {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
@@ -0,0 +1,86 @@
+pub mod packet;
+pub mod replay;
+
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+use packet::Session;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Client,
+    Server,
+}
+
+// One side of an X25519 key exchange. The public keys travel in the clear,
+// which stops anyone who can't see the traffic from forging packets.
+pub struct Handshake {
+    secret: EphemeralSecret,
+    pub public_key: [u8; 32],
+}
+
+impl Default for Handshake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Handshake {
+    pub fn new() -> Self {
+        let secret = EphemeralSecret::random();
+        let public_key = PublicKey::from(&secret).to_bytes();
+
+        Self { secret, public_key }
+    }
+
+    // Derive one key per direction from the shared secret
+    pub fn complete(self, role: Role, their_public_key: [u8; 32], encrypted: bool) -> Session {
+        let (client_public_key, server_public_key) = match role {
+            Role::Client => (self.public_key, their_public_key),
+            Role::Server => (their_public_key, self.public_key),
+        };
+
+        let shared_secret = self
+            .secret
+            .diffie_hellman(&PublicKey::from(their_public_key));
+
+        let mut salt = [0; 64];
+        salt[..32].copy_from_slice(&client_public_key);
+        salt[32..].copy_from_slice(&server_public_key);
+
+        let hkdf = Hkdf::<Sha256>::new(Some(&salt), shared_secret.as_bytes());
+        let mut client_key = [0; 32];
+        let mut server_key = [0; 32];
+        hkdf.expand(b"pong-multi client to server", &mut client_key)
+            .expect("32 bytes is a valid HKDF output length");
+        hkdf.expand(b"pong-multi server to client", &mut server_key)
+            .expect("32 bytes is a valid HKDF output length");
+
+        let client_cipher = ChaCha20Poly1305::new(Key::from_slice(&client_key));
+        let server_cipher = ChaCha20Poly1305::new(Key::from_slice(&server_key));
+
+        match role {
+            Role::Client => Session::new(client_cipher, server_cipher, encrypted),
+            Role::Server => Session::new(server_cipher, client_cipher, encrypted),
+        }
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn key_from_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut key = [0; 32];
+    for (index, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+    }
+
+    Some(key)
+}
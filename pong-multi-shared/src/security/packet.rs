@@ -0,0 +1,184 @@
+use std::fmt;
+
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    ChaCha20Poly1305, Nonce,
+};
+
+use super::replay::ReplayWindow;
+
+// First byte of a secured packet. Plain JSON packets start with '{'.
+pub const PACKET_AUTHENTICATED: u8 = 0x01;
+pub const PACKET_ENCRYPTED: u8 = 0x02;
+
+// Kind byte and sequence number
+const HEADER_SIZE: usize = 9;
+const TAG_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketError {
+    Malformed,
+    Replayed,
+    Forged,
+
+    // Only authenticated while the session requires encryption
+    Downgrade,
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketError::Malformed => write!(f, "malformed packet"),
+            PacketError::Replayed => write!(f, "replayed packet"),
+            PacketError::Forged => write!(f, "invalid authentication tag"),
+            PacketError::Downgrade => write!(f, "unencrypted packet on an encrypted session"),
+        }
+    }
+}
+
+pub fn is_secure(packet: &[u8]) -> bool {
+    matches!(
+        packet.first(),
+        Some(&PACKET_AUTHENTICATED) | Some(&PACKET_ENCRYPTED)
+    )
+}
+
+// Keys and counters of an established session. Every packet carries a sequence number,
+// used both as the AEAD nonce and for replay protection.
+pub struct Session {
+    send_cipher: ChaCha20Poly1305,
+    receive_cipher: ChaCha20Poly1305,
+    send_seq: u64,
+    replay: ReplayWindow,
+    pub encrypted: bool,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("send_seq", &self.send_seq)
+            .field("replay", &self.replay)
+            .field("encrypted", &self.encrypted)
+            .finish_non_exhaustive()
+    }
+}
+
+fn nonce(seq: u64) -> Nonce {
+    let mut nonce = [0; 12];
+    nonce[4..].copy_from_slice(&seq.to_be_bytes());
+    Nonce::from(nonce)
+}
+
+impl Session {
+    pub fn new(
+        send_cipher: ChaCha20Poly1305,
+        receive_cipher: ChaCha20Poly1305,
+        encrypted: bool,
+    ) -> Self {
+        Self {
+            send_cipher,
+            receive_cipher,
+            send_seq: 0,
+            replay: ReplayWindow::default(),
+            encrypted,
+        }
+    }
+
+    // Whether the other side already used the session
+    pub fn established(&self) -> bool {
+        self.replay.received_any()
+    }
+
+    pub fn seal(&mut self, payload: &[u8]) -> Vec<u8> {
+        self.send_seq += 1;
+
+        let kind = if self.encrypted {
+            PACKET_ENCRYPTED
+        } else {
+            PACKET_AUTHENTICATED
+        };
+
+        let mut packet = Vec::with_capacity(HEADER_SIZE + payload.len() + TAG_SIZE);
+        packet.push(kind);
+        packet.extend_from_slice(&self.send_seq.to_be_bytes());
+
+        if self.encrypted {
+            let ciphertext = self
+                .send_cipher
+                .encrypt(
+                    &nonce(self.send_seq),
+                    Payload {
+                        msg: payload,
+                        aad: &packet,
+                    },
+                )
+                .expect("ChaCha20Poly1305 encryption can't fail on a packet-sized buffer");
+            packet.extend_from_slice(&ciphertext);
+        } else {
+            // Authenticate only: the payload goes in the associated data and the tag is all we encrypt
+            packet.extend_from_slice(payload);
+            let tag = self
+                .send_cipher
+                .encrypt(
+                    &nonce(self.send_seq),
+                    Payload {
+                        msg: &[],
+                        aad: &packet,
+                    },
+                )
+                .expect("ChaCha20Poly1305 encryption can't fail on a packet-sized buffer");
+            packet.extend_from_slice(&tag);
+        }
+
+        packet
+    }
+
+    // Verify a packet and return its payload
+    pub fn open(&mut self, packet: &[u8]) -> Result<Vec<u8>, PacketError> {
+        if packet.len() < HEADER_SIZE + TAG_SIZE || !is_secure(packet) {
+            return Err(PacketError::Malformed);
+        }
+
+        let kind = packet[0];
+        if self.encrypted && kind != PACKET_ENCRYPTED {
+            return Err(PacketError::Downgrade);
+        }
+
+        let mut seq = [0; 8];
+        seq.copy_from_slice(&packet[1..HEADER_SIZE]);
+        let seq = u64::from_be_bytes(seq);
+
+        if !self.replay.check(seq) {
+            return Err(PacketError::Replayed);
+        }
+
+        let payload = if kind == PACKET_ENCRYPTED {
+            self.receive_cipher
+                .decrypt(
+                    &nonce(seq),
+                    Payload {
+                        msg: &packet[HEADER_SIZE..],
+                        aad: &packet[..HEADER_SIZE],
+                    },
+                )
+                .map_err(|_| PacketError::Forged)?
+        } else {
+            let (authenticated, tag) = packet.split_at(packet.len() - TAG_SIZE);
+            self.receive_cipher
+                .decrypt(
+                    &nonce(seq),
+                    Payload {
+                        msg: tag,
+                        aad: authenticated,
+                    },
+                )
+                .map_err(|_| PacketError::Forged)?;
+
+            authenticated[HEADER_SIZE..].to_vec()
+        };
+
+        self.replay.update(seq);
+
+        Ok(payload)
+    }
+}
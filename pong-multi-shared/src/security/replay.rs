@@ -0,0 +1,47 @@
+// Width of the window of recent sequence numbers remembered
+const WINDOW_SIZE: u64 = 64;
+
+// Sliding window over received sequence numbers, so a captured packet can't be
+// sent again while packets reordered by the network are still accepted
+#[derive(Debug, Default)]
+pub struct ReplayWindow {
+    highest: u64,
+
+    // Bit `n` is set when `highest - n` was received
+    bitmap: u64,
+}
+
+impl ReplayWindow {
+    pub fn received_any(&self) -> bool {
+        self.highest > 0
+    }
+
+    // Whether a packet with this sequence number can still be accepted
+    pub fn check(&self, seq: u64) -> bool {
+        if seq == 0 {
+            return false;
+        }
+        if seq > self.highest {
+            return true;
+        }
+
+        let offset = self.highest - seq;
+        offset < WINDOW_SIZE && self.bitmap & (1 << offset) == 0
+    }
+
+    // Mark a sequence number as received, only once the packet was authenticated
+    pub fn update(&mut self, seq: u64) {
+        if seq > self.highest {
+            let shift = seq - self.highest;
+            self.bitmap = if shift >= WINDOW_SIZE {
+                0
+            } else {
+                self.bitmap << shift
+            };
+            self.bitmap |= 1;
+            self.highest = seq;
+        } else {
+            self.bitmap |= 1 << (self.highest - seq);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use pong_multi_shared::game::{
+    ai::{landing_y, AiController, AiSettings, Difficulty},
+    ball::{Ball, BALL_SIZE},
+    formation::Seat,
+    state::GameState,
+    Side, FIELD_HEIGHT, TICK_RATE,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Plays a match of AIs for a while, returns the score
+fn play(left: AiSettings, right: AiSettings, seconds: u32) -> [u32; 2] {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut state = GameState::new();
+    let mut players = [
+        (Seat::new(Side::Left, 0), AiController::with_settings(left)),
+        (
+            Seat::new(Side::Right, 0),
+            AiController::with_settings(right),
+        ),
+    ];
+
+    for _ in 0..seconds * TICK_RATE {
+        for (seat, ai) in &mut players {
+            let direction = ai.direction(&state, *seat, &mut rng);
+            state.paddle_mut(*seat).apply_input(direction);
+        }
+        state.step();
+    }
+
+    state.score
+}
+
+#[test]
+fn difficulties_parse_back() {
+    for difficulty in Difficulty::ALL {
+        assert_eq!(difficulty.as_str().parse(), Ok(difficulty));
+    }
+    assert!("impossible".parse::<Difficulty>().is_err());
+}
+
+#[test]
+fn landing_follows_the_wall_bounces() {
+    let limit = FIELD_HEIGHT / 2.0 - BALL_SIZE / 2.0;
+    let ball = Ball {
+        x: 0.0,
+        y: 0.0,
+        vx: 100.0,
+        vy: 100.0,
+    };
+
+    assert_eq!(landing_y(&ball, 100.0), 100.0);
+
+    // Up past the top wall and back down by the same amount
+    let bounced = landing_y(&ball, limit + 50.0);
+    assert!((bounced - (limit - 50.0)).abs() < 0.01);
+}
+
+#[test]
+fn a_perfect_ai_never_misses() {
+    let perfect = AiSettings {
+        reaction_ticks: 0,
+        prediction_error: 0.0,
+        max_speed: 1.0,
+    };
+
+    assert_eq!(play(perfect, perfect, 60), [0, 0]);
+}
+
+#[test]
+fn hard_beats_easy() {
+    let [easy, hard] = play(
+        Difficulty::Easy.settings(),
+        Difficulty::Hard.settings(),
+        120,
+    );
+
+    assert!(hard > easy, "easy {easy} - hard {hard}");
+}
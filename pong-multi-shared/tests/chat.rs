@@ -0,0 +1,44 @@
+use pong_multi_shared::{
+    chat::{ChatContent, Emote, MAX_CHAT_LENGTH},
+    protocol::{Decoder, Encoder, WireFormat, EMBEDDED_JSON_ID},
+};
+use serde_json::json;
+
+#[test]
+fn emotes_parse_back() {
+    for emote in Emote::ALL {
+        assert_eq!(Emote::parse(emote.as_str()), Some(emote));
+    }
+    assert_eq!(Emote::parse("shrug"), None);
+}
+
+#[test]
+fn chat_text_is_trimmed_and_limited() {
+    let content = ChatContent::from_json(&json!({ "text": "  hello\u{7}  " }));
+    assert_eq!(content, Some(ChatContent::Text("hello".to_string())));
+
+    let longest = "a".repeat(MAX_CHAT_LENGTH);
+    assert!(ChatContent::from_json(&json!({ "text": longest })).is_some());
+
+    let too_long = "a".repeat(MAX_CHAT_LENGTH + 1);
+    assert_eq!(ChatContent::from_json(&json!({ "text": too_long })), None);
+    assert_eq!(ChatContent::from_json(&json!({ "text": "   " })), None);
+    assert_eq!(ChatContent::from_json(&json!({ "emote": "shrug" })), None);
+}
+
+#[test]
+fn chat_messages_round_trip_in_binary() {
+    let mut message = json!({ "action": "chat", "from": "SwiftWolf123", "side": "right" });
+    ChatContent::Emote(Emote::NiceShot).add_to(&mut message);
+
+    let payload = Encoder::new(WireFormat::Binary).encode(&message);
+    assert_ne!(payload[1], EMBEDDED_JSON_ID);
+
+    let decoded = Decoder::default().decode(&payload).unwrap();
+
+    assert_eq!(decoded, message);
+    assert_eq!(
+        ChatContent::from_json(&decoded),
+        Some(ChatContent::Emote(Emote::NiceShot))
+    );
+}
@@ -0,0 +1,96 @@
+use pong_multi_shared::game::{
+    ball::Ball,
+    formation::{Formation, GameMode, Seat, DEFAULT_DOUBLES_DEPTHS},
+    paddle::PADDLE_X,
+    state::GameState,
+    Side,
+};
+
+#[test]
+fn singles_keep_one_paddle_per_side() {
+    let state = GameState::new();
+
+    assert_eq!(state.formation.mode, GameMode::Singles);
+    assert_eq!(state.paddles.len(), 2);
+    assert_eq!(state.paddle(Seat::new(Side::Left, 0)).x, -PADDLE_X);
+    assert_eq!(state.paddle(Seat::new(Side::Right, 0)).x, PADDLE_X);
+}
+
+#[test]
+fn doubles_place_two_paddles_per_side_at_their_depths() {
+    let formation = Formation::doubles(DEFAULT_DOUBLES_DEPTHS);
+    let state = GameState::with_formation(formation.clone());
+
+    assert_eq!(state.paddles.len(), 4);
+    assert_eq!(
+        formation.seats().collect::<Vec<_>>(),
+        [
+            Seat::new(Side::Left, 0),
+            Seat::new(Side::Left, 1),
+            Seat::new(Side::Right, 0),
+            Seat::new(Side::Right, 1),
+        ]
+    );
+
+    for seat in formation.seats() {
+        let x = state.paddle(seat).x;
+        assert_eq!(x, seat.side.sign() * DEFAULT_DOUBLES_DEPTHS[seat.slot]);
+    }
+}
+
+#[test]
+fn depths_stay_on_their_own_half() {
+    let formation = Formation::doubles([PADDLE_X * 2.0, -10.0]);
+
+    assert_eq!(formation.depths[0], PADDLE_X);
+    assert!(formation.depths[1] > 0.0);
+}
+
+#[test]
+fn seat_keys_match_the_singles_names() {
+    for (seat, key) in [
+        (Seat::new(Side::Left, 0), "left"),
+        (Seat::new(Side::Right, 0), "right"),
+        (Seat::new(Side::Left, 1), "left_2"),
+        (Seat::new(Side::Right, 1), "right_2"),
+    ] {
+        assert_eq!(seat.key(), key);
+        assert_eq!(Seat::parse_key(key), Some(seat));
+    }
+
+    assert_eq!(Seat::parse_key("left_1"), None);
+    assert_eq!(Seat::parse_key("up"), None);
+}
+
+#[test]
+fn the_front_paddle_returns_the_ball() {
+    let mut state = GameState::with_formation(Formation::doubles(DEFAULT_DOUBLES_DEPTHS));
+    let front = Seat::new(Side::Left, 1);
+
+    state.ball = Ball {
+        x: state.paddle(front).x + 20.0,
+        y: 0.0,
+        vx: -300.0,
+        vy: 0.0,
+    };
+    state.advance();
+
+    assert!(state.ball.vx > 0.0);
+    assert!(state.ball.x > state.paddle(front).x);
+}
+
+#[test]
+fn the_own_front_paddle_lets_the_ball_through() {
+    let mut state = GameState::with_formation(Formation::doubles(DEFAULT_DOUBLES_DEPTHS));
+    let front = Seat::new(Side::Left, 1);
+
+    state.ball = Ball {
+        x: state.paddle(front).x - 20.0,
+        y: 0.0,
+        vx: 300.0,
+        vy: 0.0,
+    };
+    state.advance();
+
+    assert!(state.ball.vx > 0.0);
+}
@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant};
+
+use pong_multi_shared::fragment::{
+    reassembler::{Reassembler, MAX_PENDING_BYTES, REASSEMBLY_TIMEOUT},
+    Fragmenter, FRAGMENT_HEADER_SIZE, MAX_MESSAGE_SIZE, MAX_PACKET_SIZE,
+};
+
+fn message(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn small_packets_go_through_untouched() {
+    let mut fragmenter = Fragmenter::default();
+    let mut reassembler = Reassembler::default();
+
+    let packet = message(MAX_PACKET_SIZE);
+    let fragments = fragmenter.split(&packet);
+
+    assert_eq!(fragments, vec![packet.clone()]);
+    assert_eq!(
+        reassembler.receive(Instant::now(), packet.clone()),
+        Some(packet)
+    );
+}
+
+#[test]
+fn large_messages_come_back_in_any_order() {
+    let mut fragmenter = Fragmenter::default();
+    let mut reassembler = Reassembler::default();
+    let now = Instant::now();
+
+    let packet = message(10_000);
+    let mut fragments = fragmenter.split(&packet);
+    assert!(fragments.len() > 1);
+    assert!(fragments.iter().all(|f| f.len() <= MAX_PACKET_SIZE));
+
+    fragments.reverse();
+    let last = fragments.pop().unwrap();
+    for fragment in fragments.iter().chain(fragments.iter()) {
+        assert_eq!(reassembler.receive(now, fragment.clone()), None);
+    }
+
+    assert_eq!(reassembler.receive(now, last), Some(packet));
+    assert!(reassembler.is_empty());
+    assert_eq!(reassembler.pending_bytes(), 0);
+}
+
+#[test]
+fn oversized_messages_are_refused() {
+    let mut fragmenter = Fragmenter::default();
+
+    assert!(fragmenter.split(&message(MAX_MESSAGE_SIZE + 1)).is_empty());
+}
+
+#[test]
+fn incomplete_messages_time_out() {
+    let mut fragmenter = Fragmenter::default();
+    let mut reassembler = Reassembler::default();
+    let now = Instant::now();
+
+    let mut fragments = fragmenter.split(&message(5_000));
+    let last = fragments.pop().unwrap();
+    for fragment in fragments {
+        reassembler.receive(now, fragment);
+    }
+
+    // Only the late fragment is left, waiting for the others to come again
+    let later = now + REASSEMBLY_TIMEOUT + Duration::from_millis(1);
+    let late_bytes = last.len() - FRAGMENT_HEADER_SIZE;
+    assert_eq!(reassembler.receive(later, last), None);
+    assert_eq!(reassembler.pending_bytes(), late_bytes);
+}
+
+#[test]
+fn pending_bytes_stay_under_the_limit() {
+    let mut fragmenter = Fragmenter::default();
+    let mut reassembler = Reassembler::default();
+    let now = Instant::now();
+
+    // Never completed, every message is missing its last fragment
+    for _ in 0..20 {
+        let mut fragments = fragmenter.split(&message(MAX_MESSAGE_SIZE));
+        fragments.pop();
+        for fragment in fragments {
+            reassembler.receive(now, fragment);
+            assert!(reassembler.pending_bytes() <= MAX_PENDING_BYTES);
+        }
+    }
+}
+
+#[test]
+fn invalid_fragments_are_dropped() {
+    let mut reassembler = Reassembler::default();
+    let now = Instant::now();
+
+    // Index past the count, a count of one, and nothing after the header
+    assert_eq!(
+        reassembler.receive(now, vec![3, 0, 0, 0, 1, 0, 5, 0, 2, 42]),
+        None
+    );
+    assert_eq!(
+        reassembler.receive(now, vec![3, 0, 0, 0, 1, 0, 0, 0, 1, 42]),
+        None
+    );
+    assert_eq!(
+        reassembler.receive(now, vec![3, 0, 0, 0, 1, 0, 0, 0, 2]),
+        None
+    );
+    assert!(reassembler.is_empty());
+}
@@ -0,0 +1,114 @@
+use pong_multi_shared::game::{
+    ball::Ball,
+    map::{Map, PORTAL_RADIUS},
+    state::GameState,
+    Side, FIELD_HEIGHT, FIELD_WIDTH,
+};
+use serde_json::json;
+
+#[test]
+fn built_in_maps_load_and_round_trip() {
+    let maps = Map::built_in();
+
+    assert_eq!(maps[0], Map::classic());
+    for map in maps {
+        assert_eq!(
+            Map::from_json(&map.to_json()),
+            Ok(map.clone()),
+            "{}",
+            map.name
+        );
+    }
+}
+
+#[test]
+fn invalid_maps_are_refused() {
+    let invalid = [
+        json!({ "background": "background_blue" }),
+        json!({ "name": "dark", "background": "void" }),
+        json!({ "name": "tiny", "goal_size": 10 }),
+        json!({ "name": "far", "blocks": [{ "x": 5000, "y": 0, "width": 10, "height": 10 }] }),
+        json!({ "name": "flat", "blocks": [{ "x": 0, "y": 0, "width": 0, "height": 10 }] }),
+        json!({ "name": "odd", "portals": { "from": 1 } }),
+    ];
+
+    for map in invalid {
+        assert!(Map::from_json(&map).is_err(), "{map}");
+    }
+
+    // Everything but the name has a default
+    let minimal = Map::from_json(&json!({ "name": "empty" })).unwrap();
+    assert_eq!(minimal.goal_size, FIELD_HEIGHT);
+    assert_eq!(minimal.spawns, Map::classic().spawns);
+}
+
+#[test]
+fn blocks_bounce_the_ball() {
+    let map = Map::by_name("pillars").unwrap();
+    let block = map.blocks[0];
+
+    let mut ball = Ball {
+        x: block.x + block.width / 2.0 + 10.0,
+        y: block.y,
+        vx: -300.0,
+        vy: 0.0,
+    };
+    map.collide(&mut ball, 0);
+
+    assert!(ball.vx > 0.0);
+}
+
+#[test]
+fn portals_move_the_ball() {
+    let map = Map::by_name("wormholes").unwrap();
+    let portal = map.portals[0];
+
+    let mut ball = Ball {
+        x: portal.from.x + PORTAL_RADIUS / 2.0,
+        y: portal.from.y,
+        vx: 300.0,
+        vy: 0.0,
+    };
+    map.collide(&mut ball, 0);
+
+    assert_eq!((ball.x, ball.y), (portal.to.x, portal.to.y));
+    assert_eq!(ball.vx, 300.0);
+}
+
+#[test]
+fn small_goals_have_walls_around_them() {
+    let map = Map::by_name("windmills").unwrap();
+
+    let mut wide = Ball {
+        x: FIELD_WIDTH / 2.0 - 10.0,
+        y: map.goal_size / 2.0 + 40.0,
+        vx: 300.0,
+        vy: 0.0,
+    };
+    map.collide(&mut wide, 0);
+    assert!(wide.vx < 0.0);
+
+    let mut on_target = Ball { y: 0.0, ..wide };
+    on_target.vx = 300.0;
+    map.collide(&mut on_target, 0);
+    assert!(on_target.vx > 0.0);
+}
+
+#[test]
+fn the_ball_is_served_from_the_spawn_points_in_turns() {
+    let map = Map::by_name("windmills").unwrap();
+    let mut state = GameState::new();
+    state.set_map(map.clone());
+
+    assert_eq!(
+        (state.ball.x, state.ball.y),
+        (map.spawns[0].x, map.spawns[0].y)
+    );
+
+    state.ball.x = FIELD_WIDTH;
+    assert_eq!(state.score_point(), Some(Side::Left));
+    assert_eq!(
+        (state.ball.x, state.ball.y),
+        (map.spawns[1].x, map.spawns[1].y)
+    );
+}
@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+use pong_multi_shared::netsim::{conditioner::Conditioner, NetworkConditions};
+
+#[test]
+fn conditions_parse_presets_and_settings() {
+    let conditions: NetworkConditions = "mobile loss=0 latency=50".parse().unwrap();
+    assert_eq!(conditions.latency, 50.0);
+    assert_eq!(conditions.loss, 0.0);
+    assert_eq!(conditions.jitter, 30.0);
+
+    assert!("off".parse::<NetworkConditions>().unwrap().is_perfect());
+    assert!("loss=2".parse::<NetworkConditions>().is_err());
+    assert!("speed=10".parse::<NetworkConditions>().is_err());
+    assert!("dialup".parse::<NetworkConditions>().is_err());
+}
+
+#[test]
+fn conditions_display_round_trips() {
+    let conditions = NetworkConditions::preset("terrible").unwrap();
+    let parsed: NetworkConditions = conditions.to_string().parse().unwrap();
+
+    assert_eq!(parsed, conditions);
+}
+
+#[test]
+fn packets_are_held_for_the_latency() {
+    let mut conditioner = Conditioner::new("latency=50".parse().unwrap());
+    let now = Instant::now();
+
+    for i in 0..10 {
+        conditioner.push(now, i, 100);
+    }
+
+    assert!(conditioner
+        .pop_ready(now + Duration::from_millis(49))
+        .is_empty());
+    assert_eq!(
+        conditioner.pop_ready(now + Duration::from_millis(50)),
+        (0..10).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn jitter_alone_keeps_the_order() {
+    let mut conditioner = Conditioner::new("latency=20 jitter=20".parse().unwrap());
+    let now = Instant::now();
+
+    for i in 0..100 {
+        conditioner.push(now + Duration::from_millis(i), i, 100);
+    }
+
+    let received = conditioner.pop_ready(now + Duration::from_secs(1));
+    assert_eq!(received, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn lost_packets_never_come_out() {
+    let mut conditioner = Conditioner::new("loss=1".parse().unwrap());
+    let now = Instant::now();
+
+    conditioner.push(now, 1, 100);
+
+    assert!(conditioner.is_empty());
+}
+
+#[test]
+fn bandwidth_spreads_packets_out() {
+    // 80 kilobits per second sends 1000 bytes in 100 ms
+    let mut conditioner = Conditioner::new("bandwidth=80".parse().unwrap());
+    let now = Instant::now();
+
+    conditioner.push(now, 1, 1000);
+    conditioner.push(now, 2, 1000);
+
+    assert!(conditioner
+        .pop_ready(now + Duration::from_millis(99))
+        .is_empty());
+    assert_eq!(
+        conditioner.pop_ready(now + Duration::from_millis(101)),
+        vec![1]
+    );
+    assert_eq!(
+        conditioner.pop_ready(now + Duration::from_millis(201)),
+        vec![2]
+    );
+}
+
+#[test]
+fn duplicates_come_out_twice() {
+    let mut conditioner = Conditioner::new("duplicate=1".parse().unwrap());
+    let now = Instant::now();
+
+    conditioner.push(now, 1, 100);
+
+    assert_eq!(
+        conditioner.pop_ready(now + Duration::from_secs(1)),
+        vec![1, 1]
+    );
+}
@@ -0,0 +1,108 @@
+use pong_multi_shared::game::{
+    ball::Ball,
+    formation::Seat,
+    power_up::{
+        Lock, PowerUp, PowerUpKind, SpawnTable, KEY_TICKS, LASER_STUN_TICKS, LOCKED_BLOCK_Y,
+    },
+    state::GameState,
+    Side, FIELD_WIDTH, TICK_RATE,
+};
+
+fn ball(x: f32, y: f32, vx: f32) -> Ball {
+    Ball { x, y, vx, vy: 0.0 }
+}
+
+// A ball hit by the left player, about to run into a power-up in front of it
+fn collecting(kind: PowerUpKind) -> GameState {
+    let mut state = GameState::new();
+    state.ball = ball(-10.0, 0.0, 300.0);
+    state.last_hitter = Some(Seat::new(Side::Left, 0));
+    state.power_up = Some(PowerUp {
+        kind,
+        x: 20.0,
+        y: 0.0,
+    });
+    state
+}
+
+#[test]
+fn the_last_hitter_collects_the_laser() {
+    let mut state = collecting(PowerUpKind::Laser);
+
+    state.step();
+
+    assert!(state.power_up.is_none());
+    assert!(state.is_stunned(Side::Right));
+    assert!(!state.is_stunned(Side::Left));
+
+    for _ in 0..LASER_STUN_TICKS {
+        state.step();
+    }
+    assert!(!state.is_stunned(Side::Right));
+}
+
+#[test]
+fn a_served_ball_goes_through_power_ups() {
+    let mut state = collecting(PowerUpKind::Laser);
+    state.last_hitter = None;
+
+    state.step();
+
+    assert!(state.power_up.is_some());
+    assert!(!state.is_stunned(Side::Right));
+}
+
+#[test]
+fn the_star_ball_scores_without_a_serve() {
+    let mut state = collecting(PowerUpKind::Star);
+    state.step();
+
+    let extra = state.extra_ball.expect("no extra ball");
+    assert_eq!(extra.ball.vy, -state.ball.vy);
+
+    state.extra_ball.as_mut().unwrap().ball = ball(FIELD_WIDTH / 2.0 + 40.0, 0.0, 300.0);
+    let main = state.ball;
+
+    assert_eq!(state.score_point(), Some(Side::Left));
+    assert_eq!(state.score, [1, 0]);
+    assert!(state.extra_ball.is_none());
+    assert_eq!(state.ball, main);
+}
+
+#[test]
+fn keys_open_the_locked_blocks() {
+    let mut state = collecting(PowerUpKind::Key);
+    state.lock = Some(Lock::default());
+
+    // Locked, the ball bounces off the block
+    let mut blocked = state.clone();
+    blocked.power_up = None;
+    blocked.ball = ball(-30.0, LOCKED_BLOCK_Y, 300.0);
+    blocked.step();
+    assert!(blocked.ball.vx < 0.0);
+
+    state.step();
+    assert_eq!(state.lock.unwrap().open_ticks, KEY_TICKS);
+
+    state.ball = ball(-30.0, LOCKED_BLOCK_Y, 300.0);
+    state.step();
+    assert!(state.ball.vx > 0.0);
+}
+
+#[test]
+fn spawn_tables_are_weighted() {
+    let table: SpawnTable = "every=5,laser=2,key=1".parse().unwrap();
+
+    assert_eq!(table.interval_ticks, 5 * TICK_RATE as u64);
+    assert_eq!(table.total_weight(), 3);
+    assert_eq!(table.pick(0), Some(PowerUpKind::Laser));
+    assert_eq!(table.pick(1), Some(PowerUpKind::Laser));
+    assert_eq!(table.pick(2), Some(PowerUpKind::Key));
+    assert_eq!(table.pick(3), None);
+    assert!(table.has_locks());
+
+    assert_eq!(table.to_string().parse::<SpawnTable>(), Ok(table));
+    assert!("off".parse::<SpawnTable>().unwrap().is_off());
+    assert!("laser=1".parse::<SpawnTable>().is_err());
+    assert!("every=5,rocket=1".parse::<SpawnTable>().is_err());
+}
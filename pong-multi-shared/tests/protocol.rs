@@ -0,0 +1,331 @@
+use pong_multi_shared::protocol::{
+    decode, encode, is_binary, negotiate, schema, Decoder, Encoder, VersionMismatch, WireError,
+    WireFormat, BINARY_FLAG, EMBEDDED_JSON_ID, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, SNAPSHOT_ID,
+};
+use serde_json::{json, Value};
+
+fn snapshot(tick: u64, ball_x: f64, ack: u64) -> Value {
+    json!({
+        "action": "snapshot",
+        "tick": tick,
+        "time": 1_700_000_000_000.25 + tick as f64 * 1000.0 / 60.0,
+        "ack": ack,
+        "ball": { "x": ball_x, "y": -12.5, "vx": 300.0, "vy": -150.0 },
+        "paddles": { "left": 40.125, "right": -80.0 },
+        "score": { "left": 2, "right": 0 },
+    })
+}
+
+#[test]
+fn known_messages_round_trip() {
+    let messages = [
+        json!({
+            "action": "entered",
+            "player_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "protocol": PROTOCOL_VERSION,
+        }),
+        json!({ "action": "handshake", "public_key": "ab".repeat(32), "encrypt": true }),
+        json!({
+            "action": "move",
+            "room_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "seq": 70_000,
+            "direction": -1,
+        }),
+        json!({ "action": "pong", "client_time": 42.5, "server_receive": 1e12, "server_send": 1e12 + 0.5 }),
+        json!({
+            "action": "match_over",
+            "winner": "right",
+            "reason": "forfeit",
+            "score": { "left": 1, "right": 3 },
+        }),
+    ];
+
+    for message in messages {
+        let payload = encode(&message, WireFormat::Binary);
+
+        assert!(is_binary(&payload));
+        assert_ne!(payload[1], EMBEDDED_JSON_ID, "{message}");
+        assert!(payload.len() < message.to_string().len());
+        assert_eq!(decode(&payload).unwrap(), message);
+    }
+}
+
+#[test]
+fn schema_fields_are_added_at_the_end() {
+    for schema in schema::MESSAGES {
+        let versions: Vec<u8> = schema.fields.iter().map(|(_, _, since)| *since).collect();
+        assert!(
+            versions.is_sorted(),
+            "{} has a field added in the middle",
+            schema.action
+        );
+    }
+}
+
+#[test]
+fn other_messages_are_embedded_as_json() {
+    let messages = [
+        json!({ "action": "wave", "text": "hi" }),
+        json!({ "action": "move", "seq": 1, "direction": 1, "extra": true }),
+        json!({ "action": "entered", "player_id": "not a uuid" }),
+    ];
+
+    for message in messages {
+        let payload = encode(&message, WireFormat::Binary);
+
+        assert_eq!(payload[1], EMBEDDED_JSON_ID, "{message}");
+        assert_eq!(decode(&payload).unwrap(), message);
+    }
+}
+
+#[test]
+fn json_format_is_plain_text() {
+    let message = json!({ "action": "ping", "client_time": 1.5 });
+    let payload = encode(&message, WireFormat::Json);
+
+    assert!(!is_binary(&payload));
+    assert_eq!(payload, message.to_string().into_bytes());
+    assert_eq!(decode(&payload).unwrap(), message);
+}
+
+#[test]
+fn enter_is_always_text() {
+    let message = json!({ "action": "enter", "protocol": PROTOCOL_VERSION, "build": "1.2.3" });
+    let payload = encode(&message, WireFormat::Binary);
+
+    assert!(!is_binary(&payload));
+    assert_eq!(decode(&payload).unwrap(), message);
+}
+
+#[test]
+fn downgraded_encoders_embed_newer_fields_as_json() {
+    let message = json!({
+        "action": "entered",
+        "player_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        "protocol": 1,
+    });
+
+    let mut encoder = Encoder::new(WireFormat::Binary);
+    encoder.version = 1;
+    let payload = encoder.encode(&message);
+
+    assert_eq!(payload[0], BINARY_FLAG | 1);
+    assert_eq!(payload[1], EMBEDDED_JSON_ID);
+    assert_eq!(decode(&payload).unwrap(), message);
+
+    // Without the newer field it still gets the compact encoding
+    let old = json!({ "action": "entered", "player_id": "67e55044-10b1-426f-9247-bb680e5fe0c8" });
+    let payload = encoder.encode(&old);
+    assert_ne!(payload[1], EMBEDDED_JSON_ID);
+    assert_eq!(decode(&payload).unwrap(), old);
+}
+
+#[test]
+fn versions_are_negotiated_down_to_the_common_one() {
+    let server = MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION;
+
+    assert_eq!(
+        negotiate(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION, server.clone()),
+        Ok(PROTOCOL_VERSION)
+    );
+    assert_eq!(negotiate(1..=1, server.clone()), Ok(1));
+    assert_eq!(
+        negotiate(1..=PROTOCOL_VERSION + 1, server.clone()),
+        Ok(PROTOCOL_VERSION)
+    );
+
+    assert_eq!(
+        negotiate(0..=0, server.clone()),
+        Err(VersionMismatch::ClientTooOld)
+    );
+    assert_eq!(
+        negotiate(PROTOCOL_VERSION + 1..=PROTOCOL_VERSION + 1, server),
+        Err(VersionMismatch::ServerTooOld)
+    );
+}
+
+#[test]
+fn snapshots_are_quantized() {
+    let mut encoder = Encoder::new(WireFormat::Binary);
+    let mut decoder = Decoder::default();
+
+    let sent = snapshot(3, 100.0 + 1.0 / 3.0, 7);
+    let received = decoder.decode(&encoder.encode(&sent)).unwrap();
+
+    assert_eq!(received["tick"], 3);
+    assert_eq!(received["ack"], 7);
+    assert_eq!(received["score"], sent["score"]);
+    assert_eq!(received["paddles"], sent["paddles"]);
+
+    let x = received["ball"]["x"].as_f64().unwrap();
+    assert!((x - sent["ball"]["x"].as_f64().unwrap()).abs() <= 1.0 / 16.0);
+    let time = received["time"].as_f64().unwrap();
+    assert!((time - sent["time"].as_f64().unwrap()).abs() <= 0.005);
+}
+
+#[test]
+fn acknowledged_snapshots_become_the_baseline() {
+    let mut encoder = Encoder::new(WireFormat::Binary);
+    let mut decoder = Decoder::default();
+
+    let full = encoder.encode(&snapshot(3, 10.0, 1));
+    decoder.decode(&full).unwrap();
+
+    // Nothing acknowledged yet, still sent in full
+    let unacked = encoder.encode(&snapshot(6, 15.0, 1));
+    assert_eq!(unacked.len(), full.len());
+    decoder.decode(&unacked).unwrap();
+
+    encoder.acknowledge(6);
+    let delta = encoder.encode(&snapshot(9, 20.0, 1));
+    assert!(delta.len() < full.len() / 2);
+
+    let received = decoder.decode(&delta).unwrap();
+    assert_eq!(received["tick"], 9);
+    assert_eq!(received["ball"]["x"], 20.0);
+    assert_eq!(received["paddles"]["left"], 40.125);
+}
+
+#[test]
+fn deltas_need_their_baseline() {
+    let mut encoder = Encoder::new(WireFormat::Binary);
+
+    encoder.encode(&snapshot(3, 10.0, 1));
+    encoder.acknowledge(3);
+    let delta = encoder.encode(&snapshot(6, 15.0, 1));
+
+    let mut decoder = Decoder::default();
+    assert_eq!(decoder.decode(&delta), Err(WireError::MissingBaseline(3)));
+}
+
+#[test]
+fn a_new_match_starts_over_from_full_snapshots() {
+    let mut encoder = Encoder::new(WireFormat::Binary);
+
+    let full = encoder.encode(&snapshot(3, 10.0, 1));
+    encoder.acknowledge(3);
+    encoder.encode(&json!({
+        "action": "match_found",
+        "room_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        "side": "left",
+    }));
+
+    // Acks from the last room don't count anymore
+    encoder.acknowledge(3);
+    assert_eq!(encoder.encode(&snapshot(6, 10.0, 1)).len(), full.len());
+}
+
+#[test]
+fn spectating_starts_over_in_the_watched_room() {
+    let mut encoder = Encoder::new(WireFormat::Binary);
+    let mut decoder = Decoder::default();
+
+    // Played a match that went on for a while
+    decoder
+        .decode(&encoder.encode(&snapshot(300, 10.0, 1)))
+        .unwrap();
+    encoder.acknowledge(300);
+
+    let spectating = json!({
+        "action": "spectating",
+        "room_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        "mode": "singles",
+    });
+    decoder.decode(&encoder.encode(&spectating)).unwrap();
+
+    // The watched room is only a few ticks in, its acks count
+    let full = encoder.encode(&snapshot(3, 50.0, 1));
+    decoder.decode(&full).unwrap();
+    encoder.acknowledge(3);
+
+    let delta = encoder.encode(&snapshot(6, 55.0, 1));
+    assert!(delta.len() < full.len() / 2);
+
+    let received = decoder.decode(&delta).unwrap();
+    assert_eq!(received["tick"], 6);
+    assert_eq!(received["ball"]["x"], 55.0);
+    assert_eq!(received["paddles"]["left"], 40.125);
+}
+
+#[test]
+fn doubles_paddles_come_and_go_with_the_match() {
+    let mut encoder = Encoder::new(WireFormat::Binary);
+    let mut decoder = Decoder::default();
+
+    let mut doubles = snapshot(3, 10.0, 1);
+    doubles["paddles"]["left_2"] = json!(-20.5);
+    doubles["paddles"]["right_2"] = json!(64.0);
+
+    let payload = encoder.encode(&doubles);
+    assert_eq!(payload[1], SNAPSHOT_ID);
+    assert_eq!(decoder.decode(&payload).unwrap(), doubles);
+
+    // Back to singles against a doubles baseline, the extra paddles go away
+    encoder.acknowledge(3);
+    let singles = snapshot(6, 15.0, 1);
+    let payload = encoder.encode(&singles);
+    assert_eq!(payload[1], SNAPSHOT_ID);
+    assert_eq!(decoder.decode(&payload).unwrap(), singles);
+}
+
+#[test]
+fn older_versions_get_doubles_snapshots_as_json() {
+    let mut encoder = Encoder::new(WireFormat::Binary);
+    encoder.version = 2;
+
+    let mut doubles = snapshot(3, 10.0, 1);
+    doubles["paddles"]["left_2"] = json!(-20.5);
+    let payload = encoder.encode(&doubles);
+    assert_eq!(payload[1], EMBEDDED_JSON_ID);
+    assert_eq!(decode(&payload).unwrap(), doubles);
+
+    // Singles still use the snapshot encoding of that version
+    let payload = encoder.encode(&snapshot(6, 10.0, 1));
+    assert_eq!(payload[0], BINARY_FLAG | 2);
+    assert_eq!(payload[1], SNAPSHOT_ID);
+}
+
+#[test]
+fn power_ups_ride_along_in_snapshots() {
+    let mut encoder = Encoder::new(WireFormat::Binary);
+    let mut decoder = Decoder::default();
+
+    let mut with_power_ups = snapshot(3, 10.0, 1);
+    with_power_ups["extra_ball"] = json!({ "x": 5.0, "y": 2.5, "vx": -300.0, "vy": 150.0 });
+    with_power_ups["power_up"] = json!({ "kind": 2, "x": 100.0, "y": -64.0 });
+    with_power_ups["stunned"] = json!({ "right": 42 });
+    with_power_ups["lock"] = json!({ "open": 0 });
+
+    let payload = encoder.encode(&with_power_ups);
+    assert_eq!(payload[1], SNAPSHOT_ID);
+    assert_eq!(decoder.decode(&payload).unwrap(), with_power_ups);
+
+    // Versions before power-ups still get everything, as JSON
+    encoder.version = 3;
+    let payload = encoder.encode(&with_power_ups);
+    assert_eq!(payload[1], EMBEDDED_JSON_ID);
+}
+
+#[test]
+fn other_versions_are_refused() {
+    let mut payload = encode(&json!({ "action": "join" }), WireFormat::Binary);
+    payload[0] = BINARY_FLAG | (PROTOCOL_VERSION + 1);
+
+    assert_eq!(
+        decode(&payload),
+        Err(WireError::UnsupportedVersion(PROTOCOL_VERSION + 1))
+    );
+}
+
+#[test]
+fn truncated_messages_are_refused() {
+    let payload = encode(
+        &json!({ "action": "ping", "client_time": 12.0 }),
+        WireFormat::Binary,
+    );
+
+    assert_eq!(
+        decode(&payload[..payload.len() - 1]),
+        Err(WireError::Truncated)
+    );
+}